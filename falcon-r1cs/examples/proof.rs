@@ -1,15 +1,18 @@
-use ark_bn254::Bn254;
-use ark_groth16::{create_random_proof, verify_proof, Groth16, PreparedVerifyingKey};
-use ark_snark::SNARK;
+use ark_groth16::{create_random_proof, verify_proof, PreparedVerifyingKey};
 use ark_std::rand::SeedableRng;
-use falcon_r1cs::{hash_from_pk, hash_from_poly, FalconVerificationCircuit};
-use falcon_rust::{KeyPair, NTTPolynomial, Polynomial};
+use falcon_r1cs::{
+  contribute, contribute_powers_of_tau, hash_from_pk, hash_from_poly, initial_proving_key, verify_powers_of_tau,
+  verify_transcript, FalconVerificationCircuit, LocalCoordinator, PowersOfTau, PowersOfTauTranscript,
+};
+use falcon_rust::{KeyPair512, NTTPolynomial, Polynomial, Polynomial512};
 use rand_chacha::ChaCha20Rng;
 
 fn main() {
-  let mut rng = ChaCha20Rng::from_seed([0; 32]);
+  let mut seed = [0u8; 32];
+  getrandom::getrandom(&mut seed).unwrap();
+  let mut rng = ChaCha20Rng::from_seed(seed);
 
-  let keypair = KeyPair::keygen();
+  let keypair = KeyPair512::keygen_from_seed(&[0; 48]);
 
   let msg = "Testing message";
   let sig = keypair
@@ -23,17 +26,34 @@ fn main() {
     sig
   );
 
-  let (pp, vk) = Groth16::<Bn254>::circuit_specific_setup(cs_input.clone(), &mut rng).unwrap();
+  // phase 1 with a single participant, large enough for Falcon-512
+  let mut powers = PowersOfTau::new(1 << 20);
+  let mut powers_transcript = PowersOfTauTranscript::new(&powers);
+  contribute_powers_of_tau(&mut powers, &mut powers_transcript, &mut rng);
+  verify_powers_of_tau(&powers, &powers_transcript, &mut rng).unwrap();
+
+  // phase-2 ceremony with a single participant over the initial key
+  let initial = initial_proving_key(cs_input.clone(), &powers).unwrap();
+  let mut coordinator = LocalCoordinator::new(initial.clone());
+  let mut pp = coordinator.proving_key().clone();
+  let mut transcript = coordinator.transcript().clone();
+  contribute(&mut pp, &mut transcript, &mut rng);
+  coordinator.submit(pp, transcript, &mut rng).unwrap();
+  let (pp, transcript) = coordinator.finish();
+  verify_transcript(&initial, &pp, &transcript, &mut rng).unwrap();
+  let vk = pp.vk.clone();
+
   let proof = create_random_proof(cs_input, &pp, &mut rng).unwrap();
   let pk = Polynomial::from(&(keypair.public_key));
   let pk_ntt = NTTPolynomial::from(&pk);
-  let hm = Polynomial::from_hash_of_message(msg.as_ref(), sig.nonce());
+  let hm = Polynomial512::from_hash_of_message(msg.as_ref(), sig.nonce());
   let hm_ntt = NTTPolynomial::from(&hm);
   
-  let mut pub_input = Vec::new();
-  pub_input.push(hash_from_poly(&pk_ntt).unwrap());
-  pub_input.push(hash_from_poly(&hm_ntt).unwrap());
-  pub_input.push(hash_from_pk(&pk).unwrap());
+  let pub_input = vec![
+    hash_from_poly(&pk_ntt).unwrap(),
+    hash_from_poly(&hm_ntt).unwrap(),
+    hash_from_pk(&pk).unwrap(),
+  ];
 
   let pvk = PreparedVerifyingKey::from(vk.clone());
 
@@ -0,0 +1,427 @@
+/*
+  Phase 2 of the BGM17 setup. The initial key derives from multi-party
+  powers of tau, see `initial_proving_key`: one honest participant across
+  both phases is enough for the final key to be sound.
+*/
+use ark_bn254::{Bn254, Fr, G1Affine, G1Projective, G2Affine, G2Projective};
+use ark_ec::{msm::VariableBaseMSM, AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::{Field, PrimeField, UniformRand, Zero};
+use ark_groth16::ProvingKey;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use ark_std::rand::{CryptoRng, Rng};
+use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+use std::fmt;
+use tiny_keccak::{Hasher, Keccak};
+
+/**
+ * Errors reported by the verification of a phase-2 ceremony
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CeremonyError {
+  /// The transcript does not start from the initial proving key, or the
+  /// initial key does not derive from the powers of tau
+  InitialKey,
+  /// An element other than delta and the H and L queries was modified
+  KeyMismatch,
+  /// The proof of knowledge of a contribution is invalid, or its delta does
+  /// not follow the previous one
+  InvalidContribution(usize),
+  /// Delta or the H and L queries of the key do not match the contributions
+  InvalidUpdate,
+  /// A submitted transcript does not extend the current one by a single
+  /// contribution
+  UnexpectedTranscript,
+  /// The powers of tau are not successive powers of a single tau
+  InvalidPowers,
+}
+
+impl fmt::Display for CeremonyError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::InitialKey => write!(f, "the transcript does not start from the initial proving key"),
+      Self::KeyMismatch => write!(f, "the proving key was modified beyond delta and the H and L queries"),
+      Self::InvalidContribution(i) => write!(f, "invalid contribution: {}", i),
+      Self::InvalidUpdate => write!(f, "the proving key does not match the contributions"),
+      Self::UnexpectedTranscript => write!(f, "the transcript does not extend the current one by one contribution"),
+      Self::InvalidPowers => write!(f, "the powers of tau are inconsistent"),
+    }
+  }
+}
+
+impl std::error::Error for CeremonyError {}
+
+/**
+ * Proof of knowledge of the secret x of a contribution: `(s, s * x)` and
+ * `r * x`, with `r = hash_to_g2(transcript hash, s, s * x)`
+ */
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct KnowledgeProof {
+  pub s: G1Affine,
+  pub s_x: G1Affine,
+  pub r_x: G2Affine,
+}
+
+impl KnowledgeProof {
+  pub(crate) fn new<R: Rng + CryptoRng>(hash: &[u8; 32], x: Fr, rng: &mut R) -> Self {
+    let s = G1Projective::rand(rng).into_affine();
+    let s_x = s.mul(x).into_affine();
+    let r = hash_to_g2(hash, &s, &s_x);
+    Self { s, s_x, r_x: r.mul(x).into_affine() }
+  }
+
+  /*
+    The prover knows x, and `after = before * x`
+  */
+  pub(crate) fn verify(&self, hash: &[u8; 32], before: G1Affine, after: G1Affine) -> bool {
+    let r = hash_to_g2(hash, &self.s, &self.s_x);
+    !self.s.is_zero()
+      && !self.s_x.is_zero()
+      && same_ratio((self.s, self.s_x), (r, self.r_x))
+      && same_ratio((before, after), (r, self.r_x))
+  }
+}
+
+/**
+ * Phase-2 contribution to a Groth16 proving key: the secret d of the
+ * participant multiplies delta and divides the H and L queries
+ */
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Contribution {
+  /// delta in G1 after the contribution
+  pub delta_g1: G1Affine,
+  /// knowledge of d
+  pub proof: KnowledgeProof,
+}
+
+/**
+ * Contributions to a proving key, in order. Every contribution is bound to
+ * the hash of the initial key and of the previous contributions.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Transcript {
+  /// keccak256 of the initial proving key, uncompressed
+  pub initial: [u8; 32],
+  pub contributions: Vec<Contribution>,
+}
+
+impl Transcript {
+  pub fn new(initial: &ProvingKey<Bn254>) -> Self {
+    Self { initial: key_hash(initial), contributions: vec![] }
+  }
+
+  /**
+   * Hash chained over the contributions, starting from `initial`.
+   * Participants check that their contribution hash is in the chain.
+   */
+  pub fn hash(&self) -> [u8; 32] {
+    self.contributions.iter().fold(self.initial, |h, c| next_hash(&h, c))
+  }
+
+  pub fn len(&self) -> usize {
+    self.contributions.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.contributions.is_empty()
+  }
+}
+
+/*
+  The derive does not cover arrays: the initial hash is written as is,
+  followed by the contributions
+*/
+impl CanonicalSerialize for Transcript {
+  fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+    writer.write_all(&self.initial)?;
+    self.contributions.serialize(writer)
+  }
+
+  fn serialized_size(&self) -> usize {
+    self.initial.len() + self.contributions.serialized_size()
+  }
+}
+
+impl CanonicalDeserialize for Transcript {
+  fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+    let mut initial = [0u8; 32];
+    reader.read_exact(&mut initial)?;
+    Ok(Self { initial, contributions: Vec::deserialize(reader)? })
+  }
+}
+
+pub(crate) fn keccak256(chunks: &[&[u8]]) -> [u8; 32] {
+  let mut res = [0u8; 32];
+  let mut keccak = Keccak::v256();
+  for chunk in chunks {
+    keccak.update(chunk);
+  }
+  keccak.finalize(&mut res);
+  res
+}
+
+pub(crate) fn to_bytes(x: &impl CanonicalSerialize) -> Vec<u8> {
+  let mut res = vec![];
+  x.serialize_uncompressed(&mut res).unwrap();
+  res
+}
+
+fn key_hash(pk: &ProvingKey<Bn254>) -> [u8; 32] {
+  keccak256(&[&to_bytes(pk)])
+}
+
+pub(crate) fn next_hash(hash: &[u8; 32], contribution: &impl CanonicalSerialize) -> [u8; 32] {
+  keccak256(&[hash, &to_bytes(contribution)])
+}
+
+/*
+  r has no known discrete logarithm: it is sampled by try-and-increment
+  from a seed fixed by the transcript and the contribution
+*/
+fn hash_to_g2(hash: &[u8; 32], s: &G1Affine, s_delta: &G1Affine) -> G2Affine {
+  let seed = keccak256(&[hash, &to_bytes(s), &to_bytes(s_delta)]);
+  G2Projective::rand(&mut ChaCha20Rng::from_seed(seed)).into_affine()
+}
+
+pub(crate) fn same_ratio(a: (G1Affine, G1Affine), b: (G2Affine, G2Affine)) -> bool {
+  Bn254::pairing(a.0, b.1) == Bn254::pairing(a.1, b.0)
+}
+
+pub(crate) fn random_nonzero<R: Rng + CryptoRng>(rng: &mut R) -> Fr {
+  loop {
+    let x = Fr::rand(rng);
+    if !x.is_zero() {
+      return x;
+    }
+  }
+}
+
+fn scale(points: &mut Vec<G1Affine>, x: Fr) {
+  let res: Vec<G1Projective> = points.iter().map(|p| p.mul(x)).collect();
+  *points = G1Projective::batch_normalization_into_affine(&res);
+}
+
+/**
+ * Contributes fresh randomness from `rng` to `pk`, in place, and appends the
+ * contribution to `transcript`. Returns the new transcript hash.
+ * Only delta is re-randomized: the other elements of `pk` come from the
+ * powers of tau of phase 1.
+ */
+pub fn contribute<R: Rng + CryptoRng>(pk: &mut ProvingKey<Bn254>, transcript: &mut Transcript, rng: &mut R) -> [u8; 32] {
+  let d = random_nonzero(rng);
+  let proof = KnowledgeProof::new(&transcript.hash(), d, rng);
+
+  pk.delta_g1 = pk.delta_g1.mul(d).into_affine();
+  pk.vk.delta_g2 = pk.vk.delta_g2.mul(d).into_affine();
+  let d_inv = d.inverse().unwrap();
+  scale(&mut pk.h_query, d_inv);
+  scale(&mut pk.l_query, d_inv);
+
+  transcript.contributions.push(Contribution { delta_g1: pk.delta_g1, proof });
+  transcript.hash()
+}
+
+/*
+  Checks that `after` is `before` updated by `contributions`, chained from
+  `hash`. The H and L queries are compared through a random linear
+  combination: sum(x_i * after_i) * delta_after = sum(x_i * before_i) * delta_before.
+*/
+fn verify_contributions<R: Rng + CryptoRng>(
+  before: &ProvingKey<Bn254>,
+  after: &ProvingKey<Bn254>,
+  hash: [u8; 32],
+  contributions: &[Contribution],
+  offset: usize,
+  rng: &mut R,
+) -> Result<[u8; 32], CeremonyError> {
+  let unchanged = before.vk.alpha_g1 == after.vk.alpha_g1
+    && before.vk.beta_g2 == after.vk.beta_g2
+    && before.vk.gamma_g2 == after.vk.gamma_g2
+    && before.vk.gamma_abc_g1 == after.vk.gamma_abc_g1
+    && before.beta_g1 == after.beta_g1
+    && before.a_query == after.a_query
+    && before.b_g1_query == after.b_g1_query
+    && before.b_g2_query == after.b_g2_query
+    && before.h_query.len() == after.h_query.len()
+    && before.l_query.len() == after.l_query.len();
+  if !unchanged {
+    return Err(CeremonyError::KeyMismatch);
+  }
+
+  let mut hash = hash;
+  let mut delta_g1 = before.delta_g1;
+  for (i, c) in contributions.iter().enumerate() {
+    if !c.proof.verify(&hash, delta_g1, c.delta_g1) {
+      return Err(CeremonyError::InvalidContribution(offset + i));
+    }
+    hash = next_hash(&hash, c);
+    delta_g1 = c.delta_g1;
+  }
+
+  // the setup samples its own generators: delta in G2 follows delta in G1
+  if after.delta_g1 != delta_g1
+    || !same_ratio((before.delta_g1, after.delta_g1), (before.vk.delta_g2, after.vk.delta_g2))
+  {
+    return Err(CeremonyError::InvalidUpdate);
+  }
+
+  let scalars: Vec<_> = (0..before.h_query.len() + before.l_query.len())
+    .map(|_| Fr::rand(rng).into_repr())
+    .collect();
+  let combine = |pk: &ProvingKey<Bn254>| {
+    let bases = [pk.h_query.as_slice(), &pk.l_query].concat();
+    VariableBaseMSM::multi_scalar_mul(&bases, &scalars).into_affine()
+  };
+  if Bn254::pairing(combine(after), after.vk.delta_g2) != Bn254::pairing(combine(before), before.vk.delta_g2) {
+    return Err(CeremonyError::InvalidUpdate);
+  }
+
+  Ok(hash)
+}
+
+/**
+ * Verifies that `final_pk` results from `initial` and the contributions of
+ * `transcript`. Returns the transcript hash.
+ */
+pub fn verify_transcript<R: Rng + CryptoRng>(
+  initial: &ProvingKey<Bn254>,
+  final_pk: &ProvingKey<Bn254>,
+  transcript: &Transcript,
+  rng: &mut R,
+) -> Result<[u8; 32], CeremonyError> {
+  if transcript.initial != key_hash(initial) {
+    return Err(CeremonyError::InitialKey);
+  }
+  verify_contributions(initial, final_pk, transcript.initial, &transcript.contributions, 0, rng)
+}
+
+/**
+ * Stand-in for a ceremony coordinator: hands out the current key and
+ * transcript, and accepts a participant's update once it is verified
+ */
+pub struct LocalCoordinator {
+  pk: ProvingKey<Bn254>,
+  transcript: Transcript,
+}
+
+impl LocalCoordinator {
+  pub fn new(initial: ProvingKey<Bn254>) -> Self {
+    let transcript = Transcript::new(&initial);
+    Self { pk: initial, transcript }
+  }
+
+  pub fn proving_key(&self) -> &ProvingKey<Bn254> {
+    &self.pk
+  }
+
+  pub fn transcript(&self) -> &Transcript {
+    &self.transcript
+  }
+
+  /**
+   * Accepts `pk` and `transcript` if they add one valid contribution to the
+   * current ones. Returns the new transcript hash.
+   */
+  pub fn submit<R: Rng + CryptoRng>(
+    &mut self,
+    pk: ProvingKey<Bn254>,
+    transcript: Transcript,
+    rng: &mut R,
+  ) -> Result<[u8; 32], CeremonyError> {
+    let n = self.transcript.len();
+    if transcript.len() != n + 1
+      || transcript.initial != self.transcript.initial
+      || transcript.contributions[..n] != self.transcript.contributions
+    {
+      return Err(CeremonyError::UnexpectedTranscript);
+    }
+
+    let hash = verify_contributions(&self.pk, &pk, self.transcript.hash(), &transcript.contributions[n..], n, rng)?;
+    self.pk = pk;
+    self.transcript = transcript;
+    Ok(hash)
+  }
+
+  /**
+   * Final proving key and transcript
+   */
+  pub fn finish(self) -> (ProvingKey<Bn254>, Transcript) {
+    (self.pk, self.transcript)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_utils::SquareCircuit;
+  use ark_groth16::{create_random_proof, generate_random_parameters, prepare_verifying_key, verify_proof};
+
+  #[test]
+  fn test_ceremony() {
+    let mut rng = ChaCha20Rng::from_seed([0; 32]);
+    let circuit = SquareCircuit(Fr::from(3u8));
+    let initial = generate_random_parameters::<Bn254, _, _>(circuit.clone(), &mut rng).unwrap();
+
+    let mut coordinator = LocalCoordinator::new(initial.clone());
+    for i in 0..3 {
+      let mut pk = coordinator.proving_key().clone();
+      let mut transcript = coordinator.transcript().clone();
+      let hash = contribute(&mut pk, &mut transcript, &mut rng);
+      assert_eq!(transcript.len(), i + 1);
+      assert_eq!(coordinator.submit(pk, transcript, &mut rng), Ok(hash));
+    }
+    let (pk, transcript) = coordinator.finish();
+    assert_ne!(pk.delta_g1, initial.delta_g1);
+    assert_eq!(verify_transcript(&initial, &pk, &transcript, &mut rng), Ok(transcript.hash()));
+
+    let mut bytes = vec![];
+    transcript.serialize(&mut bytes).unwrap();
+    assert_eq!(Transcript::deserialize(bytes.as_slice()).unwrap(), transcript);
+
+    // the final key proves, and proofs of the initial key no longer verify
+    let inputs = [Fr::from(9u8)];
+    let proof = create_random_proof(circuit.clone(), &pk, &mut rng).unwrap();
+    assert!(verify_proof(&prepare_verifying_key(&pk.vk), &proof, &inputs).unwrap());
+    let proof = create_random_proof(circuit, &initial, &mut rng).unwrap();
+    assert!(!verify_proof(&prepare_verifying_key(&pk.vk), &proof, &inputs).unwrap());
+
+    // another initial key
+    let other = generate_random_parameters::<Bn254, _, _>(SquareCircuit(Fr::from(2u8)), &mut rng).unwrap();
+    assert_eq!(verify_transcript(&other, &pk, &transcript, &mut rng), Err(CeremonyError::InitialKey));
+  }
+
+  #[test]
+  fn test_ceremony_tampering() {
+    let mut rng = ChaCha20Rng::from_seed([0; 32]);
+    let initial = generate_random_parameters::<Bn254, _, _>(SquareCircuit(Fr::from(3u8)), &mut rng).unwrap();
+    let mut pk = initial.clone();
+    let mut transcript = Transcript::new(&initial);
+    contribute(&mut pk, &mut transcript, &mut rng);
+    contribute(&mut pk, &mut transcript, &mut rng);
+
+    // a contribution without knowledge of its secret
+    let mut bad = transcript.clone();
+    bad.contributions[1].proof.s_x = bad.contributions[1].proof.s;
+    assert_eq!(verify_transcript(&initial, &pk, &bad, &mut rng), Err(CeremonyError::InvalidContribution(1)));
+
+    // an H query element that is not divided by delta
+    let mut bad = pk.clone();
+    bad.h_query[0] = initial.h_query[0];
+    assert_eq!(verify_transcript(&initial, &bad, &transcript, &mut rng), Err(CeremonyError::InvalidUpdate));
+
+    let mut bad = pk.clone();
+    bad.vk.delta_g2 = initial.vk.delta_g2;
+    assert_eq!(verify_transcript(&initial, &bad, &transcript, &mut rng), Err(CeremonyError::InvalidUpdate));
+
+    let mut bad = pk.clone();
+    bad.beta_g1 = bad.delta_g1;
+    assert_eq!(verify_transcript(&initial, &bad, &transcript, &mut rng), Err(CeremonyError::KeyMismatch));
+
+    // the coordinator takes one contribution at a time
+    let mut coordinator = LocalCoordinator::new(initial);
+    assert_eq!(
+      coordinator.submit(pk, transcript, &mut rng),
+      Err(CeremonyError::UnexpectedTranscript)
+    );
+    assert!(coordinator.transcript().is_empty());
+  }
+}
@@ -0,0 +1,204 @@
+use super::falcon_circuit::const_q_power_vars;
+use crate::{gadgets::*, FalconVerificationWitness};
+use ark_bn254::Fr;
+use ark_ff::PrimeField;
+use ark_r1cs_std::{alloc::AllocVar, fields::fp::FpVar, prelude::*};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, Result, SynthesisError};
+use falcon_rust::*;
+
+/**
+ * Verification of k Falcon signatures in one circuit, for the parameter
+ * set `P`. The only public input is a Poseidon commitment to the
+ * `(hash(pk), hash(hm_ntt))` pair of every signature, see `batch_commitment`.
+ * The circuit is specific to k.
+ */
+#[derive(Clone, Debug)]
+pub struct FalconBatchVerificationCircuit<P: FalconParams = Falcon512> {
+  entries: Vec<(PublicKey<P>, Vec<u8>, Signature<P>)>,
+}
+
+impl<P: FalconParams> FalconBatchVerificationCircuit<P> {
+  /**
+   * An empty batch has no commitment: its constraints fail with
+   * `Unsatisfiable`
+   */
+  pub fn build_circuit(entries: Vec<(PublicKey<P>, Vec<u8>, Signature<P>)>) -> Self {
+    Self { entries }
+  }
+
+  pub fn len(&self) -> usize {
+    self.entries.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
+
+  /**
+   * `(hash(pk), hash(hm_ntt))` of every signature, in order
+   */
+  pub fn hash_pairs(&self) -> Vec<(Fr, Fr)> {
+    self
+      .entries
+      .iter()
+      .map(|(pk, msg, sig)| {
+        let hm_ntt = NTTPolynomial::<P>::from_hash_of_message(msg, sig.nonce());
+        (hash_from_pk(&pk.into()).unwrap(), hash_from_poly(&hm_ntt).unwrap())
+      })
+      .collect()
+  }
+
+  /**
+   * Public inputs expected by the verifier: `[commitment]`, or none for an
+   * empty batch
+   */
+  pub fn public_inputs(&self) -> Vec<Fr> {
+    batch_commitment(&self.hash_pairs()).into_iter().collect()
+  }
+}
+
+/**
+ * Poseidon commitment to a batch: `hash_chain` over
+ * `[hash(pk_1), hash(hm_ntt_1), ..., hash(pk_k), hash(hm_ntt_k)]`
+ */
+pub fn batch_commitment(pairs: &[(Fr, Fr)]) -> Result<Fr> {
+  let inputs: Vec<Fr> = pairs.iter().flat_map(|(pk, hm)| [*pk, *hm]).collect();
+  hash_chain(&inputs)
+}
+
+impl<F: PrimeField, P: FalconParams> ConstraintSynthesizer<F> for FalconBatchVerificationCircuit<P> {
+  fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<()> {
+    let witnesses: Vec<_> = self
+      .entries
+      .iter()
+      .map(|(pk, msg, sig)| FalconVerificationWitness::<F, P>::from_signature(pk, msg, sig))
+      .collect();
+    enforce_batch(cs, &witnesses)
+  }
+}
+
+/*
+  Batch verification over the raw witness of every signature
+*/
+fn enforce_batch<F: PrimeField, P: FalconParams>(
+  mut cs: ConstraintSystemRef<F>,
+  witnesses: &[FalconVerificationWitness<F, P>],
+) -> Result<()> {
+  if witnesses.is_empty() {
+    return Err(SynthesisError::Unsatisfiable);
+  }
+
+  let const_q_power_vars = const_q_power_vars::<F, P>(cs.clone())?;
+  let param_vars = ntt_param_var::<F, P>(cs.clone())?;
+  let hasher = poseidon_gadget(&mut cs)?;
+
+  let mut digests = Vec::with_capacity(2 * witnesses.len());
+  for witness in witnesses {
+    let (pk_vars, _, hm_ntt_vars) = witness.enforce_verification(cs.clone(), &const_q_power_vars, &param_vars)?;
+
+    digests.push(hash_chain_var(&hasher, pk_vars.coeff())?);
+    digests.push(hash_chain_var(&hasher, hm_ntt_vars.coeff())?);
+  }
+
+  let commitment = hash_chain_var(&hasher, &digests)?;
+  let res = FpVar::new_input(cs, || commitment.value())?;
+  res.enforce_equal(&commitment)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::FalconVerificationCircuit;
+  use ark_relations::r1cs::ConstraintSystem;
+
+  #[test]
+  fn test_batch_verification_r1cs() {
+    let entries: Vec<_> = [&b"first message"[..], b"second message"]
+      .iter()
+      .enumerate()
+      .map(|(i, msg)| {
+        let keypair = KeyPair512::keygen_from_seed(&[i as u8; 48]);
+        let sig = keypair.secret_key.sign(msg);
+        (keypair.public_key, msg.to_vec(), sig)
+      })
+      .collect();
+
+    let circuit = FalconBatchVerificationCircuit::build_circuit(entries.clone());
+    let public_inputs = circuit.public_inputs();
+
+    let pairs: Vec<_> = entries
+      .iter()
+      .map(|(pk, msg, sig)| {
+        let single = FalconVerificationCircuit::build_circuit(*pk, msg.clone(), *sig).public_inputs();
+        (single[2], single[1])
+      })
+      .collect();
+    assert_eq!(circuit.hash_pairs(), pairs);
+    assert_eq!(public_inputs, [batch_commitment(&pairs).unwrap()]);
+
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    circuit.generate_constraints(cs.clone()).unwrap();
+    println!(
+      "number of variables {} {} and constraints {}\n",
+      cs.num_instance_variables(),
+      cs.num_witness_variables(),
+      cs.num_constraints(),
+    );
+
+    assert!(cs.is_satisfied().unwrap());
+    assert_eq!(cs.borrow().unwrap().instance_assignment[1..], public_inputs);
+
+    // the commitment binds the order of the pairs
+    let swapped = batch_commitment(&[pairs[1], pairs[0]]).unwrap();
+    assert_ne!(swapped, public_inputs[0]);
+    cs.borrow_mut().unwrap().instance_assignment[1] = swapped;
+    assert!(!cs.is_satisfied().unwrap());
+  }
+
+  #[test]
+  fn test_batch_verification_r1cs_bad_signature() {
+    let keypair = KeyPair512::keygen_from_seed(&[0; 48]);
+    let sig = keypair.secret_key.sign(b"signed message");
+    let circuit = FalconBatchVerificationCircuit::build_circuit(vec![(keypair.public_key, b"another message".to_vec(), sig)]);
+
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    circuit.generate_constraints(cs.clone()).unwrap();
+    assert!(!cs.is_satisfied().unwrap());
+  }
+
+  #[test]
+  fn test_batch_verification_r1cs_forged_signature() {
+    let keys: Vec<_> = (0..2u8).map(|i| KeyPair512::keygen_from_seed(&[i; 48])).collect();
+    let sig = keys[0].secret_key.sign(b"first message");
+    let mut witnesses = vec![
+      FalconVerificationWitness::<Fr>::from_signature(&keys[0].public_key, b"first message", &sig),
+      FalconVerificationWitness::<Fr>::from_signature(&keys[1].public_key, b"never signed", &sig),
+    ];
+
+    /*
+      The second entry takes s2 = v = 0, with a zero norm. hm_ntt is then the
+      remainder of v_ntt + s2_ntt * pk_ntt = 0, and only the range checks of
+      the quotients -hm_ntt / q fail.
+    */
+    witnesses[1].s2 = vec![Fr::from(0u8); 512];
+    witnesses[1].v = vec![Fr::from(0u8); 512];
+
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    enforce_batch(cs.clone(), &witnesses).unwrap();
+    assert!(!cs.is_satisfied().unwrap());
+
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    enforce_batch(cs.clone(), &witnesses[..1]).unwrap();
+    assert!(cs.is_satisfied().unwrap());
+  }
+
+  #[test]
+  fn test_batch_verification_r1cs_empty() {
+    let circuit = FalconBatchVerificationCircuit::<Falcon512>::build_circuit(vec![]);
+    assert!(circuit.is_empty());
+    assert!(circuit.public_inputs().is_empty());
+
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    assert!(matches!(circuit.generate_constraints(cs), Err(SynthesisError::Unsatisfiable)));
+  }
+}
@@ -1,95 +1,329 @@
 use crate::gadgets::*;
-use ark_ff::PrimeField;
-use ark_r1cs_std::{alloc::AllocVar, fields::fp::FpVar, prelude::*};
-use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, Result};
+use ark_bn254::Fr;
+use ark_ff::{PrimeField, ToConstraintField};
+use ark_r1cs_std::{alloc::AllocVar, fields::fp::FpVar, prelude::*, uint8::UInt8};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, Result, SynthesisError};
+use std::marker::PhantomData;
 use falcon_rust::*;
 
+/**
+ * How the signed message enters the circuit
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MessageMode {
+  /// `hm_ntt` is a witness, revealed through its Poseidon hash.
+  /// Public inputs: `[hash(pk_ntt), hash(hm_ntt), hash(pk)]`
+  #[default]
+  HashedPoint,
+  /// The nonce is a witness and `hm` is computed in-circuit with SHAKE256.
+  /// Public inputs: `[hash(pk_ntt), hash(pk), msg packed into field elements...]`
+  PublicMessage,
+}
+
+/**
+ * Falcon signature verification circuit, for the parameter set `P`
+ */
 #[derive(Clone, Debug)]
-pub struct FalconVerificationCircuit {
-  pk: PublicKey,
-  msg: Vec<u8>,
-  sig: Signature
+pub struct FalconVerificationCircuit<P: FalconParams = Falcon512> {
+  pub(crate) pk: PublicKey<P>,
+  pub(crate) msg: Vec<u8>,
+  pub(crate) sig: Signature<P>,
+  pub(crate) mode: MessageMode,
 }
 
-impl FalconVerificationCircuit {
-  pub fn build_circuit(pk: PublicKey, msg: Vec<u8>, sig: Signature) -> Self {
-    Self { pk, msg, sig }
+impl<P: FalconParams> FalconVerificationCircuit<P> {
+  pub fn build_circuit(pk: PublicKey<P>, msg: Vec<u8>, sig: Signature<P>) -> Self {
+    Self { pk, msg, sig, mode: MessageMode::HashedPoint }
+  }
+
+  pub fn build_circuit_with_public_message(pk: PublicKey<P>, msg: Vec<u8>, sig: Signature<P>) -> Self {
+    Self { pk, msg, sig, mode: MessageMode::PublicMessage }
+  }
+
+  pub fn mode(&self) -> MessageMode {
+    self.mode
+  }
+
+  /**
+   * Public inputs expected by the verifier, in allocation order
+   */
+  pub fn public_inputs(&self) -> Vec<Fr> {
+    let pk = Polynomial::from(&self.pk);
+    let pk_ntt = NTTPolynomial::from(&pk);
+
+    match self.mode {
+      MessageMode::HashedPoint => {
+        let hm_ntt = NTTPolynomial::<P>::from_hash_of_message(self.msg.as_ref(), self.sig.nonce());
+        vec![
+          hash_from_poly(&pk_ntt).unwrap(),
+          hash_from_poly(&hm_ntt).unwrap(),
+          hash_from_pk(&pk).unwrap(),
+        ]
+      }
+      MessageMode::PublicMessage => [
+        vec![hash_from_poly(&pk_ntt).unwrap(), hash_from_pk(&pk).unwrap()],
+        pack_message(self.msg.as_ref()),
+      ]
+      .concat(),
+    }
   }
 }
 
-impl<F: PrimeField> ConstraintSynthesizer<F> for FalconVerificationCircuit {
+/**
+ * Field-packed message encoding used by `MessageMode::PublicMessage`
+ */
+pub fn pack_message<F: PrimeField>(msg: &[u8]) -> Vec<F> {
+  msg.to_field_elements().unwrap()
+}
+
+impl<F: PrimeField, P: FalconParams> ConstraintSynthesizer<F> for FalconVerificationCircuit<P> {
   fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<()> {
-    let sig_poly: Polynomial = (&self.sig).into();
-    let pk_poly: Polynomial = (&self.pk).into();
-
-    let const_q_power_vars: Vec<FpVar<F>> = (1..LOG_N + 2)
-      .map(|x| {
-        FpVar::<F>::new_constant(
-          cs.clone(), 
-          F::from(1u32 << (x - 1)) * F::from(MODULUS).pow(&[x as u64]),
-        )
-        .unwrap()
-      })
-      .collect();
-    let param_vars = ntt_param_var(cs.clone()).unwrap();
-
-    let hm = Polynomial::from_hash_of_message(self.msg.as_ref(), self.sig.nonce());
-    let hm_ntt = NTTPolynomial::from(&hm);
+    if self.mode == MessageMode::HashedPoint {
+      return FalconVerificationWitness::<F, P>::from_signature(&self.pk, &self.msg, &self.sig).generate_constraints(cs);
+    }
+
+    let pk_poly: Polynomial<P> = (&self.pk).into();
+    let const_q_power_vars = const_q_power_vars::<F, P>(cs.clone())?;
+    let param_vars = ntt_param_var::<F, P>(cs.clone())?;
+
+    let (pk_vars, pk_ntt_vars) = alloc_public_key(cs.clone(), &pk_poly, &const_q_power_vars, &param_vars)?;
+
+    // see `FalconVerificationWitness` for the public inputs
+    PoseidonVars::hash_poly(cs.clone(), &pk_ntt_vars, AllocationMode::Input)?;
+    PoseidonVars::hash_pubic_key(cs.clone(), &pk_vars, AllocationMode::Input)?;
+    let msg_vars = UInt8::new_input_vec(cs.clone(), self.msg.as_ref())?;
+    let nonce_vars = UInt8::new_witness_vec(cs.clone(), self.sig.nonce())?;
+    let hm_vars = hash_to_point_var::<F, P>(cs.clone(), &nonce_vars, &msg_vars, &const_q_power_vars[0])?;
+    let hm_ntt_vars = NTTPolyVar::ntt_circuit(cs.clone(), &hm_vars, &const_q_power_vars, &param_vars)?;
+
+    enforce_signature(
+      cs,
+      &self.sig,
+      &pk_poly,
+      &self.msg,
+      &pk_ntt_vars,
+      &hm_ntt_vars,
+      &const_q_power_vars,
+      &param_vars,
+    )
+  }
+}
+
+/**
+ * Witness of the `MessageMode::HashedPoint` circuit as raw field elements:
+ * the public key, the hashed message in NTT form, s2 and v = hm - s2 * h.
+ * Unlike `FalconVerificationCircuit`, it can hold any value, valid or not,
+ * e.g. to check that tampered witnesses leave the circuit unsatisfied.
+ * Public inputs: `[hash(pk_ntt), hash(hm_ntt), hash(pk)]`
+ */
+#[derive(Clone, Debug)]
+pub struct FalconVerificationWitness<F: PrimeField, P: FalconParams = Falcon512> {
+  pub pk: Vec<F>,
+  pub hm_ntt: Vec<F>,
+  pub s2: Vec<F>,
+  pub v: Vec<F>,
+  params: PhantomData<P>,
+}
 
-    let uh = sig_poly * pk_poly;
-    let v: Polynomial = hm - uh;
+impl<F: PrimeField, P: FalconParams> FalconVerificationWitness<F, P> {
+  pub fn new(pk: Vec<F>, hm_ntt: Vec<F>, s2: Vec<F>, v: Vec<F>) -> Self {
+    Self { pk, hm_ntt, s2, v, params: PhantomData }
+  }
 
-    let pk_ntt = NTTPolynomial::from(&pk_poly);
+  /**
+   * Witness of the verification of `sig`, which does not have to be valid
+   */
+  pub fn from_signature(pk: &PublicKey<P>, msg: &[u8], sig: &Signature<P>) -> Self {
+    let pk_poly: Polynomial<P> = pk.into();
+    let hm = Polynomial::<P>::from_hash_of_message(msg, sig.nonce());
+    let (s2, v) = signature_witness::<F, P>(sig, &pk_poly, msg);
+    Self::new(field_coeffs(pk_poly.coeff()), field_coeffs(NTTPolynomial::from(&hm).coeff()), s2, v)
+  }
+}
 
-    let sig_poly_vars = PolyVar::<F>::alloc_vars(cs.clone(), &sig_poly, AllocationMode::Witness)?;
-    let pk_ntt_vars = NTTPolyVar::<F>::alloc_vars(cs.clone(), &pk_ntt, AllocationMode::Witness)?;
-    let hm_ntt_vars = NTTPolyVar::<F>::alloc_vars(cs.clone(), &hm_ntt, AllocationMode::Witness)?;
-    let v_vars = PolyVar::<F>::alloc_vars(cs.clone(), &v, AllocationMode::Witness)?;
+impl<F: PrimeField, P: FalconParams> FalconVerificationWitness<F, P> {
+  /*
+    Allocates pk and hm_ntt, derives pk_ntt from pk and enforces the
+    signature relation over them. Returns the pk, pk_ntt and hm_ntt vars.
+  */
+  pub(crate) fn enforce_verification(
+    &self,
+    cs: ConstraintSystemRef<F>,
+    const_q_power_vars: &[FpVar<F>],
+    param_vars: &[FpVar<F>],
+  ) -> Result<(PolyVar<F>, NTTPolyVar<F>, NTTPolyVar<F>)> {
+    if [&self.pk, &self.hm_ntt, &self.s2, &self.v].iter().any(|x| x.len() != P::N) {
+      return Err(SynthesisError::Unsatisfiable);
+    }
+
+    let pk_vars = PolyVar::new(alloc_coeffs(cs.clone(), &self.pk)?);
+    let pk_ntt_vars = public_key_ntt(cs.clone(), &pk_vars, const_q_power_vars, param_vars)?;
+    let hm_ntt_vars = NTTPolyVar::new(alloc_coeffs(cs.clone(), &self.hm_ntt)?);
+
+    enforce_signature_witness::<F, P>(
+      cs,
+      &self.s2,
+      &self.v,
+      &pk_ntt_vars,
+      &hm_ntt_vars,
+      const_q_power_vars,
+      param_vars,
+    )?;
+
+    Ok((pk_vars, pk_ntt_vars, hm_ntt_vars))
+  }
+}
+
+impl<F: PrimeField, P: FalconParams> ConstraintSynthesizer<F> for FalconVerificationWitness<F, P> {
+  fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<()> {
+    let const_q_power_vars = const_q_power_vars::<F, P>(cs.clone())?;
+    let param_vars = ntt_param_var::<F, P>(cs.clone())?;
+
+    let (pk_vars, pk_ntt_vars, hm_ntt_vars) = self.enforce_verification(cs.clone(), &const_q_power_vars, &param_vars)?;
 
     /*
       Compress public inputs
         - Reveal the G1 points' hash to the verifier.
         - Calculate the owner hash from public key with poseidon.
+      The hashes are computed in-circuit over the witness vars, so the
+      public inputs are bound to the key and message being verified.
     */
-    PoseidonVars::hash_poly(cs.clone(), &pk_ntt, AllocationMode::Input)?;
-    PoseidonVars::hash_poly(cs.clone(), &hm_ntt, AllocationMode::Input)?;
-    PoseidonVars::hash_pubic_key(cs.clone(), &pk_poly, AllocationMode::Input)?;
+    PoseidonVars::hash_poly(cs.clone(), &pk_ntt_vars, AllocationMode::Input)?;
+    PoseidonVars::hash_poly(cs.clone(), &hm_ntt_vars, AllocationMode::Input)?;
+    PoseidonVars::hash_pubic_key(cs, &pk_vars, AllocationMode::Input)?;
+    Ok(())
+  }
+}
 
-    for e in v_vars.coeff() {
-      enforce_less_than_q(cs.clone(), &e)?;
-    }
+fn field_coeffs<F: PrimeField>(coeffs: &[u16]) -> Vec<F> {
+  coeffs.iter().map(|&x| F::from(x)).collect()
+}
 
-    let sig_ntt_vars = NTTPolyVar::ntt_circuit(cs.clone(), &sig_poly_vars, &const_q_power_vars, &param_vars)?;
-    let v_ntt_vars = NTTPolyVar::ntt_circuit(cs.clone(), &v_vars, &const_q_power_vars, &param_vars)?;
+fn alloc_coeffs<F: PrimeField>(cs: ConstraintSystemRef<F>, coeffs: &[F]) -> Result<Vec<FpVar<F>>> {
+  coeffs.iter().map(|x| FpVar::new_witness(cs.clone(), || Ok(*x))).collect()
+}
 
-    for i in 0..N {
-      hm_ntt_vars.coeff()[i].enforce_equal(&add_mod(
-        cs.clone(),
-        &v_ntt_vars.coeff()[i],
-        &(&sig_ntt_vars.coeff()[i] * &pk_ntt_vars.coeff()[i]),
-        &const_q_power_vars[0],
-      )?)?;
-    }
+/**
+ * `2^(i - 1) * q^i` for i in 1..=LOG_N + 1, used by the modular reductions
+ * and the NTT
+ */
+pub(crate) fn const_q_power_vars<F: PrimeField, P: FalconParams>(cs: ConstraintSystemRef<F>) -> Result<Vec<FpVar<F>>> {
+  (1..P::LOG_N + 2)
+    .map(|x| FpVar::<F>::new_constant(cs.clone(), F::from(1u32 << (x - 1)) * F::from(MODULUS).pow([x as u64])))
+    .collect()
+}
+
+/**
+ * Public key vars in coefficient and NTT form. The key is allocated once
+ * in coefficient form and its NTT form is derived in-circuit, with range
+ * checked reduction quotients, so both describe the same key.
+ */
+pub(crate) fn alloc_public_key<F: PrimeField, P: FalconParams>(
+  cs: ConstraintSystemRef<F>,
+  pk: &Polynomial<P>,
+  const_q_power_vars: &[FpVar<F>],
+  param_vars: &[FpVar<F>],
+) -> Result<(PolyVar<F>, NTTPolyVar<F>)> {
+  let pk_vars = PolyVar::<F>::alloc_vars(cs.clone(), pk, AllocationMode::Witness)?;
+  let pk_ntt_vars = public_key_ntt(cs, &pk_vars, const_q_power_vars, param_vars)?;
+  Ok((pk_vars, pk_ntt_vars))
+}
+
+fn public_key_ntt<F: PrimeField>(
+  cs: ConstraintSystemRef<F>,
+  pk_vars: &PolyVar<F>,
+  const_q_power_vars: &[FpVar<F>],
+  param_vars: &[FpVar<F>],
+) -> Result<NTTPolyVar<F>> {
+  for e in pk_vars.coeff() {
+    enforce_less_than_q(cs.clone(), e)?;
+  }
+  NTTPolyVar::ntt_circuit(cs, pk_vars, const_q_power_vars, param_vars)
+}
+
+/**
+ * Falcon verification over the public key and hashed message vars: s2 and
+ * v = hm - s2 * h are witnesses, hm = v + s2 * h is enforced in NTT form
+ * and ||(v, s2)||^2 must be below the norm bound
+ */
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn enforce_signature<F: PrimeField, P: FalconParams>(
+  cs: ConstraintSystemRef<F>,
+  sig: &Signature<P>,
+  pk: &Polynomial<P>,
+  msg: &[u8],
+  pk_ntt_vars: &NTTPolyVar<F>,
+  hm_ntt_vars: &NTTPolyVar<F>,
+  const_q_power_vars: &[FpVar<F>],
+  param_vars: &[FpVar<F>],
+) -> Result<()> {
+  let (s2, v) = signature_witness::<F, P>(sig, pk, msg);
+  enforce_signature_witness::<F, P>(cs, &s2, &v, pk_ntt_vars, hm_ntt_vars, const_q_power_vars, param_vars)
+}
+
+/*
+  s2 and v = hm - s2 * h, reduced mod q
+*/
+fn signature_witness<F: PrimeField, P: FalconParams>(
+  sig: &Signature<P>,
+  pk: &Polynomial<P>,
+  msg: &[u8],
+) -> (Vec<F>, Vec<F>) {
+  let sig_poly: Polynomial<P> = sig.into();
+  let hm = Polynomial::<P>::from_hash_of_message(msg, sig.nonce());
+  let v = hm - sig_poly * *pk;
+  (field_coeffs(sig_poly.coeff()), field_coeffs(v.coeff()))
+}
+
+fn enforce_signature_witness<F: PrimeField, P: FalconParams>(
+  cs: ConstraintSystemRef<F>,
+  s2: &[F],
+  v: &[F],
+  pk_ntt_vars: &NTTPolyVar<F>,
+  hm_ntt_vars: &NTTPolyVar<F>,
+  const_q_power_vars: &[FpVar<F>],
+  param_vars: &[FpVar<F>],
+) -> Result<()> {
+  let sig_poly_vars = PolyVar::new(alloc_coeffs(cs.clone(), s2)?);
+  let v_vars = PolyVar::new(alloc_coeffs(cs.clone(), v)?);
 
-    let l2_norm_var = l2_norm_var(
+  for e in v_vars.coeff() {
+    enforce_less_than_q(cs.clone(), e)?;
+  }
+
+  let sig_ntt_vars = NTTPolyVar::ntt_circuit(cs.clone(), &sig_poly_vars, const_q_power_vars, param_vars)?;
+  let v_ntt_vars = NTTPolyVar::ntt_circuit(cs.clone(), &v_vars, const_q_power_vars, param_vars)?;
+
+  // hm_ntt is the remainder: its quotient is derived from it and range checked
+  for i in 0..P::N {
+    enforce_add_mod(
       cs.clone(),
-      &[v_vars.coeff(), sig_poly_vars.coeff()].concat(),
+      &v_ntt_vars.coeff()[i],
+      &(&sig_ntt_vars.coeff()[i] * &pk_ntt_vars.coeff()[i]),
+      &hm_ntt_vars.coeff()[i],
       &const_q_power_vars[0],
     )?;
-
-    enforce_less_than_norm_bound(cs, &l2_norm_var)
   }
+
+  let l2_norm_var = l2_norm_var(
+    cs.clone(),
+    &[v_vars.coeff(), sig_poly_vars.coeff()].concat(),
+    &const_q_power_vars[0],
+  )?;
+
+  enforce_less_than_norm_bound::<F, P>(cs, &l2_norm_var)
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
+  use ark_bn254::Fr;
   use ark_ed_on_bn254::fq::Fq;
   use ark_relations::r1cs::ConstraintSystem;
   
   #[test]
   fn test_verification_r1cs() {
-    let keypair = KeyPair::keygen();
+    let keypair = KeyPair512::keygen_from_seed(b"falcon-r1cs test seed");
     let message = "Testing message".as_bytes();
     let sig = keypair
       .secret_key
@@ -100,11 +334,11 @@ mod tests {
     
     let cs = ConstraintSystem::<Fq>::new_ref();
 
-    let falcon_circuit = FalconVerificationCircuit {
-      pk: keypair.public_key,
-      msg: message.to_vec(),
+    let falcon_circuit = FalconVerificationCircuit::build_circuit(
+      keypair.public_key,
+      message.to_vec(),
       sig,
-    };
+    );
 
     falcon_circuit.generate_constraints(cs.clone()).unwrap();
     println!(
@@ -116,4 +350,273 @@ mod tests {
 
     assert!(cs.is_satisfied().unwrap());
   }
+
+  #[test]
+  fn test_verification_r1cs_public_inputs() {
+    let keypair = KeyPair512::keygen_from_seed(b"falcon-r1cs test seed");
+    let message = "Testing message".as_bytes();
+    let sig = keypair
+      .secret_key
+      .sign(message.as_ref());
+
+    let pk = Polynomial::from(&keypair.public_key);
+    let pk_ntt = NTTPolynomial::from(&pk);
+    let hm_ntt = NTTPolynomial512::from_hash_of_message(message, sig.nonce());
+
+    let circuit = FalconVerificationCircuit::build_circuit(keypair.public_key, message.to_vec(), sig);
+    let public_inputs = circuit.public_inputs();
+
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    circuit.generate_constraints(cs.clone()).unwrap();
+    assert!(cs.is_satisfied().unwrap());
+
+    // index 0 is the constant one
+    let instance = cs.borrow().unwrap().instance_assignment.clone();
+    assert_eq!(
+      instance[1..],
+      [
+        hash_from_poly(&pk_ntt).unwrap(),
+        hash_from_poly(&hm_ntt).unwrap(),
+        hash_from_pk(&pk).unwrap(),
+      ]
+    );
+    assert_eq!(instance[1..], public_inputs);
+
+    for (i, value) in instance.iter().enumerate().skip(1) {
+      cs.borrow_mut().unwrap().instance_assignment[i] += Fr::from(1u8);
+      assert!(!cs.is_satisfied().unwrap());
+      cs.borrow_mut().unwrap().instance_assignment[i] = *value;
+    }
+  }
+
+  fn assert_unsatisfied(witness: FalconVerificationWitness<Fr>) {
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    witness.generate_constraints(cs.clone()).unwrap();
+    assert!(!cs.is_satisfied().unwrap());
+  }
+
+  #[test]
+  fn test_verification_witness_public_inputs() {
+    let keypair = KeyPair512::keygen_from_seed(b"falcon-r1cs test seed");
+    let other = KeyPair512::keygen_from_seed(b"another seed");
+    let message = "Testing message".as_bytes();
+    let sig = keypair.secret_key.sign(message);
+
+    let witness = FalconVerificationWitness::from_signature(&keypair.public_key, message, &sig);
+    let public_inputs = FalconVerificationCircuit::build_circuit(keypair.public_key, message.to_vec(), sig).public_inputs();
+
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    witness.generate_constraints(cs.clone()).unwrap();
+    assert!(cs.is_satisfied().unwrap());
+    assert_eq!(cs.borrow().unwrap().instance_assignment[1..], public_inputs);
+
+    // Poseidon digests of another key and another message
+    let hm_ntt = NTTPolynomial512::from_hash_of_message(b"Another message", sig.nonce());
+    for (i, value) in [
+      (1, hash_from_poly(&NTTPolynomial::from(&Polynomial::from(&other.public_key))).unwrap()),
+      (2, hash_from_poly(&hm_ntt).unwrap()),
+      (3, hash_from_pk(&(&other.public_key).into()).unwrap()),
+    ] {
+      cs.borrow_mut().unwrap().instance_assignment[i] = value;
+      assert!(!cs.is_satisfied().unwrap());
+      cs.borrow_mut().unwrap().instance_assignment[i] = public_inputs[i - 1];
+    }
+    assert!(cs.is_satisfied().unwrap());
+  }
+
+  #[test]
+  fn test_verification_witness_wrong_message() {
+    let keypair = KeyPair512::keygen_from_seed(b"falcon-r1cs test seed");
+    let sig = keypair.secret_key.sign(b"Testing message");
+    assert_unsatisfied(FalconVerificationWitness::from_signature(&keypair.public_key, b"Another message", &sig));
+  }
+
+  #[test]
+  fn test_verification_witness_swapped_public_key() {
+    let keypair = KeyPair512::keygen_from_seed(b"falcon-r1cs test seed");
+    let other = KeyPair512::keygen_from_seed(b"another seed");
+    let message = "Testing message".as_bytes();
+    let sig = keypair.secret_key.sign(message);
+    assert_unsatisfied(FalconVerificationWitness::from_signature(&other.public_key, message, &sig));
+  }
+
+  #[test]
+  fn test_verification_witness_flipped_signature_coefficient() {
+    let keypair = KeyPair512::keygen_from_seed(b"falcon-r1cs test seed");
+    let message = "Testing message".as_bytes();
+    let sig = keypair.secret_key.sign(message);
+
+    let mut witness = FalconVerificationWitness::from_signature(&keypair.public_key, message, &sig);
+    witness.s2[7] += Fr::from(1u8);
+    assert_unsatisfied(witness);
+  }
+
+  #[test]
+  fn test_verification_witness_out_of_range_v() {
+    let keypair = KeyPair512::keygen_from_seed(b"falcon-r1cs test seed");
+    let message = "Testing message".as_bytes();
+    let sig = keypair.secret_key.sign(message);
+
+    // v + q still satisfies hm = v + s2 * h mod q, only the range check fails
+    let mut witness = FalconVerificationWitness::from_signature(&keypair.public_key, message, &sig);
+    witness.v[0] += Fr::from(MODULUS);
+    assert_unsatisfied(witness);
+  }
+
+  #[test]
+  fn test_verification_witness_over_norm_signature() {
+    let keypair = KeyPair512::keygen_from_seed(b"falcon-r1cs test seed");
+    let message = "Testing message".as_bytes();
+    let sig = keypair.secret_key.sign(message);
+
+    // s2 and v satisfy hm = v + s2 * h, but are far too long
+    let pk = Polynomial::from(&keypair.public_key);
+    let hm = Polynomial512::from_hash_of_message(message, sig.nonce());
+    let s2 = Polynomial512::from_coeffs(&[4096; 512]).unwrap();
+    let v = hm - s2 * pk;
+    assert!(s2.l2_norm() + v.l2_norm() >= Falcon512::SIG_L2_BOUND);
+
+    assert_unsatisfied(FalconVerificationWitness::new(
+      field_coeffs(pk.coeff()),
+      field_coeffs(NTTPolynomial::from(&hm).coeff()),
+      field_coeffs(s2.coeff()),
+      field_coeffs(v.coeff()),
+    ));
+  }
+
+  #[test]
+  fn test_verification_witness_forged_quotients() {
+    let keypair = KeyPair512::keygen_from_seed(b"falcon-r1cs test seed");
+    let pk = Polynomial::from(&keypair.public_key);
+    let hm_ntt = NTTPolynomial512::from_hash_of_message(b"never signed", &[0; 40]);
+
+    /*
+      s2 = v = 0 has a zero norm and passes every range check of s2 and v.
+      hm_ntt is the remainder of v_ntt + s2_ntt * pk_ntt = 0, so the
+      quotients are -hm_ntt / q in the field: only their range checks fail.
+    */
+    assert_unsatisfied(FalconVerificationWitness::new(
+      field_coeffs(pk.coeff()),
+      field_coeffs(hm_ntt.coeff()),
+      vec![Fr::from(0u8); 512],
+      vec![Fr::from(0u8); 512],
+    ));
+  }
+
+  /*
+    Malicious prover: allocates pk_ntt as a witness, checked by
+    `NTTPolyVar::enforce_ntt`, and an hm_ntt of its choice. Returns whether
+    the constraints hold.
+  */
+  fn verify_with_public_key_ntt(pk: &[u16], pk_ntt: &[u16], hm_ntt: &[u16], s2: &[u16], v: &[u16]) -> bool {
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    let const_q_power_vars = const_q_power_vars::<Fr, Falcon512>(cs.clone()).unwrap();
+    let param_vars = ntt_param_var::<Fr, Falcon512>(cs.clone()).unwrap();
+
+    let pk_vars = PolyVar::new(alloc_coeffs(cs.clone(), &field_coeffs(pk)).unwrap());
+    let pk_ntt_vars = NTTPolyVar::new(alloc_coeffs(cs.clone(), &field_coeffs(pk_ntt)).unwrap());
+    NTTPolyVar::enforce_ntt(cs.clone(), &pk_vars, &pk_ntt_vars, &const_q_power_vars, &param_vars).unwrap();
+    let hm_ntt_vars = NTTPolyVar::new(alloc_coeffs(cs.clone(), &field_coeffs(hm_ntt)).unwrap());
+
+    enforce_signature_witness::<Fr, Falcon512>(
+      cs.clone(),
+      &field_coeffs(s2),
+      &field_coeffs(v),
+      &pk_ntt_vars,
+      &hm_ntt_vars,
+      &const_q_power_vars,
+      &param_vars,
+    )
+    .unwrap();
+
+    for (var, value) in pk_ntt_vars.coeff().iter().zip(pk_ntt) {
+      assert_eq!(var.value().unwrap(), Fr::from(*value));
+    }
+    cs.is_satisfied().unwrap()
+  }
+
+  #[test]
+  fn test_verification_witness_forged_public_key_ntt() {
+    let keypair = KeyPair512::keygen_from_seed(b"falcon-r1cs test seed");
+    let message = "Testing message".as_bytes();
+    let sig = keypair.secret_key.sign(message);
+    let pk = Polynomial::from(&keypair.public_key);
+    let pk_ntt = NTTPolynomial::from(&pk);
+    let s2 = Polynomial::from(&sig);
+    let s2_ntt = NTTPolynomial::from(&s2);
+    let hm = Polynomial512::from_hash_of_message(message, sig.nonce());
+    let hm_ntt = NTTPolynomial::from(&hm);
+    let v = hm - s2 * pk;
+
+    let verify = |pk_ntt: &[u16], hm_ntt: &[u16]| verify_with_public_key_ntt(pk.coeff(), pk_ntt, hm_ntt, s2.coeff(), v.coeff());
+    assert!(verify(pk_ntt.coeff(), hm_ntt.coeff()));
+
+    // pk, s2 and v are unchanged but pk_ntt[0] is not the NTT of pk, and
+    // hm_ntt[0] is moved so that hm_ntt[0] = v_ntt[0] + s2_ntt[0] * pk_ntt[0]
+    // still holds
+    let q = MODULUS as u32;
+    let mut forged_pk_ntt = pk_ntt.coeff().to_vec();
+    forged_pk_ntt[0] = ((forged_pk_ntt[0] as u32 + 1) % q) as u16;
+    let mut forged_hm_ntt = hm_ntt.coeff().to_vec();
+    forged_hm_ntt[0] = ((forged_hm_ntt[0] as u32 + s2_ntt.coeff()[0] as u32) % q) as u16;
+    assert!(!verify(&forged_pk_ntt, &forged_hm_ntt));
+  }
+
+  #[test]
+  fn test_verification_witness_length() {
+    let witness = FalconVerificationWitness::<Fr>::new(vec![], vec![], vec![], vec![]);
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    assert!(witness.generate_constraints(cs).is_err());
+  }
+
+  #[test]
+  fn test_verification_r1cs_public_message() {
+    let keypair = KeyPair512::keygen_from_seed(b"falcon-r1cs test seed");
+    let message = "Testing message".as_bytes();
+    let sig = keypair
+      .secret_key
+      .sign(message.as_ref());
+
+    let circuit = FalconVerificationCircuit::build_circuit_with_public_message(
+      keypair.public_key,
+      message.to_vec(),
+      sig,
+    );
+    let public_inputs = circuit.public_inputs();
+
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    circuit.generate_constraints(cs.clone()).unwrap();
+    println!(
+      "number of variables {} {} and constraints {}\n",
+      cs.num_instance_variables(),
+      cs.num_witness_variables(),
+      cs.num_constraints(),
+    );
+
+    assert!(cs.is_satisfied().unwrap());
+    assert_eq!(cs.borrow().unwrap().instance_assignment[1..], public_inputs);
+  }
+
+  #[test]
+  fn test_verification_r1cs_1024() {
+    let keypair = KeyPair::<Falcon1024>::keygen_from_seed(b"falcon-r1cs test seed");
+    let message = "Testing message".as_bytes();
+    let sig = keypair.secret_key.sign(message);
+    assert!(keypair.public_key.verify_rust(message, &sig));
+
+    let circuit = FalconVerificationCircuit::build_circuit(keypair.public_key, message.to_vec(), sig);
+    let public_inputs = circuit.public_inputs();
+
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    circuit.generate_constraints(cs.clone()).unwrap();
+    println!(
+      "number of variables {} {} and constraints {}\n",
+      cs.num_instance_variables(),
+      cs.num_witness_variables(),
+      cs.num_constraints(),
+    );
+
+    assert!(cs.is_satisfied().unwrap());
+    assert_eq!(cs.borrow().unwrap().instance_assignment[1..], public_inputs);
+  }
 }
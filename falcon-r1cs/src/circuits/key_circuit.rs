@@ -0,0 +1,175 @@
+use super::falcon_circuit::{alloc_public_key, const_q_power_vars};
+use crate::gadgets::*;
+use ark_bn254::Fr;
+use ark_ff::PrimeField;
+use ark_r1cs_std::{fields::fp::FpVar, prelude::*};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, Result};
+use falcon_rust::*;
+
+/**
+ * Proof of possession of the secret key behind a public key h, for the
+ * parameter set `P`: knowledge of f and g with f * h = g mod (x^N + 1, q),
+ * f invertible and ||(g, -f)||^2 < KEY_L2_BOUND.
+ * Public inputs: `[hash(pk)]`, i.e. `hash_from_pk(h)`
+ */
+#[derive(Clone, Debug)]
+pub struct FalconKeyPossessionCircuit<P: FalconParams = Falcon512> {
+  pk: PublicKey<P>,
+  basis: NtruBasis<P>,
+}
+
+impl<P: FalconParams> FalconKeyPossessionCircuit<P> {
+  /**
+   * Fails with the error of `SecretKey::try_basis` if `sk` does not decode
+   */
+  pub fn build_circuit(pk: PublicKey<P>, sk: SecretKey<P>) -> core::result::Result<Self, FalconError> {
+    Ok(Self { pk, basis: sk.try_basis()? })
+  }
+
+  /**
+   * Public inputs expected by the verifier: `[hash(pk)]`
+   */
+  pub fn public_inputs(&self) -> Vec<Fr> {
+    vec![hash_from_pk(&(&self.pk).into()).unwrap()]
+  }
+}
+
+impl<F: PrimeField, P: FalconParams> ConstraintSynthesizer<F> for FalconKeyPossessionCircuit<P> {
+  fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<()> {
+    let f = Polynomial::from(&self.basis.f);
+    let g = Polynomial::from(&self.basis.g);
+    enforce_key_possession(cs, &(&self.pk).into(), &f, &g, &NTTPolynomial::from(&g))
+  }
+}
+
+/*
+  Possession of f and g for h, with the NTT of g as a witness checked
+  against g
+*/
+fn enforce_key_possession<F: PrimeField, P: FalconParams>(
+  cs: ConstraintSystemRef<F>,
+  pk: &Polynomial<P>,
+  f: &Polynomial<P>,
+  g: &Polynomial<P>,
+  g_ntt: &NTTPolynomial<P>,
+) -> Result<()> {
+  let const_q_power_vars = const_q_power_vars::<F, P>(cs.clone())?;
+  let param_vars = ntt_param_var::<F, P>(cs.clone())?;
+
+  let (pk_vars, pk_ntt_vars) = alloc_public_key(cs.clone(), pk, &const_q_power_vars, &param_vars)?;
+  PoseidonVars::hash_pubic_key(cs.clone(), &pk_vars, AllocationMode::Input)?;
+
+  // f and g are witnesses, reduced mod q
+  let f_vars = PolyVar::<F>::alloc_vars(cs.clone(), f, AllocationMode::Witness)?;
+  let g_vars = PolyVar::<F>::alloc_vars(cs.clone(), g, AllocationMode::Witness)?;
+  for e in f_vars.coeff().iter().chain(g_vars.coeff()) {
+    enforce_less_than_q(cs.clone(), e)?;
+  }
+
+  let f_ntt_vars = NTTPolyVar::ntt_circuit(cs.clone(), &f_vars, &const_q_power_vars, &param_vars)?;
+  let g_ntt_vars = NTTPolyVar::<F>::alloc_vars(cs.clone(), g_ntt, AllocationMode::Witness)?;
+  NTTPolyVar::enforce_ntt(cs.clone(), &g_vars, &g_ntt_vars, &const_q_power_vars, &param_vars)?;
+
+  /*
+    f * h = g in NTT form. f = g = 0 would satisfy it, so every NTT
+    coefficient of f must be non-zero, i.e. f is invertible mod q.
+  */
+  let zero = FpVar::<F>::zero();
+  for i in 0..P::N {
+    f_ntt_vars.coeff()[i].enforce_not_equal(&zero)?;
+    enforce_mul_mod(
+      cs.clone(),
+      &f_ntt_vars.coeff()[i],
+      &pk_ntt_vars.coeff()[i],
+      &g_ntt_vars.coeff()[i],
+      &const_q_power_vars[0],
+    )?;
+  }
+
+  let l2_norm_var = l2_norm_var(
+    cs.clone(),
+    &[g_vars.coeff(), f_vars.coeff()].concat(),
+    &const_q_power_vars[0],
+  )?;
+
+  enforce_less_than_key_norm_bound(cs, &l2_norm_var)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use ark_relations::r1cs::ConstraintSystem;
+
+  #[test]
+  fn test_key_possession_r1cs() {
+    let keypair = KeyPair512::keygen_from_seed(b"falcon-r1cs test seed");
+    let basis = keypair.secret_key.basis();
+    let norm = basis.f.l2_norm() + basis.g.l2_norm();
+    assert!(norm < KEY_L2_BOUND);
+
+    let circuit = FalconKeyPossessionCircuit::build_circuit(keypair.public_key, keypair.secret_key.clone()).unwrap();
+    let public_inputs = circuit.public_inputs();
+
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    circuit.generate_constraints(cs.clone()).unwrap();
+    println!(
+      "number of variables {} {} and constraints {}\n",
+      cs.num_instance_variables(),
+      cs.num_witness_variables(),
+      cs.num_constraints(),
+    );
+
+    assert!(cs.is_satisfied().unwrap());
+    assert_eq!(cs.borrow().unwrap().instance_assignment[1..], public_inputs);
+
+    let other = KeyPair512::keygen_from_seed(b"another seed");
+    cs.borrow_mut().unwrap().instance_assignment[1] = hash_from_pk(&(&other.public_key).into()).unwrap();
+    assert!(!cs.is_satisfied().unwrap());
+  }
+
+  #[test]
+  fn test_key_possession_r1cs_wrong_key() {
+    let keypair = KeyPair512::keygen_from_seed(b"falcon-r1cs test seed");
+    let other = KeyPair512::keygen_from_seed(b"another seed");
+
+    let circuit = FalconKeyPossessionCircuit::build_circuit(keypair.public_key, other.secret_key.clone()).unwrap();
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    circuit.generate_constraints(cs.clone()).unwrap();
+    assert!(!cs.is_satisfied().unwrap());
+  }
+
+  #[test]
+  fn test_key_possession_r1cs_forged_ntt() {
+    let keypair = KeyPair512::keygen_from_seed(b"falcon-r1cs test seed");
+    let other = KeyPair512::keygen_from_seed(b"another seed");
+    let h = Polynomial::from(&keypair.public_key);
+    let basis = other.secret_key.basis();
+    let (f, g) = (Polynomial::from(&basis.f), Polynomial::from(&basis.g));
+
+    // the own f and g of a key, with the NTT of g
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    let other_h = Polynomial::from(&other.public_key);
+    enforce_key_possession(cs.clone(), &other_h, &f, &g, &NTTPolynomial::from(&g)).unwrap();
+    assert!(cs.is_satisfied().unwrap());
+
+    /*
+      f and g of another key are short and f is invertible, and the NTT of
+      g is forged to f_ntt * h_ntt, so only its check against g fails
+    */
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    let forged_g_ntt = NTTPolynomial::from(&f) * NTTPolynomial::from(&h);
+    enforce_key_possession(cs.clone(), &h, &f, &g, &forged_g_ntt).unwrap();
+    assert!(!cs.is_satisfied().unwrap());
+  }
+
+  #[test]
+  fn test_key_possession_malformed_secret_key() {
+    let keypair = KeyPair512::keygen_from_seed(b"falcon-r1cs test seed");
+    let mut bytes = keypair.secret_key.as_bytes().to_vec();
+
+    // f = 0 is well encoded, but not invertible: G cannot be recomputed
+    bytes[1..1 + 512 * Falcon512::SK_fg_BITS / 8].fill(0);
+    let sk = SecretKey512::from_bytes(&bytes).unwrap();
+    assert!(FalconKeyPossessionCircuit::build_circuit(keypair.public_key, sk).is_err());
+  }
+}
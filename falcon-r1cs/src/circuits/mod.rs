@@ -1,3 +1,9 @@
+mod batch_circuit;
 mod falcon_circuit;
+mod key_circuit;
+mod ring_circuit;
 
-pub use falcon_circuit::*;
\ No newline at end of file
+pub use batch_circuit::*;
+pub use falcon_circuit::*;
+pub use key_circuit::*;
+pub use ring_circuit::*;
@@ -0,0 +1,168 @@
+use super::falcon_circuit::const_q_power_vars;
+use crate::{gadgets::*, FalconVerificationCircuit, FalconVerificationWitness};
+use ark_bn254::Fr;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, Result};
+use arkworks_native_gadgets::poseidon::Poseidon;
+use arkworks_utils::Curve;
+use falcon_rust::*;
+
+/**
+ * Verification of a Falcon signature under some key of a set, for the
+ * parameter set `P`. The key is a witness whose `hash_from_pk` must be a
+ * leaf of a `PoseidonMerkleTree` of depth `DEPTH`.
+ * Public inputs: `[root, hash(hm_ntt)]`
+ */
+#[derive(Clone)]
+pub struct FalconRingVerificationCircuit<const DEPTH: usize, P: FalconParams = Falcon512> {
+  circuit: FalconVerificationCircuit<P>,
+  path: PoseidonMerklePath<DEPTH>,
+}
+
+impl<const DEPTH: usize, P: FalconParams> FalconRingVerificationCircuit<DEPTH, P> {
+  /**
+   * `path` is the membership proof of `hash_from_pk(pk)` in the key set
+   */
+  pub fn build_circuit(pk: PublicKey<P>, msg: Vec<u8>, sig: Signature<P>, path: PoseidonMerklePath<DEPTH>) -> Self {
+    Self { circuit: FalconVerificationCircuit::build_circuit(pk, msg, sig), path }
+  }
+
+  /**
+   * Public inputs expected by the verifier, in allocation order. The root
+   * is computed from the key along the path: for a key that is not on the
+   * path, it is not the root of the key set and the proof does not verify
+   * against it.
+   */
+  pub fn public_inputs(&self) -> Vec<Fr> {
+    let hasher = Poseidon::<Fr>::new(setup_params(Curve::Bn254, 5, 3));
+    let leaf = hash_from_pk(&(&self.circuit.pk).into()).unwrap();
+    let hm_ntt = NTTPolynomial::<P>::from_hash_of_message(&self.circuit.msg, self.circuit.sig.nonce());
+    vec![
+      self.path.calculate_root(&leaf, &hasher).unwrap(),
+      hash_from_poly(&hm_ntt).unwrap(),
+    ]
+  }
+}
+
+impl<const DEPTH: usize, P: FalconParams> ConstraintSynthesizer<Fr> for FalconRingVerificationCircuit<DEPTH, P> {
+  fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<()> {
+    let FalconVerificationCircuit { pk, msg, sig, .. } = &self.circuit;
+    enforce_member_signature(cs, &FalconVerificationWitness::from_signature(pk, msg, sig), &self.path)
+  }
+}
+
+/*
+  Ring verification over the raw witness of the signature
+*/
+fn enforce_member_signature<const DEPTH: usize, P: FalconParams>(
+  cs: ConstraintSystemRef<Fr>,
+  witness: &FalconVerificationWitness<Fr, P>,
+  path: &PoseidonMerklePath<DEPTH>,
+) -> Result<()> {
+  let const_q_power_vars = const_q_power_vars::<Fr, P>(cs.clone())?;
+  let param_vars = ntt_param_var::<Fr, P>(cs.clone())?;
+
+  let (pk_vars, _, hm_ntt_vars) = witness.enforce_verification(cs.clone(), &const_q_power_vars, &param_vars)?;
+
+  // the key is only revealed as a member of the tree
+  let leaf = PoseidonVars::hash_pubic_key(cs.clone(), &pk_vars, AllocationMode::Witness)?;
+  let _root = merkle_root_var(cs.clone(), path, leaf.coeff(), AllocationMode::Input)?;
+
+  PoseidonVars::hash_poly(cs, &hm_ntt_vars, AllocationMode::Input)?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use ark_relations::r1cs::ConstraintSystem;
+
+  #[test]
+  fn test_ring_verification_r1cs() {
+    let keys: Vec<KeyPair> = (0..3u8).map(|i| KeyPair::keygen_from_seed(&[i; 48])).collect();
+    let leaves: Vec<Fr> = keys
+      .iter()
+      .map(|keypair| hash_from_pk(&(&keypair.public_key).into()).unwrap())
+      .collect();
+    let tree = merkle_tree::<3>(&leaves).unwrap();
+
+    let msg = b"signed by one of us";
+    let sig = keys[1].secret_key.sign(msg);
+    let circuit = FalconRingVerificationCircuit::build_circuit(
+      keys[1].public_key,
+      msg.to_vec(),
+      sig,
+      tree.generate_membership_proof(1),
+    );
+    let public_inputs = circuit.public_inputs();
+    assert_eq!(public_inputs[0], tree.root());
+
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    circuit.generate_constraints(cs.clone()).unwrap();
+    println!(
+      "number of variables {} {} and constraints {}\n",
+      cs.num_instance_variables(),
+      cs.num_witness_variables(),
+      cs.num_constraints(),
+    );
+
+    assert!(cs.is_satisfied().unwrap());
+    assert_eq!(cs.borrow().unwrap().instance_assignment[1..], public_inputs);
+
+    // another key set
+    cs.borrow_mut().unwrap().instance_assignment[1] = merkle_tree::<3>(&leaves[..2]).unwrap().root();
+    assert!(!cs.is_satisfied().unwrap());
+  }
+
+  #[test]
+  fn test_ring_verification_r1cs_non_member() {
+    let keys: Vec<KeyPair> = (0..3u8).map(|i| KeyPair::keygen_from_seed(&[i; 48])).collect();
+    let leaves: Vec<Fr> = keys[..2]
+      .iter()
+      .map(|keypair| hash_from_pk(&(&keypair.public_key).into()).unwrap())
+      .collect();
+    let tree = merkle_tree::<3>(&leaves).unwrap();
+
+    // a valid signature under a key outside of the set
+    let msg = b"signed by one of us";
+    let sig = keys[2].secret_key.sign(msg);
+    let circuit = FalconRingVerificationCircuit::build_circuit(
+      keys[2].public_key,
+      msg.to_vec(),
+      sig,
+      tree.generate_membership_proof(1),
+    );
+
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    circuit.generate_constraints(cs.clone()).unwrap();
+    assert!(!cs.is_satisfied().unwrap());
+  }
+
+  #[test]
+  fn test_ring_verification_r1cs_forged_signature() {
+    let keys: Vec<KeyPair> = (0..3u8).map(|i| KeyPair::keygen_from_seed(&[i; 48])).collect();
+    let leaves: Vec<Fr> = keys
+      .iter()
+      .map(|keypair| hash_from_pk(&(&keypair.public_key).into()).unwrap())
+      .collect();
+    let tree = merkle_tree::<3>(&leaves).unwrap();
+    let path = tree.generate_membership_proof(1);
+
+    // a member key, and a message none of the keys signed
+    let sig = keys[2].secret_key.sign(b"signed by one of us");
+    let mut witness = FalconVerificationWitness::<Fr>::from_signature(&keys[1].public_key, b"never signed", &sig);
+
+    /*
+      s2 = v = 0, with a zero norm. hm_ntt is then the remainder of
+      v_ntt + s2_ntt * pk_ntt = 0, and only the range checks of the
+      quotients -hm_ntt / q fail.
+    */
+    witness.s2 = vec![Fr::from(0u8); 512];
+    witness.v = vec![Fr::from(0u8); 512];
+
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    enforce_member_signature(cs.clone(), &witness, &path).unwrap();
+    assert!(!cs.is_satisfied().unwrap());
+    assert_eq!(cs.borrow().unwrap().instance_assignment[1], tree.root());
+  }
+}
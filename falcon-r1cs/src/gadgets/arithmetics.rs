@@ -5,39 +5,75 @@ use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
 use falcon_rust::MODULUS;
 use num_bigint::BigUint;
 
+/*
+  Remainder witness by q of the value a_val, which is only evaluated out of
+  setup mode
+*/
+fn alloc_remainder<F: PrimeField>(
+  cs: ConstraintSystemRef<F>,
+  a_val: impl FnOnce() -> Result<F, SynthesisError>,
+) -> Result<FpVar<F>, SynthesisError> {
+  FpVar::<F>::new_witness(cs, || {
+    let a_int: BigUint = a_val()?.into();
+    let modulus_int: BigUint = F::from(MODULUS).into();
+    Ok(F::from(&a_int % &modulus_int))
+  })
+}
+
+/*
+  Quotient witness of a by q for the remainder c, i.e. (a - c) / q in the
+  field. It is the integer quotient when c = a mod q, and any other c gives
+  a quotient the callers reject: a free quotient would satisfy
+  a - t * q = c for any c, since t can be any field element.
+*/
+fn alloc_quotient<F: PrimeField>(
+  cs: ConstraintSystemRef<F>,
+  a: &FpVar<F>,
+  c: &FpVar<F>,
+) -> Result<FpVar<F>, SynthesisError> {
+  FpVar::<F>::new_witness(cs, || Ok((a.value()? - c.value()?) * F::from(MODULUS).inverse().unwrap()))
+}
+
+/**
+ * a mod q, for a < 2^quotient_bits * q. The quotient is checked to fit in
+ * `quotient_bits`, which must leave t * q + c below the field modulus.
+ */
 #[allow(dead_code)]
 pub fn mod_q<F: PrimeField>(
   cs: ConstraintSystemRef<F>,
   a: &FpVar<F>,
-  modulus_var: &FpVar<F>
+  modulus_var: &FpVar<F>,
+  quotient_bits: usize,
 ) -> Result<FpVar<F>, SynthesisError> {
-  let a_val = if cs.is_in_setup_mode() {
-    F::one()
-  } else {
-    a.value()?
-  };
-
-  let a_int: BigUint = a_val.into();
-
-  let modulus_int: BigUint = F::from(MODULUS).into();
-  let t_int = &a_int / &modulus_int;
-  let b_int = &a_int % &modulus_int;
-
-  let t_val = F::from(t_int);
-  let b_val = F::from(b_int);
+  let b_var = alloc_remainder(cs.clone(), || a.value())?;
+  enforce_mod_q(cs, a, &b_var, modulus_var, quotient_bits)?;
+  Ok(b_var)
+}
 
-  let t_var = FpVar::<F>::new_witness(cs.clone(), || Ok(t_val))?;
-  let b_var = FpVar::<F>::new_witness(cs.clone(), || Ok(b_val))?;
+/**
+ * Enforces b = a mod q, see `mod_q`, for a remainder b of the caller
+ */
+pub(crate) fn enforce_mod_q<F: PrimeField>(
+  cs: ConstraintSystemRef<F>,
+  a: &FpVar<F>,
+  b_var: &FpVar<F>,
+  modulus_var: &FpVar<F>,
+  quotient_bits: usize,
+) -> Result<(), SynthesisError> {
+  let t_var = alloc_quotient(cs.clone(), a, b_var)?;
 
-  let t_12289 = t_var * modulus_var;
+  let t_12289 = &t_var * modulus_var;
   let left = a - t_12289;
-  left.enforce_equal(&b_var)?;
-
-  enforce_less_than_q(cs, &b_var)?;
+  left.enforce_equal(b_var)?;
 
-  Ok(b_var)
+  enforce_bit_length(cs.clone(), &t_var, quotient_bits)?;
+  enforce_less_than_q(cs, b_var)
 }
 
+/**
+ * a + b mod q, for a < q and b <= (q - 1)^2, e.g. a product of two
+ * reduced values
+ */
 #[allow(dead_code)]
 pub(crate) fn add_mod<F: PrimeField>(
   cs: ConstraintSystemRef<F>,
@@ -45,40 +81,145 @@ pub(crate) fn add_mod<F: PrimeField>(
   b: &FpVar<F>,
   modulus_var: &FpVar<F>
 ) -> Result<FpVar<F>, SynthesisError> {
-  let a_val = if cs.is_in_setup_mode() {
-    F::one()
-  } else {
-    a.value()?
-  };
-
-  let b_val = if cs.is_in_setup_mode() {
-    F::one()
-  } else {
-    b.value()?
-  };
-
-  let ab_val = a_val + b_val;
-  let ab_int: BigUint = ab_val.into();
-
-  let modulus_int: BigUint = F::from(MODULUS).into();
-  let c_int = &ab_int % &modulus_int;
-  let t_int = (&ab_int - &c_int) / &modulus_int;
-
-  let t_val = F::from(t_int);
-  let c_val = F::from(c_int);
+  let c_var = alloc_remainder(cs.clone(), || Ok(a.value()? + b.value()?))?;
+  enforce_add_mod(cs, a, b, &c_var, modulus_var)?;
+  Ok(c_var)
+}
 
-  // cast the variables
-  let t_var = FpVar::<F>::new_witness(cs.clone(), || Ok(t_val))?;
-  let c_var = FpVar::<F>::new_witness(cs.clone(), || Ok(c_val))?;
+/**
+ * Enforces c = a + b mod q, see `add_mod`, for a remainder c of the caller
+ */
+pub(crate) fn enforce_add_mod<F: PrimeField>(
+  cs: ConstraintSystemRef<F>,
+  a: &FpVar<F>,
+  b: &FpVar<F>,
+  c_var: &FpVar<F>,
+  modulus_var: &FpVar<F>
+) -> Result<(), SynthesisError> {
+  let ab_var = a + b;
+  let t_var = alloc_quotient(cs.clone(), &ab_var, c_var)?;
 
   // (1) a + b - t * 12289 = c
-  let ab_var = a + b;
-  let t_q = t_var * modulus_var;
+  let t_q = &t_var * modulus_var;
   let left = ab_var - t_q;
-  left.enforce_equal(&c_var)?;
+  left.enforce_equal(c_var)?;
 
-  // (2) c < 12289
-  enforce_less_than_q(cs, &c_var)?;
+  // (2) t < 12289, as a + b <= q * (q - 1), and c < 12289
+  enforce_less_than_q(cs.clone(), &t_var)?;
+  enforce_less_than_q(cs, c_var)
+}
 
+/**
+ * a * b mod q. The quotient is range checked as well, so a and b must be
+ * less than q.
+ */
+#[allow(dead_code)]
+pub(crate) fn mul_mod<F: PrimeField>(
+  cs: ConstraintSystemRef<F>,
+  a: &FpVar<F>,
+  b: &FpVar<F>,
+  modulus_var: &FpVar<F>
+) -> Result<FpVar<F>, SynthesisError> {
+  let c_var = alloc_remainder(cs.clone(), || Ok(a.value()? * b.value()?))?;
+  enforce_mul_mod(cs, a, b, &c_var, modulus_var)?;
   Ok(c_var)
-}
\ No newline at end of file
+}
+
+/**
+ * Enforces c = a * b mod q, see `mul_mod`, for a remainder c of the caller
+ */
+pub(crate) fn enforce_mul_mod<F: PrimeField>(
+  cs: ConstraintSystemRef<F>,
+  a: &FpVar<F>,
+  b: &FpVar<F>,
+  c_var: &FpVar<F>,
+  modulus_var: &FpVar<F>
+) -> Result<(), SynthesisError> {
+  let ab_var = a * b;
+  let t_var = alloc_quotient(cs.clone(), &ab_var, c_var)?;
+
+  // a * b - t * 12289 = c, with t < (q - 1)^2 / q < q and c < q
+  (ab_var - t_var.clone() * modulus_var).enforce_equal(c_var)?;
+  enforce_less_than_q(cs.clone(), &t_var)?;
+  enforce_less_than_q(cs, c_var)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use ark_bn254::Fr;
+  use ark_relations::r1cs::{ConstraintSystem, Result};
+
+  type Reduction = fn(ConstraintSystemRef<Fr>, &FpVar<Fr>, &FpVar<Fr>, &FpVar<Fr>) -> Result<FpVar<Fr>>;
+  type Enforce = fn(ConstraintSystemRef<Fr>, &FpVar<Fr>, &FpVar<Fr>, &FpVar<Fr>, &FpVar<Fr>) -> Result<()>;
+
+  fn alloc(a: u64, b: u64) -> (ConstraintSystemRef<Fr>, FpVar<Fr>, FpVar<Fr>, FpVar<Fr>) {
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    let a = FpVar::new_witness(cs.clone(), || Ok(Fr::from(a))).unwrap();
+    let b = FpVar::new_witness(cs.clone(), || Ok(Fr::from(b))).unwrap();
+    let modulus_var = FpVar::new_constant(cs.clone(), Fr::from(MODULUS)).unwrap();
+    (cs, a, b, modulus_var)
+  }
+
+  /*
+    Reduces a + b, or a * b. Returns the remainder and whether the
+    constraints hold.
+  */
+  fn reduce(reduction: Reduction, a: u64, b: u64) -> (Fr, bool) {
+    let (cs, a, b, modulus_var) = alloc(a, b);
+    let c = reduction(cs.clone(), &a, &b, &modulus_var).unwrap();
+    (c.value().unwrap(), cs.is_satisfied().unwrap())
+  }
+
+  /*
+    Malicious prover: enforces the reduction of a + b, or a * b, to the
+    remainder c of its choice. Returns whether the constraints hold.
+  */
+  fn forge(enforce: Enforce, a: u64, b: u64, c: u16) -> bool {
+    let (cs, a, b, modulus_var) = alloc(a, b);
+    let c = FpVar::new_witness(cs.clone(), || Ok(Fr::from(c))).unwrap();
+    enforce(cs.clone(), &a, &b, &c, &modulus_var).unwrap();
+    cs.is_satisfied().unwrap()
+  }
+
+  fn mod_q_14(cs: ConstraintSystemRef<Fr>, a: &FpVar<Fr>, _: &FpVar<Fr>, modulus_var: &FpVar<Fr>) -> Result<FpVar<Fr>> {
+    mod_q(cs, a, modulus_var, 14)
+  }
+
+  fn enforce_mod_q_14(
+    cs: ConstraintSystemRef<Fr>,
+    a: &FpVar<Fr>,
+    _: &FpVar<Fr>,
+    c: &FpVar<Fr>,
+    modulus_var: &FpVar<Fr>,
+  ) -> Result<()> {
+    enforce_mod_q(cs, a, c, modulus_var, 14)
+  }
+
+  #[test]
+  fn test_mod_q() {
+    assert_eq!(reduce(mod_q_14, 20000, 0), (Fr::from(7711u16), true));
+    assert_eq!(reduce(mod_q_14, (1 << 14) * 12289 - 1, 0), (Fr::from(12288u16), true));
+    assert!(!reduce(mod_q_14, (1 << 14) * 12289, 0).1);
+
+    // a forged remainder needs a quotient outside of its 14 bits
+    assert!(forge(enforce_mod_q_14, 20000, 0, 7711));
+    assert!(!forge(enforce_mod_q_14, 20000, 0, 5));
+  }
+
+  #[test]
+  fn test_add_mod() {
+    let b = 12288 * 12288;
+    assert_eq!(reduce(add_mod, 12288, b), (Fr::from((12288 + b) % 12289), true));
+    assert!(forge(enforce_add_mod, 12288, b, ((12288 + b) % 12289) as u16));
+    assert!(!forge(enforce_add_mod, 0, 0, 5));
+    assert!(!forge(enforce_add_mod, 12288, b, 5));
+  }
+
+  #[test]
+  fn test_mul_mod() {
+    assert_eq!(reduce(mul_mod, 12288, 12000), (Fr::from(12288 * 12000 % 12289u64), true));
+    assert!(forge(enforce_mul_mod, 12288, 12000, (12288 * 12000 % 12289u64) as u16));
+    assert!(!forge(enforce_mul_mod, 12288, 12000, 5));
+  }
+}
@@ -0,0 +1,201 @@
+use crate::{enforce_less_than_q, hash_chain_var, poseidon_gadget, shake256_var, PolyVar};
+use ark_ff::{FpParameters, PrimeField};
+use ark_r1cs_std::{fields::fp::FpVar, prelude::*, uint8::UInt8};
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+use falcon_rust::{FalconParams, MODULUS, MODULUS_THRESHOLD};
+
+/**
+ * In-circuit counterpart of `Polynomial::from_hash_of_message`
+ */
+pub fn hash_to_point_var<F: PrimeField, P: FalconParams>(
+  cs: ConstraintSystemRef<F>,
+  nonce: &[UInt8<F>],
+  message: &[UInt8<F>],
+  modulus_var: &FpVar<F>,
+) -> Result<PolyVar<F>, SynthesisError> {
+  hash_to_point_var_with_len(cs, nonce, message, modulus_var, P::N)
+}
+
+/*
+  Squeezes 3n bytes and parses them as 16-bit big-endian samples. Samples
+  below 61445 are accepted and reduced mod q, and the first n accepted ones
+  form the output.
+
+  The output coefficients are witnesses. They are bound to the accepted
+  samples with a polynomial identity at a point z derived in-circuit from a
+  Poseidon hash of the output and the hashed bytes:
+
+    sum_i res[i] * z^i == sum_j used[j] * r[j] * z^(count of used samples before j)
+*/
+pub(crate) fn hash_to_point_var_with_len<F: PrimeField>(
+  cs: ConstraintSystemRef<F>,
+  nonce: &[UInt8<F>],
+  message: &[UInt8<F>],
+  modulus_var: &FpVar<F>,
+  n: usize,
+) -> Result<PolyVar<F>, SynthesisError> {
+  let input = [nonce, message].concat();
+  let buffer = shake256_var(&input, n * 3)?;
+
+  let mut sample_vals = Vec::new();
+  let mut accepted_vars = Vec::new();
+  let mut reduced_vars = Vec::new();
+  for pair in buffer.chunks(2) {
+    let bits = [pair[1].to_bits_le()?, pair[0].to_bits_le()?].concat();
+    let sample_var = Boolean::le_bits_to_fp_var(&bits)?;
+
+    let sample_val = if cs.is_in_setup_mode() {
+      0u16
+    } else {
+      sample_var.value()?.into_repr().as_ref()[0] as u16
+    };
+
+    accepted_vars.push(is_less_than_threshold(&bits)?);
+    reduced_vars.push(reduce_sample(cs.clone(), &sample_var, sample_val, modulus_var)?);
+    sample_vals.push(sample_val);
+  }
+
+  // only the first n accepted samples are used
+  let n_var = FpVar::<F>::constant(F::from(n as u64));
+  let mut count = FpVar::<F>::zero();
+  let mut used_vars = Vec::new();
+  for accepted in accepted_vars.iter() {
+    let done = count.is_eq(&n_var)?;
+    let used = accepted.and(&done.not())?;
+    count += FpVar::<F>::from(used.clone());
+    used_vars.push(used);
+  }
+  count.enforce_equal(&n_var)?;
+
+  let mut res = vec![0u16; n];
+  if !cs.is_in_setup_mode() {
+    let mut i = 0;
+    for &sample in sample_vals.iter() {
+      if i < n && sample < MODULUS_THRESHOLD {
+        res[i] = sample % MODULUS;
+        i += 1;
+      }
+    }
+  }
+  let res_vars = res
+    .iter()
+    .map(|x| FpVar::<F>::new_witness(cs.clone(), || Ok(F::from(*x))))
+    .collect::<Result<Vec<_>, _>>()?;
+
+  let mut challenge_inputs = res_vars.clone();
+  let input_bits = input
+    .iter()
+    .map(|x| x.to_bits_le())
+    .collect::<Result<Vec<_>, _>>()?
+    .concat();
+  for chunk in input_bits.chunks(8 * (F::Params::CAPACITY as usize / 8)) {
+    challenge_inputs.push(Boolean::le_bits_to_fp_var(chunk)?);
+  }
+  let mut cs_ref = cs.clone();
+  let hasher = poseidon_gadget(&mut cs_ref)?;
+  let z = hash_chain_var(&hasher, &challenge_inputs)?;
+
+  let z_minus_one = &z - F::one();
+  let mut power = FpVar::<F>::one();
+  let mut lhs = FpVar::<F>::zero();
+  for (used, reduced) in used_vars.iter().zip(reduced_vars.iter()) {
+    let used_power = &FpVar::<F>::from(used.clone()) * &power;
+    lhs += &used_power * reduced;
+    power += &used_power * &z_minus_one;
+  }
+
+  let mut rhs = res_vars[n - 1].clone();
+  for e in res_vars.iter().rev().skip(1) {
+    rhs = &rhs * &z + e;
+  }
+  lhs.enforce_equal(&rhs)?;
+
+  Ok(PolyVar::new(res_vars))
+}
+
+/*
+  61445 = 0b1111_0000_0000_0101, so a 16-bit sample is rejected iff its top
+  four bits are set and its low twelve bits are at least 5.
+*/
+fn is_less_than_threshold<F: PrimeField>(bits: &[Boolean<F>]) -> Result<Boolean<F>, SynthesisError> {
+  let low_at_least_5 = Boolean::kary_or(bits[3..12].as_ref())?
+    .or(&bits[2].and(&bits[0].or(&bits[1])?)?)?;
+  let rejected = Boolean::kary_and(bits[12..16].as_ref())?.and(&low_at_least_5)?;
+
+  Ok(rejected.not())
+}
+
+/*
+  sample = t * q + r with t < 8 and r < q
+*/
+fn reduce_sample<F: PrimeField>(
+  cs: ConstraintSystemRef<F>,
+  sample: &FpVar<F>,
+  sample_val: u16,
+  modulus_var: &FpVar<F>,
+) -> Result<FpVar<F>, SynthesisError> {
+  let t_val = sample_val / MODULUS;
+  let r_val = sample_val % MODULUS;
+
+  let t_bit_vars = (0..3)
+    .map(|i| Boolean::new_witness(cs.clone(), || Ok((t_val >> i) & 1 == 1)))
+    .collect::<Result<Vec<_>, _>>()?;
+  let t_var = Boolean::le_bits_to_fp_var(&t_bit_vars)?;
+  let r_var = FpVar::<F>::new_witness(cs.clone(), || Ok(F::from(r_val)))?;
+
+  (sample - &t_var * modulus_var).enforce_equal(&r_var)?;
+  enforce_less_than_q(cs, &r_var)?;
+
+  Ok(r_var)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use ark_bn254::Fr;
+  use ark_relations::r1cs::ConstraintSystem;
+  use falcon_rust::Polynomial512;
+
+  #[test]
+  fn test_hash_to_point_var() {
+    let nonce = [7u8; 40];
+    let message = [42u8; 200];
+    let n = 16;
+
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    let nonce_vars = UInt8::new_witness_vec(cs.clone(), &nonce).unwrap();
+    let message_vars = UInt8::new_witness_vec(cs.clone(), &message).unwrap();
+    let modulus_var = FpVar::<Fr>::new_constant(cs.clone(), Fr::from(MODULUS)).unwrap();
+
+    let res = hash_to_point_var_with_len(cs.clone(), &nonce_vars, &message_vars, &modulus_var, n).unwrap();
+    assert!(cs.is_satisfied().unwrap());
+
+    let expected = Polynomial512::from_hash_of_message(&message, &nonce);
+    for (var, e) in res.coeff().iter().zip(expected.coeff().iter()) {
+      assert_eq!(var.value().unwrap(), Fr::from(*e));
+    }
+  }
+
+  #[test]
+  fn test_hash_to_point_var_rejects_wrong_output() {
+    let nonce = [1u8; 40];
+    let message = b"Testing message";
+    let n = 16;
+
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    let nonce_vars = UInt8::new_witness_vec(cs.clone(), &nonce).unwrap();
+    let message_vars = UInt8::new_witness_vec(cs.clone(), message).unwrap();
+    let modulus_var = FpVar::<Fr>::new_constant(cs.clone(), Fr::from(MODULUS)).unwrap();
+
+    let res = hash_to_point_var_with_len(cs.clone(), &nonce_vars, &message_vars, &modulus_var, n).unwrap();
+
+    // swap two output coefficients in the witness
+    let index_of = |var: &FpVar<Fr>| match var {
+      FpVar::Var(v) => v.variable.get_index_unchecked(0).unwrap(),
+      _ => unreachable!(),
+    };
+    let (a, b) = (index_of(&res.coeff()[0]), index_of(&res.coeff()[1]));
+    cs.borrow_mut().unwrap().witness_assignment.swap(a, b);
+    assert!(!cs.is_satisfied().unwrap());
+  }
+}
@@ -0,0 +1,133 @@
+use ark_ff::PrimeField;
+use ark_r1cs_std::{prelude::*, uint8::UInt8};
+use ark_relations::r1cs::SynthesisError;
+
+use falcon_rust::{KECCAK_RC, KECCAK_RHO, SHAKE256_RATE};
+
+/**
+ * A 64-bit Keccak lane as little-endian bits
+ */
+type LaneVar<F> = Vec<Boolean<F>>;
+
+fn xor_lane<F: PrimeField>(a: &LaneVar<F>, b: &LaneVar<F>) -> Result<LaneVar<F>, SynthesisError> {
+  a.iter().zip(b.iter()).map(|(x, y)| x.xor(y)).collect()
+}
+
+fn rotl_lane<F: PrimeField>(a: &LaneVar<F>, by: usize) -> LaneVar<F> {
+  (0..64).map(|i| a[(i + 64 - by) % 64].clone()).collect()
+}
+
+/**
+ * Keccak-f[1600] permutation over 25 lanes
+ */
+pub fn keccak_f1600_var<F: PrimeField>(state: &mut [LaneVar<F>]) -> Result<(), SynthesisError> {
+  if state.len() != 25 {
+    panic!("Invalid state length: {}", state.len());
+  }
+
+  for rc in KECCAK_RC.iter() {
+    // theta
+    let mut c = Vec::with_capacity(5);
+    for x in 0..5 {
+      let mut tmp = xor_lane(&state[x], &state[x + 5])?;
+      for y in 2..5 {
+        tmp = xor_lane(&tmp, &state[x + 5 * y])?;
+      }
+      c.push(tmp);
+    }
+    for x in 0..5 {
+      let d = xor_lane(&c[(x + 4) % 5], &rotl_lane(&c[(x + 1) % 5], 1))?;
+      for y in 0..5 {
+        state[x + 5 * y] = xor_lane(&state[x + 5 * y], &d)?;
+      }
+    }
+
+    // rho and pi
+    let mut b = vec![Vec::new(); 25];
+    for x in 0..5 {
+      for y in 0..5 {
+        b[y + 5 * ((2 * x + 3 * y) % 5)] = rotl_lane(&state[x + 5 * y], KECCAK_RHO[x + 5 * y] as usize);
+      }
+    }
+
+    // chi
+    for x in 0..5 {
+      for y in 0..5 {
+        let lane = b[x + 5 * y]
+          .iter()
+          .zip(b[(x + 1) % 5 + 5 * y].iter())
+          .zip(b[(x + 2) % 5 + 5 * y].iter())
+          .map(|((a, n), m)| a.xor(&n.not().and(m)?))
+          .collect::<Result<Vec<_>, _>>()?;
+        state[x + 5 * y] = lane;
+      }
+    }
+
+    // iota
+    for (i, e) in state[0].iter_mut().enumerate() {
+      if (rc >> i) & 1 == 1 {
+        *e = e.not();
+      }
+    }
+  }
+
+  Ok(())
+}
+
+/**
+ * SHAKE256 over byte vars, squeezing `output_len` bytes
+ */
+pub fn shake256_var<F: PrimeField>(
+  input: &[UInt8<F>],
+  output_len: usize,
+) -> Result<Vec<UInt8<F>>, SynthesisError> {
+  let mut padded = input.to_vec();
+  padded.push(UInt8::constant(0x1F));
+  padded.resize(padded.len().div_ceil(SHAKE256_RATE) * SHAKE256_RATE, UInt8::constant(0));
+  let last = padded.len() - 1;
+  padded[last] = padded[last].xor(&UInt8::constant(0x80))?;
+
+  let mut state: Vec<LaneVar<F>> = vec![vec![Boolean::FALSE; 64]; 25];
+
+  for block in padded.chunks(SHAKE256_RATE) {
+    for (i, byte) in block.iter().enumerate() {
+      let lane = &mut state[i / 8];
+      let offset = (i % 8) * 8;
+      for (j, bit) in byte.to_bits_le()?.iter().enumerate() {
+        lane[offset + j] = lane[offset + j].xor(bit)?;
+      }
+    }
+    keccak_f1600_var(&mut state)?;
+  }
+
+  let mut output = Vec::with_capacity(output_len);
+  loop {
+    for i in 0..SHAKE256_RATE.min(output_len - output.len()) {
+      let offset = (i % 8) * 8;
+      output.push(UInt8::from_bits_le(&state[i / 8][offset..offset + 8]));
+    }
+    if output.len() == output_len {
+      return Ok(output);
+    }
+    keccak_f1600_var(&mut state)?;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use ark_bn254::Fr;
+  use ark_relations::r1cs::ConstraintSystem;
+
+  #[test]
+  fn test_shake256_var() {
+    let expected = hex::decode("483366601360a8771c6863080cc4114d8db44530f8f1e1ee4f94ea37e78b5739").unwrap();
+
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    let input = UInt8::new_witness_vec(cs.clone(), b"abc").unwrap();
+    let output = shake256_var(&input, 32).unwrap();
+
+    assert_eq!(output.value().unwrap(), expected);
+    assert!(cs.is_satisfied().unwrap());
+  }
+}
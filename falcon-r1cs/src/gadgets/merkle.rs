@@ -0,0 +1,113 @@
+use crate::{poseidon_gadget, setup_params};
+use ark_bn254::Fr;
+use ark_ff::PrimeField;
+use ark_r1cs_std::{fields::fp::FpVar, prelude::*};
+use ark_relations::r1cs::{Namespace, SynthesisError};
+use arkworks_native_gadgets::{
+  merkle_tree::{Path, SparseMerkleTree},
+  poseidon::Poseidon,
+};
+use arkworks_r1cs_gadgets::{merkle_tree::PathVar, poseidon::PoseidonGadget};
+use arkworks_utils::Curve;
+use std::collections::BTreeMap;
+
+// empty leaves are zero
+const EMPTY_LEAF: [u8; 32] = [0u8; 32];
+
+/**
+ * Poseidon Merkle tree of depth `DEPTH`, with the parameters of `hash_chain`
+ */
+pub type PoseidonMerkleTree<const DEPTH: usize> = SparseMerkleTree<Fr, Poseidon<Fr>, DEPTH>;
+
+/**
+ * Sibling pairs from a leaf up to the root of a `PoseidonMerkleTree`
+ */
+pub type PoseidonMerklePath<const DEPTH: usize> = Path<Fr, Poseidon<Fr>, DEPTH>;
+
+/**
+ * Tree over `leaves`, in order, e.g. the `hash_from_pk` values of a key set.
+ * More than 2^DEPTH leaves fail with `Unsatisfiable`.
+ */
+pub fn merkle_tree<const DEPTH: usize>(leaves: &[Fr]) -> Result<PoseidonMerkleTree<DEPTH>, SynthesisError> {
+  if leaves.len() > 1 << DEPTH {
+    return Err(SynthesisError::Unsatisfiable);
+  }
+
+  /*
+    `SparseMerkleTree::new` only accepts up to 2^(DEPTH-1) leaves although
+    the tree has 2^DEPTH of them, so the leaves are inserted into an empty tree
+  */
+  let hasher = Poseidon::<Fr>::new(setup_params(Curve::Bn254, 5, 3));
+  let mut tree = PoseidonMerkleTree::new(&BTreeMap::new(), &hasher, &EMPTY_LEAF).unwrap();
+  let leaves: BTreeMap<u32, Fr> = leaves.iter().enumerate().map(|(i, leaf)| (i as u32, *leaf)).collect();
+  tree.insert_batch(&leaves, &hasher).unwrap();
+  Ok(tree)
+}
+
+/**
+ * Allocates the root of the tree containing `leaf` with `mode`, enforcing
+ * it equal to the root computed in-circuit along `path`, a witness
+ */
+pub fn merkle_root_var<F: PrimeField, const DEPTH: usize>(
+  cs: impl Into<Namespace<F>>,
+  path: &Path<F, Poseidon<F>, DEPTH>,
+  leaf: &FpVar<F>,
+  mode: AllocationMode,
+) -> Result<FpVar<F>, SynthesisError> {
+  let ns = cs.into();
+  let mut cs = ns.cs();
+
+  let hasher = poseidon_gadget(&mut cs)?;
+  let path_vars = PathVar::<F, PoseidonGadget<F>, DEPTH>::new_witness(cs.clone(), || Ok(path))?;
+  let root = path_vars.root_hash(leaf, &hasher)?;
+
+  let res = FpVar::new_variable(cs, || root.value(), mode)?;
+  res.enforce_equal(&root)?;
+
+  Ok(res)
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use ark_relations::r1cs::ConstraintSystem;
+
+  #[test]
+  fn test_merkle_membership() {
+    let leaves: Vec<Fr> = (1..9u64).map(Fr::from).collect();
+    let tree = merkle_tree::<3>(&leaves).unwrap();
+    let hasher = Poseidon::<Fr>::new(setup_params(Curve::Bn254, 5, 3));
+
+    for (i, leaf) in leaves.iter().enumerate() {
+      let path = tree.generate_membership_proof(i as u64);
+      assert_eq!(path.calculate_root(leaf, &hasher).unwrap(), tree.root());
+
+      let cs = ConstraintSystem::<Fr>::new_ref();
+      let leaf_var = FpVar::new_witness(cs.clone(), || Ok(*leaf)).unwrap();
+      let root_var = merkle_root_var(cs.clone(), &path, &leaf_var, AllocationMode::Input).unwrap();
+      assert_eq!(root_var.value().unwrap(), tree.root());
+      assert!(cs.is_satisfied().unwrap());
+    }
+
+    // a leaf that is not in the tree
+    let path = tree.generate_membership_proof(0);
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    let leaf_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(100u8))).unwrap();
+    let _root = merkle_root_var(cs.clone(), &path, &leaf_var, AllocationMode::Input).unwrap();
+    assert!(!cs.is_satisfied().unwrap());
+  }
+
+  #[test]
+  fn test_merkle_tree_too_many_leaves() {
+    assert!(merkle_tree::<2>(&[Fr::from(1u8); 4]).is_ok());
+    assert!(matches!(merkle_tree::<2>(&[Fr::from(1u8); 5]), Err(SynthesisError::Unsatisfiable)));
+  }
+
+  #[test]
+  fn test_merkle_tree_empty_leaves() {
+    let hasher = Poseidon::<Fr>::new(setup_params(Curve::Bn254, 5, 3));
+    let tree = merkle_tree::<3>(&[Fr::from(1u8)]).unwrap();
+    let path = tree.generate_membership_proof(1);
+    assert_eq!(path.calculate_root(&Fr::from(0u8), &hasher).unwrap(), tree.root());
+  }
+}
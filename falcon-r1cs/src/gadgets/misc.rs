@@ -1,14 +1,16 @@
 use ark_ff::PrimeField;
 use ark_r1cs_std::{fields::fp::FpVar, prelude::*};
 use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
-use falcon_rust::{N, NTT_TABLE};
+use falcon_rust::{FalconParams, NTT_TABLE};
 
 use crate::is_less_than_6144;
 
-pub fn ntt_param_var<F: PrimeField>(cs: ConstraintSystemRef<F>) -> Result<Vec<FpVar<F>>, SynthesisError> {
+pub fn ntt_param_var<F: PrimeField, P: FalconParams>(
+  cs: ConstraintSystemRef<F>,
+) -> Result<Vec<FpVar<F>>, SynthesisError> {
   let mut res = Vec::new();
 
-  for e in NTT_TABLE[0..N].as_ref() {
+  for e in NTT_TABLE[0..P::N].as_ref() {
     res.push(FpVar::<F>::new_constant(cs.clone(), F::from(*e))?)
   }
 
@@ -1,10 +1,16 @@
 mod arithmetics;
+mod hash_to_point;
+mod keccak;
+mod merkle;
 mod misc;
 mod poly;
 mod poseidon;
 mod range_proofs;
 
 pub use arithmetics::*;
+pub use hash_to_point::*;
+pub use keccak::*;
+pub use merkle::*;
 pub use misc::*;
 pub use poly::*;
 pub use poseidon::*;
@@ -1,8 +1,9 @@
-use crate::mod_q;
+use crate::{enforce_mod_q, mod_q};
 use ark_ff::PrimeField;
 use ark_r1cs_std::{fields::fp::FpVar, prelude::*};
 use ark_relations::r1cs::{ConstraintSystemRef, Namespace, SynthesisError};
-use falcon_rust::{NTTPolynomial, Polynomial, LOG_N, N};
+use falcon_rust::{FalconParams, NTTPolynomial, Polynomial, MODULUS};
+use num_bigint::BigUint;
 use std::ops::{Add, Mul};
 
 #[derive(Debug, Clone)]
@@ -37,9 +38,9 @@ impl<F: PrimeField> PolyVar<F> {
     Self(coeff)
   }
 
-  pub fn alloc_vars(
+  pub fn alloc_vars<P: FalconParams>(
     cs: impl Into<Namespace<F>>,
-    poly: &Polynomial,
+    poly: &Polynomial<P>,
     mode: AllocationMode,
   ) -> Result<Self, SynthesisError> {
     let ns = cs.into();
@@ -92,9 +93,9 @@ impl<F: PrimeField> NTTPolyVar<F> {
     Self(coeff)
   }
 
-  pub fn alloc_vars(
+  pub fn alloc_vars<P: FalconParams>(
     cs: impl Into<Namespace<F>>,
-    poly: &NTTPolynomial,
+    poly: &NTTPolynomial<P>,
     mode: AllocationMode,
   ) -> Result<Self, SynthesisError> {
     let ns = cs.into();
@@ -114,6 +115,10 @@ impl<F: PrimeField> NTTPolyVar<F> {
     &self.0
   }
 
+  /**
+   * NTT of `input`, reduced mod q. The input coefficients must be below
+   * 2^14, which bounds the quotients of the reduction.
+   */
   pub fn ntt_circuit(
     cs: ConstraintSystemRef<F>,
     input: &PolyVar<F>,
@@ -121,26 +126,54 @@ impl<F: PrimeField> NTTPolyVar<F> {
     param: &[FpVar<F>],
   ) -> Result<Self, SynthesisError> {
     let mut output = Self::ntt_circuit_defer_range_check(input, const_vars, param)?;
+    let quotient_bits = ntt_quotient_bits(output.0.len().trailing_zeros() as usize);
 
     for e in output.0.iter_mut() {
-      *e = mod_q(cs.clone(), e, &const_vars[0])?;
+      *e = mod_q(cs.clone(), e, &const_vars[0], quotient_bits)?;
     }
 
     Ok(output)
   }
 
+  /**
+   * Enforces that `output`, e.g. a witness of the caller, is the NTT of
+   * `input` reduced mod q. The input coefficients must be below 2^14, as
+   * in `ntt_circuit`.
+   */
+  pub fn enforce_ntt(
+    cs: ConstraintSystemRef<F>,
+    input: &PolyVar<F>,
+    output: &Self,
+    const_vars: &[FpVar<F>],
+    param: &[FpVar<F>],
+  ) -> Result<(), SynthesisError> {
+    if output.0.len() != input.0.len() {
+      return Err(SynthesisError::Unsatisfiable);
+    }
+
+    let unreduced = Self::ntt_circuit_defer_range_check(input, const_vars, param)?;
+    let quotient_bits = ntt_quotient_bits(unreduced.0.len().trailing_zeros() as usize);
+
+    for (a, b) in unreduced.0.iter().zip(output.0.iter()) {
+      enforce_mod_q(cs.clone(), a, b, &const_vars[0], quotient_bits)?;
+    }
+
+    Ok(())
+  }
+
   pub fn ntt_circuit_defer_range_check(
     input: &PolyVar<F>,
     const_vars: &[FpVar<F>],
     param: &[FpVar<F>]
   ) -> Result<Self, SynthesisError> {
-    if input.coeff().len() != N {
-      panic!("Input length {} is not N", input.coeff().len())
+    let n = input.coeff().len();
+    if !n.is_power_of_two() || n > param.len() {
+      panic!("Invalid input length: {}", n)
     }
     let mut output = input.coeff().to_vec();
 
-    let mut t = N;
-    for l in 0..LOG_N {
+    let mut t = n;
+    for l in 0..n.trailing_zeros() as usize {
       let m = 1 << l;
       let ht = t / 2;
       let mut i = 0;
@@ -166,4 +199,18 @@ impl<F: PrimeField> NTTPolyVar<F> {
 
     Ok(NTTPolyVar(output.to_vec()))
   }
-}
\ No newline at end of file
+}
+/*
+  Bit length of the quotients by q of the NTT outputs, for inputs below
+  2^14. Layer l multiplies by twiddle factors below q and adds
+  2^(l + 1) * q^(l + 2) to keep the differences positive.
+*/
+fn ntt_quotient_bits(log_n: usize) -> usize {
+  let modulus = BigUint::from(MODULUS);
+  let mut bound = BigUint::from(1u32 << 14);
+  for l in 0..log_n {
+    let offset = (BigUint::from(1u32) << (l + 1)) * modulus.pow(l as u32 + 2);
+    bound = &bound + (&bound * (&modulus - 1u32)).max(offset);
+  }
+  ((bound - 1u32) / modulus).bits() as usize
+}
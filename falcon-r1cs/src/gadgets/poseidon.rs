@@ -1,11 +1,13 @@
 use ark_ff::{Fp256, PrimeField};
 use ark_r1cs_std::{fields::fp::FpVar, prelude::*};
-use ark_relations::r1cs::{Namespace, SynthesisError};
-use falcon_rust::{NTTPolynomial, N, Polynomial};
+use ark_relations::r1cs::{ConstraintSystemRef, Namespace, SynthesisError};
+use falcon_rust::{FalconParams, NTTPolynomial, Polynomial};
 use ark_bn254::{Fr, FrParameters};
 use arkworks_native_gadgets::poseidon::{
   sbox::PoseidonSbox, FieldHasher, Poseidon, PoseidonParameters,
 };
+use arkworks_r1cs_gadgets::poseidon::{FieldHasherGadget, PoseidonGadget};
+use crate::{NTTPolyVar, PolyVar};
 use arkworks_utils::{
   bytes_matrix_to_f, bytes_vec_to_f, poseidon_params::setup_poseidon_params, Curve,
 };
@@ -37,54 +39,45 @@ impl<Fr: PrimeField> PoseidonVars<Fr> {
     Self(coeff)
   }
 
+  /**
+   * Hashes the NTT coefficient vars in-circuit and allocates the digest
+   * with `mode`, enforcing it equal to the computed hash
+   */
   pub fn hash_poly(
     cs: impl Into<Namespace<Fr>>,
-    poly: &NTTPolynomial,
+    poly: &NTTPolyVar<Fr>,
     mode: AllocationMode,
   ) -> Result<Self, SynthesisError> {
-    let curve = Curve::Bn254;
-    let ns = cs.into();
-    let cs = ns.cs();
-
-    let mut inputs = Vec::new();
-    for e in poly.coeff() {
-      inputs.push(Fr::from(*e))
-    }
-
-    let parameters =  setup_params(curve, 5, 3);
-    let hasher = Poseidon::<Fr>::new(parameters);
-
-    let mut res = hasher.hash(&[inputs[0], inputs[1]]).unwrap();
-    for &value in &inputs[2..N] {
-      res = hasher.hash(&[res, value]).unwrap();
-    }
-    
-    Ok(Self(FpVar::new_variable(cs.clone(), || Ok(Fr::from(res)), mode)?))
+    Self::hash_coeff_vars(cs, poly.coeff(), mode)
   }
 
+  /**
+   * Hashes the public key coefficient vars in-circuit and allocates the
+   * digest with `mode`, enforcing it equal to the computed hash
+   */
   pub fn hash_pubic_key(
     cs: impl Into<Namespace<Fr>>,
-    poly: &Polynomial,
+    poly: &PolyVar<Fr>,
+    mode: AllocationMode,
+  ) -> Result<Self, SynthesisError> {
+    Self::hash_coeff_vars(cs, poly.coeff(), mode)
+  }
+
+  fn hash_coeff_vars(
+    cs: impl Into<Namespace<Fr>>,
+    coeff: &[FpVar<Fr>],
     mode: AllocationMode,
   ) -> Result<Self, SynthesisError> {
-    let curve = Curve::Bn254;
     let ns = cs.into();
-    let cs = ns.cs();
+    let mut cs = ns.cs();
 
-    let mut inputs = Vec::new();
-    for e in poly.coeff() {
-      inputs.push(Fr::from(*e))
-    }
+    let hasher = poseidon_gadget(&mut cs)?;
+    let digest = hash_chain_var(&hasher, coeff)?;
 
-    let parameters =  setup_params(curve, 5, 3);
-    let hasher = Poseidon::<Fr>::new(parameters);
+    let res = FpVar::new_variable(cs.clone(), || digest.value(), mode)?;
+    res.enforce_equal(&digest)?;
 
-    let mut res = hasher.hash(&[inputs[0], inputs[1]]).unwrap();
-    for &value in &inputs[2..N] {
-      res = hasher.hash(&[res, value]).unwrap();
-    }
-    
-    Ok(Self(FpVar::new_variable(cs.clone(), || Ok(Fr::from(res)), mode)?))
+    Ok(Self(res))
   }
 
   pub fn coeff(&self) -> &FpVar<Fr> {
@@ -93,50 +86,74 @@ impl<Fr: PrimeField> PoseidonVars<Fr> {
 }
 
 /**
- * Poseidon Hash for NTTPolynomial
+ * Poseidon gadget with the same parameters as the native hasher
  */
-type PoseidonHasher = Poseidon<Fr>;
-pub fn hash_from_poly(poly: &NTTPolynomial) -> Result<Fp256<FrParameters>, SynthesisError> {
-  let curve = Curve::Bn254;
+pub fn poseidon_gadget<F: PrimeField>(
+  cs: &mut ConstraintSystemRef<F>,
+) -> Result<PoseidonGadget<F>, SynthesisError> {
+  let parameters = setup_params(Curve::Bn254, 5, 3);
+  PoseidonGadget::from_native(cs, Poseidon::<F>::new(parameters))
+}
 
-  let mut inputs = Vec::new();
-  for e in poly.coeff() {
-    inputs.push(Fr::from(*e))
+/**
+ * In-circuit counterpart of `hash_chain`. Fails with `Unsatisfiable` on
+ * fewer than two inputs.
+ */
+pub fn hash_chain_var<F: PrimeField>(
+  hasher: &PoseidonGadget<F>,
+  inputs: &[FpVar<F>],
+) -> Result<FpVar<F>, SynthesisError> {
+  if inputs.len() < 2 {
+    return Err(SynthesisError::Unsatisfiable);
   }
 
-  let parameters = setup_params(curve, 5, 3);
-  let hasher = PoseidonHasher::new(parameters);
-
-  let mut res = hasher.hash(&[inputs[0], inputs[1]]).unwrap();
-  for &value in &inputs[2..N] {
-    res = hasher.hash(&[res, value]).unwrap();
+  let mut res = hasher.hash_two(&inputs[0], &inputs[1])?;
+  for value in &inputs[2..] {
+    res = hasher.hash_two(&res, value)?;
   }
 
   Ok(res)
 }
 
+type PoseidonHasher = Poseidon<Fr>;
+
 /**
- * Poseidon hash for Polynomial
+ * Chained Poseidon hash: h(...h(h(x0, x1), x2)..., xn). Fails with
+ * `Unsatisfiable` on fewer than two inputs.
  */
-pub fn hash_from_pk(poly: &Polynomial) -> Result<Fp256<FrParameters>, SynthesisError> {
-  let curve = Curve::Bn254;
-
-  let mut inputs = Vec::new();
-  for e in poly.coeff() {
-    inputs.push(Fr::from(*e));
+pub fn hash_chain(inputs: &[Fr]) -> Result<Fr, SynthesisError> {
+  if inputs.len() < 2 {
+    return Err(SynthesisError::Unsatisfiable);
   }
 
-  let parameters = setup_params(curve, 5, 3);
+  let parameters = setup_params(Curve::Bn254, 5, 3);
   let hasher = PoseidonHasher::new(parameters);
 
-  let mut res = hasher.hash(&[inputs[0], inputs[1]]).unwrap();
-  for &value in &inputs[2..N] {
-    res = hasher.hash(&[res, value]).unwrap();
+  let hash_two = |x: &Fr, y: &Fr| hasher.hash_two(x, y).map_err(|_| SynthesisError::Unsatisfiable);
+  let mut res = hash_two(&inputs[0], &inputs[1])?;
+  for value in &inputs[2..] {
+    res = hash_two(&res, value)?;
   }
 
   Ok(res)
 }
 
+/**
+ * Poseidon Hash for NTTPolynomial
+ */
+pub fn hash_from_poly<P: FalconParams>(poly: &NTTPolynomial<P>) -> Result<Fp256<FrParameters>, SynthesisError> {
+  let inputs: Vec<Fr> = poly.coeff().iter().map(|e| Fr::from(*e)).collect();
+  hash_chain(&inputs)
+}
+
+/**
+ * Poseidon hash for Polynomial
+ */
+pub fn hash_from_pk<P: FalconParams>(poly: &Polynomial<P>) -> Result<Fp256<FrParameters>, SynthesisError> {
+  let inputs: Vec<Fr> = poly.coeff().iter().map(|e| Fr::from(*e)).collect();
+  hash_chain(&inputs)
+}
+
 #[cfg(test)]
 pub mod test {
   use ark_bn254::Fr;
@@ -156,16 +173,14 @@ pub mod test {
 		let mds_f = bytes_matrix_to_f(&pos_data.mds);
 		let rounds_f = bytes_vec_to_f(&pos_data.rounds);
 
-		let pos = PoseidonParameters {
+		PoseidonParameters {
 			mds_matrix: mds_f,
 			round_keys: rounds_f,
 			full_rounds: pos_data.full_rounds,
 			partial_rounds: pos_data.partial_rounds,
 			sbox: PoseidonSbox(pos_data.exp),
 			width: pos_data.width,
-		};
-
-		pos
+		}
 	}
 
   type PoseidonHasher = Poseidon<Fr>;
@@ -189,4 +204,47 @@ pub mod test {
 
     assert_eq!(res[0], poseidon_res, "{} != {}", res[0], poseidon_res);
   }
+
+  #[test]
+  fn should_match_native_hash_chain() {
+    use ark_r1cs_std::{alloc::AllocVar, fields::fp::FpVar, R1CSVar};
+    use ark_relations::r1cs::ConstraintSystem;
+
+    let inputs: Vec<Fr> = (0..8u64).map(Fr::from).collect();
+
+    let mut cs = ConstraintSystem::<Fr>::new_ref();
+    let input_vars = inputs
+      .iter()
+      .map(|x| FpVar::new_witness(cs.clone(), || Ok(*x)))
+      .collect::<Result<Vec<_>, _>>()
+      .unwrap();
+    let hasher = super::poseidon_gadget(&mut cs).unwrap();
+    let res = super::hash_chain_var(&hasher, &input_vars).unwrap();
+
+    assert_eq!(res.value().unwrap(), super::hash_chain(&inputs).unwrap());
+    assert!(cs.is_satisfied().unwrap());
+  }
+
+  #[test]
+  fn should_reject_short_hash_chain() {
+    use ark_r1cs_std::{alloc::AllocVar, fields::fp::FpVar};
+    use ark_relations::r1cs::{ConstraintSystem, SynthesisError};
+
+    for len in 0..2u64 {
+      let inputs: Vec<Fr> = (0..len).map(Fr::from).collect();
+      assert_eq!(super::hash_chain(&inputs), Err(SynthesisError::Unsatisfiable));
+
+      let mut cs = ConstraintSystem::<Fr>::new_ref();
+      let input_vars = inputs
+        .iter()
+        .map(|x| FpVar::new_witness(cs.clone(), || Ok(*x)))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+      let hasher = super::poseidon_gadget(&mut cs).unwrap();
+      assert!(matches!(
+        super::hash_chain_var(&hasher, &input_vars),
+        Err(SynthesisError::Unsatisfiable)
+      ));
+    }
+  }
 }
@@ -1,13 +1,15 @@
 use ark_ff::{BigInteger, PrimeField};
 use ark_r1cs_std::{alloc::AllocVar, fields::fp::FpVar, prelude::*};
 use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
-#[cfg(not(test))]
-use falcon_rust::MODULUS;
-#[cfg(not(test))]
-use falcon_rust::SIG_L2_BOUND;
+use falcon_rust::{FalconParams, KEY_L2_BOUND};
 
 use crate::enforce_decompose;
 
+/*
+  Out of range inputs leave the constraint system unsatisfied rather than
+  panicking, in test and release builds alike: either the truncated bit
+  decomposition or the comparison fails.
+*/
 pub(crate) fn enforce_less_than_q<F: PrimeField>(
   cs: ConstraintSystemRef<F>,
   a: &FpVar<F>,
@@ -18,11 +20,6 @@ pub(crate) fn enforce_less_than_q<F: PrimeField>(
     a.value()?
   };
 
-  #[cfg(not(test))]
-  if a_val >= F::from(MODULUS) {
-    panic!("Invalid input: {}", a_val);
-  }
-
   let a_bits = a_val.into_repr().to_bits_le();
   let a_bit_vars = a_bits
     .iter()
@@ -43,6 +40,31 @@ pub(crate) fn enforce_less_than_q<F: PrimeField>(
   Ok(())
 }
 
+/**
+ * Enforces `a < 2^bits`
+ */
+pub(crate) fn enforce_bit_length<F: PrimeField>(
+  cs: ConstraintSystemRef<F>,
+  a: &FpVar<F>,
+  bits: usize,
+) -> Result<(), SynthesisError> {
+  let a_val = if cs.is_in_setup_mode() {
+    F::one()
+  } else {
+    a.value()?
+  };
+
+  let a_bit_vars = a_val
+    .into_repr()
+    .to_bits_le()
+    .iter()
+    .take(bits)
+    .map(|x| Boolean::new_witness(cs.clone(), || Ok(x)))
+    .collect::<Result<Vec<_>, _>>()?;
+
+  enforce_decompose(a, a_bit_vars.as_ref())
+}
+
 pub(crate) fn is_less_than_6144<F: PrimeField>(
   cs: ConstraintSystemRef<F>,
   a: &FpVar<F>,
@@ -63,28 +85,57 @@ pub(crate) fn is_less_than_6144<F: PrimeField>(
 
   enforce_decompose(a, a_bit_vars.as_ref())?;
 
-  let res = (a_bit_vars[13].is_eq(&Boolean::FALSE)?)
+  (a_bit_vars[13].is_eq(&Boolean::FALSE)?)
       .and(&a_bit_vars[12].is_eq(&Boolean::FALSE)?
       .   or(&a_bit_vars[11].is_eq(&Boolean::FALSE)?
           )?
       )?
-      .is_eq(&Boolean::TRUE);
-  res
+      .is_eq(&Boolean::TRUE)
 }
 
-pub fn enforce_less_than_norm_bound<F: PrimeField>(
+/**
+ * Enforces `a < KEY_L2_BOUND`, the squared norm bound of (g, -f) in key
+ * generation
+ */
+pub fn enforce_less_than_key_norm_bound<F: PrimeField>(
   cs: ConstraintSystemRef<F>,
   a: &FpVar<F>,
 ) -> Result<(), SynthesisError> {
-  #[cfg(feature = "falcon-512")]
-  enforce_less_than_norm_bound_512(cs, a)?;
-  #[cfg(feature = "falcon-1024")]
-  enforce_less_than_norm_bound_1024(cs, a)?;
+  let a_val = if cs.is_in_setup_mode() {
+    F::one()
+  } else {
+    a.value()?
+  };
+
+  let bound = KEY_L2_BOUND - 1;
+  let a_bits = a_val.into_repr().to_bits_le();
+  let a_bit_vars = a_bits
+    .iter()
+    .take(64 - bound.leading_zeros() as usize)
+    .map(|x| Boolean::new_witness(cs.clone(), || Ok(x)))
+    .collect::<Result<Vec<_>, _>>()?;
+
+  enforce_decompose(a, a_bit_vars.as_ref())?;
+  Boolean::enforce_smaller_or_equal_than_le(&a_bit_vars, [bound])?;
 
   Ok(())
 }
 
-#[cfg(feature = "falcon-512")]
+/**
+ * Enforces `a < P::SIG_L2_BOUND`. Only Falcon-512 and Falcon-1024 are
+ * supported, other degrees fail with `Unsatisfiable`.
+ */
+pub fn enforce_less_than_norm_bound<F: PrimeField, P: FalconParams>(
+  cs: ConstraintSystemRef<F>,
+  a: &FpVar<F>,
+) -> Result<(), SynthesisError> {
+  match P::LOG_N {
+    9 => enforce_less_than_norm_bound_512(cs, a),
+    10 => enforce_less_than_norm_bound_1024(cs, a),
+    _ => Err(SynthesisError::Unsatisfiable),
+  }
+}
+
 fn enforce_less_than_norm_bound_512<F: PrimeField>(
     cs: ConstraintSystemRef<F>,
     a: &FpVar<F>,
@@ -95,11 +146,6 @@ fn enforce_less_than_norm_bound_512<F: PrimeField>(
         a.value()?
     };
 
-    #[cfg(not(test))]
-    if a_val >= F::from(SIG_L2_BOUND) {
-        panic!("Invalid input: {}", a_val);
-    }
-
     let a_bits = a_val.into_repr().to_bits_le();
     let a_bit_vars = a_bits
         .iter()
@@ -138,7 +184,6 @@ fn enforce_less_than_norm_bound_512<F: PrimeField>(
     Ok(())
 }
    
-#[cfg(feature = "falcon-1024")]
 fn enforce_less_than_norm_bound_1024<F: PrimeField>(
     cs: ConstraintSystemRef<F>,
     a: &FpVar<F>,
@@ -149,11 +194,6 @@ fn enforce_less_than_norm_bound_1024<F: PrimeField>(
         a.value()?
     };
 
-    #[cfg(not(test))]
-    if a_val >= F::from(SIG_L2_BOUND) {
-        panic!("Invalid input: {}", a_val);
-    }
-
     let a_bits = a_val.into_repr().to_bits_le();
     let a_bit_vars = a_bits
         .iter()
@@ -1,5 +1,14 @@
+mod ceremony;
 mod circuits;
 mod gadgets;
+mod powers_of_tau;
+mod solidity;
+#[cfg(test)]
+mod test_utils;
+pub mod wasm;
 
+pub use ceremony::*;
 pub use circuits::*;
-pub use gadgets::*;
\ No newline at end of file
+pub use gadgets::*;
+pub use powers_of_tau::*;
+pub use solidity::*;
\ No newline at end of file
@@ -1,66 +1,503 @@
-use std::{error::Error, fs};
-use ark_bn254::{Bn254, Fr};
-use ark_groth16::Groth16;
-use ark_relations::r1cs::ConstraintSynthesizer;
-use ark_serialize::CanonicalSerialize;
-use ark_snark::SNARK;
+use std::{error::Error, fs, io::Write, path::{Path, PathBuf}, process};
+use ark_bn254::Bn254;
+use ark_groth16::{Groth16, ProvingKey, VerifyingKey};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use arkworks_solidity_verifier::SolidityVerifier;
-use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+use clap::{Parser, Subcommand, ValueEnum};
+use rand_core::OsRng;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use zeroize::Zeroizing;
 
-use falcon_r1cs::FalconVerificationCircuit;
-use falcon_rust::KeyPair;
+use falcon_r1cs::{
+    contribute, contribute_powers_of_tau, initial_proving_key, verify_powers_of_tau, verify_transcript, wasm,
+    CeremonyError, FalconVerificationCircuit, PowersOfTau, PowersOfTauTranscript, Transcript,
+};
+use falcon_rust::{Falcon1024, Falcon512, FalconParams, KeyPair, PublicKey, SecretKey, Signature};
 
-fn generate<C: ConstraintSynthesizer<Fr>>(circuit: C, label: &str) -> Result<(), Box<dyn Error>> {
-    println!("Generating keys for {}", label);
+const PK_PATH: &str = "out/falcon_pk.bin";
+const SK_PATH: &str = "out/falcon_sk.bin";
+const SIG_PATH: &str = "out/falcon_sig.bin";
+const POWERS_OF_TAU_PATH: &str = "out/powers_of_tau.bin";
+const POWERS_OF_TAU_TRANSCRIPT_PATH: &str = "out/powers_of_tau_transcript.bin";
+const PROVING_KEY_PATH: &str = "out/Verification_pk.bin";
+const VERIFYING_KEY_PATH: &str = "out/Verification_vk.bin";
+const TRANSCRIPT_PATH: &str = "out/Verification_transcript.bin";
+const INITIAL_PROVING_KEY_PATH: &str = "out/Verification_initial_pk.bin";
+const VERIFIER_PATH: &str = "out/Verification_verifier.sol";
+const PROOF_PATH: &str = "out/proof.json";
+const CALLDATA_PATH: &str = "out/calldata.json";
 
-    let mut rng = ChaCha20Rng::from_seed([0; 32]);
-    let (pk, vk) = Groth16::<Bn254>::circuit_specific_setup(circuit, &mut rng)?;
-    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+/// Falcon keys and signatures, and Groth16 proofs of their verification.
+/// Results are printed as JSON.
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
 
-    let mut pk_bytes = vec![];
-    let mut vk_bytes = vec![];
+#[derive(Clone, Copy, ValueEnum)]
+enum Degree {
+    #[value(name = "512")]
+    Falcon512,
+    #[value(name = "1024")]
+    Falcon1024,
+}
 
-    println!("Serializing keys for {}", label);
-    pk.serialize_uncompressed(&mut pk_bytes)?;
-    vk.serialize_uncompressed(&mut vk_bytes)?;
+#[derive(Subcommand)]
+enum Command {
+    /// Generates a key pair
+    Keygen {
+        #[arg(long, value_enum, default_value = "512")]
+        degree: Degree,
+        /// Hex-encoded seed, random if omitted
+        #[arg(long)]
+        seed: Option<String>,
+        #[arg(long, default_value = PK_PATH)]
+        pk: PathBuf,
+        #[arg(long, default_value = SK_PATH)]
+        sk: PathBuf,
+    },
+    /// Signs a file
+    Sign {
+        msg: PathBuf,
+        #[arg(long, default_value = SK_PATH)]
+        sk: PathBuf,
+        #[arg(long, default_value = SIG_PATH)]
+        sig: PathBuf,
+    },
+    /// Verifies the signature of a file
+    Verify {
+        msg: PathBuf,
+        #[arg(long, default_value = PK_PATH)]
+        pk: PathBuf,
+        #[arg(long, default_value = SIG_PATH)]
+        sig: PathBuf,
+    },
+    /// Starts the powers of tau, phase 1 of the setup, without contributions
+    PowersOfTau {
+        /// Supports circuits of up to 2^power constraints and public inputs:
+        /// 20 for Falcon-512, 21 for Falcon-1024
+        #[arg(long, default_value_t = 20, value_parser = clap::value_parser!(u32).range(1..=28))]
+        power: u32,
+        #[arg(long, default_value = POWERS_OF_TAU_PATH)]
+        powers_of_tau: PathBuf,
+        #[arg(long, default_value = POWERS_OF_TAU_TRANSCRIPT_PATH)]
+        transcript: PathBuf,
+    },
+    /// Contributes randomness to the powers of tau, in place, and appends the
+    /// contribution to their transcript. The contribution is drawn from
+    /// system randomness
+    ContributePowersOfTau {
+        #[arg(long, default_value = POWERS_OF_TAU_PATH)]
+        powers_of_tau: PathBuf,
+        #[arg(long, default_value = POWERS_OF_TAU_TRANSCRIPT_PATH)]
+        transcript: PathBuf,
+    },
+    /// Verifies that the powers of tau result from the contributions of their
+    /// transcript
+    VerifyPowersOfTau {
+        #[arg(long, default_value = POWERS_OF_TAU_PATH)]
+        powers_of_tau: PathBuf,
+        #[arg(long, default_value = POWERS_OF_TAU_TRANSCRIPT_PATH)]
+        transcript: PathBuf,
+    },
+    /// Derives the proving and verifying keys of the verification circuit from
+    /// verified powers of tau with at least one contribution
+    Setup {
+        #[arg(long, value_enum, default_value = "512")]
+        degree: Degree,
+        #[arg(long, default_value = POWERS_OF_TAU_PATH)]
+        powers_of_tau: PathBuf,
+        #[arg(long, default_value = POWERS_OF_TAU_TRANSCRIPT_PATH)]
+        powers_of_tau_transcript: PathBuf,
+        #[arg(long, default_value = PROVING_KEY_PATH)]
+        proving_key: PathBuf,
+        #[arg(long, default_value = VERIFYING_KEY_PATH)]
+        verifying_key: PathBuf,
+        /// Phase-2 ceremony transcript, started empty
+        #[arg(long, default_value = TRANSCRIPT_PATH)]
+        transcript: PathBuf,
+        /// Copy of the proving key that the ceremony starts from, kept for
+        /// `verify-ceremony`
+        #[arg(long, default_value = INITIAL_PROVING_KEY_PATH)]
+        initial_proving_key: PathBuf,
+    },
+    /// Contributes randomness to the proving key written by `setup`, in place,
+    /// and appends the contribution to the ceremony transcript. The
+    /// contribution is drawn from system randomness
+    Contribute {
+        #[arg(long, default_value = PROVING_KEY_PATH)]
+        proving_key: PathBuf,
+        #[arg(long, default_value = VERIFYING_KEY_PATH)]
+        verifying_key: PathBuf,
+        #[arg(long, default_value = TRANSCRIPT_PATH)]
+        transcript: PathBuf,
+    },
+    /// Verifies the powers of tau and that the initial proving key of `setup`
+    /// derives from them
+    VerifySetup {
+        #[arg(long, value_enum, default_value = "512")]
+        degree: Degree,
+        #[arg(long, default_value = POWERS_OF_TAU_PATH)]
+        powers_of_tau: PathBuf,
+        #[arg(long, default_value = POWERS_OF_TAU_TRANSCRIPT_PATH)]
+        powers_of_tau_transcript: PathBuf,
+        #[arg(long, default_value = INITIAL_PROVING_KEY_PATH)]
+        initial_proving_key: PathBuf,
+    },
+    /// Verifies that a proving key results from the initial key of `setup` and
+    /// the contributions of the transcript
+    VerifyCeremony {
+        /// Proving key written by `setup`, before any contribution
+        #[arg(long, default_value = INITIAL_PROVING_KEY_PATH)]
+        initial_proving_key: PathBuf,
+        #[arg(long, default_value = PROVING_KEY_PATH)]
+        proving_key: PathBuf,
+        #[arg(long, default_value = TRANSCRIPT_PATH)]
+        transcript: PathBuf,
+    },
+    /// Proves the verification of the signature of a file
+    Prove {
+        msg: PathBuf,
+        #[arg(long, default_value = PROVING_KEY_PATH)]
+        proving_key: PathBuf,
+        #[arg(long, default_value = PK_PATH)]
+        pk: PathBuf,
+        #[arg(long, default_value = SIG_PATH)]
+        sig: PathBuf,
+        /// Output JSON file with the proof and its public inputs
+        #[arg(long, default_value = PROOF_PATH)]
+        proof: PathBuf,
+    },
+    /// Verifies a proof written by `prove`
+    VerifyProof {
+        #[arg(long, default_value = VERIFYING_KEY_PATH)]
+        verifying_key: PathBuf,
+        #[arg(long, default_value = PROOF_PATH)]
+        proof: PathBuf,
+    },
+    /// Exports a proof written by `prove` as arguments and transaction data of
+    /// `verifyTx` in the Solidity verifier
+    ExportCalldata {
+        #[arg(long, default_value = PROOF_PATH)]
+        proof: PathBuf,
+        #[arg(long, default_value = CALLDATA_PATH)]
+        out: PathBuf,
+    },
+    /// Exports the Solidity verifier of a verifying key
+    ExportVerifier {
+        #[arg(long, default_value = VERIFYING_KEY_PATH)]
+        verifying_key: PathBuf,
+        #[arg(long, default_value = VERIFIER_PATH)]
+        out: PathBuf,
+    },
+}
 
-    println!("Writing key for {}", label);
-    fs::write(format!("{}/out/{}_pk.bin", manifest_dir, label), pk_bytes)?;
-    fs::write(format!("{}/out/{}_vk.bin", manifest_dir, label), vk_bytes)?;
+/// Proof and public inputs, as written by `prove`
+#[derive(Serialize, Deserialize)]
+struct ProofJson {
+    /// compressed proof
+    proof: String,
+    /// 32-byte big-endian field elements
+    public_inputs: Vec<String>,
+}
 
-    println!("Exporting verifier for {}", label);
-    let verifier = Groth16::export(&vk);
+fn to_hex(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
 
-    println!("Writing verifier for {}", label);
-    fs::write(
-        format!("{}/out/{}_verifier.sol", manifest_dir, label),
-        verifier,
-    )?;
+fn from_hex(s: &str) -> Result<Vec<u8>, hex::FromHexError> {
+    hex::decode(s.strip_prefix("0x").unwrap_or(s))
+}
 
-    Ok(())
+fn public_inputs_from_hex(inputs: &[String]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut res = vec![];
+    for x in inputs {
+        let x = from_hex(x)?;
+        if x.len() != 32 {
+            return Err(format!("public inputs must be 32 bytes, found {}", x.len()).into());
+        }
+        res.extend(x);
+    }
+    Ok(res)
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let dir = format!("{}/out", env!("CARGO_MANIFEST_DIR"));
-    if fs::read_dir(&dir).is_err() {
-        fs::create_dir(&dir)?;
+fn create_parent_dir(path: &Path) -> Result<(), Box<dyn Error>> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
     }
+    Ok(())
+}
+
+fn write(path: &Path, contents: impl AsRef<[u8]>) -> Result<(), Box<dyn Error>> {
+    create_parent_dir(path)?;
+    Ok(fs::write(path, contents)?)
+}
+
+// secret keys are only readable by their owner, overwritten ones included
+fn write_secret(path: &Path, contents: impl AsRef<[u8]>) -> Result<(), Box<dyn Error>> {
+    create_parent_dir(path)?;
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let mut file = options.open(path)?;
+    #[cfg(unix)]
+    file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+    file.write_all(contents.as_ref())?;
+    Ok(())
+}
+
+fn print(value: &impl Serialize) -> Result<(), Box<dyn Error>> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+// keys and signatures carry the degree in the low nibble of their header
+fn is_falcon1024(bytes: &[u8]) -> bool {
+    bytes.first().map(|h| (h & 0x0F) as usize) == Some(Falcon1024::LOG_N)
+}
+
+fn read_proving_key(path: &Path) -> Result<ProvingKey<Bn254>, Box<dyn Error>> {
+    Ok(wasm::proving_key_from_bytes(&fs::read(path)?)?)
+}
+
+fn read_powers_of_tau(path: &Path) -> Result<PowersOfTau, Box<dyn Error>> {
+    Ok(PowersOfTau::from_bytes(&fs::read(path)?)?)
+}
+
+fn read_powers_of_tau_transcript(path: &Path) -> Result<PowersOfTauTranscript, Box<dyn Error>> {
+    Ok(PowersOfTauTranscript::deserialize_uncompressed(fs::read(path)?.as_slice())?)
+}
+
+fn serialize(x: &impl CanonicalSerialize) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut res = vec![];
+    x.serialize_uncompressed(&mut res)?;
+    Ok(res)
+}
+
+// the copy of the secret key is wiped once written, as the key itself
+fn keygen<P: FalconParams>(seed: Option<&[u8]>) -> (Vec<u8>, Zeroizing<Vec<u8>>) {
+    let keypair = match seed {
+        Some(seed) => KeyPair::<P>::keygen_from_seed(seed),
+        None => KeyPair::<P>::keygen(),
+    };
+    (keypair.public_key.as_bytes().to_vec(), Zeroizing::new(keypair.secret_key.as_bytes().to_vec()))
+}
 
-    let keypair = KeyPair::keygen();
+fn sign<P: FalconParams>(sk: &[u8], msg: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let sk = SecretKey::<P>::from_bytes(sk)?;
+    Ok(sk.try_sign(msg)?.as_bytes().to_vec())
+}
+
+fn verify<P: FalconParams>(pk: &[u8], msg: &[u8], sig: &[u8]) -> Result<bool, Box<dyn Error>> {
+    let pk = PublicKey::<P>::from_bytes(pk)?;
+    let sig = Signature::<P>::from_bytes(sig)?;
+    Ok(pk.verify(msg, &sig))
+}
 
+/*
+  The circuit only depends on the parameter set: it is built over a
+  throwaway signature
+*/
+fn setup<P: FalconParams>(powers: &PowersOfTau) -> Result<ProvingKey<Bn254>, Box<dyn Error>> {
+    let keypair = KeyPair::<P>::keygen_from_seed(&[0; 48]);
     let msg = "Testing message";
-    let sig = keypair
-        .secret_key
-        .sign(msg.as_ref());
-    assert!(keypair.public_key.verify(msg.as_ref(), &sig));
-
-    generate(
-        FalconVerificationCircuit::build_circuit(
-            keypair.public_key, 
-            msg.as_bytes().to_vec(), 
-            sig), 
-        "Verification"
-    )?;
+    let sig = keypair.secret_key.sign(msg.as_ref());
+    let circuit = FalconVerificationCircuit::build_circuit(keypair.public_key, msg.as_bytes().to_vec(), sig);
+
+    Ok(initial_proving_key(circuit, powers)?)
+}
+
+// the initial key of a ceremony is only sound over verified powers of tau
+fn verified_powers_of_tau(powers: &Path, transcript: &Path) -> Result<(PowersOfTau, [u8; 32]), Box<dyn Error>> {
+    let powers = read_powers_of_tau(powers)?;
+    let transcript = read_powers_of_tau_transcript(transcript)?;
+    if transcript.is_empty() {
+        return Err("the powers of tau have no contribution".into());
+    }
+    let hash = verify_powers_of_tau(&powers, &transcript, &mut OsRng)?;
+    Ok((powers, hash))
+}
+
+fn run(command: Command) -> Result<Value, Box<dyn Error>> {
+    match command {
+        Command::Keygen { degree, seed, pk, sk } => {
+            let seed = seed.map(|seed| from_hex(&seed)).transpose()?;
+            let (pk_bytes, sk_bytes) = match degree {
+                Degree::Falcon512 => keygen::<Falcon512>(seed.as_deref()),
+                Degree::Falcon1024 => keygen::<Falcon1024>(seed.as_deref()),
+            };
+            write(&pk, &pk_bytes)?;
+            write_secret(&sk, sk_bytes)?;
+
+            Ok(json!({
+                "public_key": pk,
+                "secret_key": sk,
+                "owner": wasm::hash_from_pk(&pk_bytes)?,
+            }))
+        }
+        Command::Sign { msg, sk, sig } => {
+            let sk_bytes = Zeroizing::new(fs::read(&sk)?);
+            let msg = fs::read(msg)?;
+            let sig_bytes = if is_falcon1024(&sk_bytes) {
+                sign::<Falcon1024>(&sk_bytes, &msg)?
+            } else {
+                sign::<Falcon512>(&sk_bytes, &msg)?
+            };
+            write(&sig, &sig_bytes)?;
+
+            Ok(json!({ "signature": sig, "value": to_hex(&sig_bytes) }))
+        }
+        Command::Verify { msg, pk, sig } => {
+            let pk = fs::read(pk)?;
+            let sig = fs::read(sig)?;
+            let msg = fs::read(msg)?;
+            let valid = if is_falcon1024(&pk) {
+                verify::<Falcon1024>(&pk, &msg, &sig)?
+            } else {
+                verify::<Falcon512>(&pk, &msg, &sig)?
+            };
+
+            Ok(json!({ "valid": valid }))
+        }
+        Command::PowersOfTau { power, powers_of_tau, transcript } => {
+            let powers = PowersOfTau::new(1 << power);
+            write(&powers_of_tau, serialize(&powers)?)?;
+            write(&transcript, serialize(&PowersOfTauTranscript::new(&powers))?)?;
+
+            Ok(json!({ "powers_of_tau": powers_of_tau, "transcript": transcript }))
+        }
+        Command::ContributePowersOfTau { powers_of_tau, transcript } => {
+            let mut powers = read_powers_of_tau(&powers_of_tau)?;
+            let mut contributions = read_powers_of_tau_transcript(&transcript)?;
+
+            let hash = contribute_powers_of_tau(&mut powers, &mut contributions, &mut OsRng);
+            write(&powers_of_tau, serialize(&powers)?)?;
+            write(&transcript, serialize(&contributions)?)?;
+
+            Ok(json!({ "contribution": contributions.len(), "hash": to_hex(&hash) }))
+        }
+        Command::VerifyPowersOfTau { powers_of_tau, transcript } => {
+            let powers = read_powers_of_tau(&powers_of_tau)?;
+            let contributions = read_powers_of_tau_transcript(&transcript)?;
+
+            Ok(match verify_powers_of_tau(&powers, &contributions, &mut OsRng) {
+                Ok(hash) => json!({ "valid": true, "contributions": contributions.len(), "hash": to_hex(&hash) }),
+                Err(e) => json!({ "valid": false, "error": e.to_string() }),
+            })
+        }
+        Command::Setup {
+            degree,
+            powers_of_tau,
+            powers_of_tau_transcript,
+            proving_key,
+            verifying_key,
+            transcript,
+            initial_proving_key,
+        } => {
+            let (powers, powers_hash) = verified_powers_of_tau(&powers_of_tau, &powers_of_tau_transcript)?;
+            let pk = match degree {
+                Degree::Falcon512 => setup::<Falcon512>(&powers)?,
+                Degree::Falcon1024 => setup::<Falcon1024>(&powers)?,
+            };
+            let pk_bytes = serialize(&pk)?;
+            write(&proving_key, &pk_bytes)?;
+            write(&initial_proving_key, &pk_bytes)?;
+            write(&verifying_key, serialize(&pk.vk)?)?;
+            write(&transcript, serialize(&Transcript::new(&pk))?)?;
 
+            Ok(json!({
+                "proving_key": proving_key,
+                "verifying_key": verifying_key,
+                "transcript": transcript,
+                "initial_proving_key": initial_proving_key,
+                "powers_of_tau_hash": to_hex(&powers_hash),
+            }))
+        }
+        Command::Contribute { proving_key, verifying_key, transcript } => {
+            let mut pk = read_proving_key(&proving_key)?;
+            let mut contributions = Transcript::deserialize(fs::read(&transcript)?.as_slice())?;
+
+            let hash = contribute(&mut pk, &mut contributions, &mut OsRng);
+            write(&proving_key, serialize(&pk)?)?;
+            write(&verifying_key, serialize(&pk.vk)?)?;
+            write(&transcript, serialize(&contributions)?)?;
+
+            Ok(json!({ "contribution": contributions.len(), "hash": to_hex(&hash) }))
+        }
+        Command::VerifySetup { degree, powers_of_tau, powers_of_tau_transcript, initial_proving_key } => {
+            let (powers, powers_hash) = verified_powers_of_tau(&powers_of_tau, &powers_of_tau_transcript)?;
+            let initial = read_proving_key(&initial_proving_key)?;
+            let derived = match degree {
+                Degree::Falcon512 => setup::<Falcon512>(&powers)?,
+                Degree::Falcon1024 => setup::<Falcon1024>(&powers)?,
+            };
+
+            Ok(if derived == initial {
+                json!({ "valid": true, "powers_of_tau_hash": to_hex(&powers_hash) })
+            } else {
+                json!({ "valid": false, "error": CeremonyError::InitialKey.to_string() })
+            })
+        }
+        Command::VerifyCeremony { initial_proving_key, proving_key, transcript } => {
+            let initial = read_proving_key(&initial_proving_key)?;
+            let pk = read_proving_key(&proving_key)?;
+            let contributions = Transcript::deserialize(fs::read(&transcript)?.as_slice())?;
+
+            Ok(match verify_transcript(&initial, &pk, &contributions, &mut OsRng) {
+                Ok(hash) => json!({ "valid": true, "contributions": contributions.len(), "hash": to_hex(&hash) }),
+                Err(e) => json!({ "valid": false, "error": e.to_string() }),
+            })
+        }
+        Command::Prove { msg, proving_key, pk, sig, proof } => {
+            let params = fs::read(proving_key)?;
+            let pk = fs::read(pk)?;
+            let sig = fs::read(sig)?;
+            let msg = fs::read(msg)?;
+
+            let res = ProofJson {
+                proof: to_hex(&wasm::prove(&params, &pk, &msg, &sig)?),
+                public_inputs: wasm::public_inputs(&pk, &msg, &sig)?.chunks(32).map(to_hex).collect(),
+            };
+            write(&proof, serde_json::to_string_pretty(&res)?)?;
+
+            Ok(serde_json::to_value(res)?)
+        }
+        Command::VerifyProof { verifying_key, proof } => {
+            let vk = fs::read(verifying_key)?;
+            let proof: ProofJson = serde_json::from_slice(&fs::read(proof)?)?;
+
+            let inputs = public_inputs_from_hex(&proof.public_inputs)?;
+
+            Ok(json!({ "valid": wasm::verify(&vk, &from_hex(&proof.proof)?, &inputs)? }))
+        }
+        Command::ExportCalldata { proof, out } => {
+            let proof: ProofJson = serde_json::from_slice(&fs::read(proof)?)?;
+            let res = wasm::solidity_calldata(&from_hex(&proof.proof)?, &public_inputs_from_hex(&proof.public_inputs)?)?;
+            write(&out, &res)?;
+
+            Ok(serde_json::from_str(&res)?)
+        }
+        Command::ExportVerifier { verifying_key, out } => {
+            let vk = VerifyingKey::<Bn254>::deserialize_uncompressed(fs::read(verifying_key)?.as_slice())?;
+            write(&out, Groth16::export(&vk))?;
+
+            Ok(json!({ "verifier": out }))
+        }
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let res = run(Cli::parse().command)?;
+    print(&res)?;
+
+    // failed verifications are reported through the exit code as well
+    if res.get("valid") == Some(&Value::Bool(false)) {
+        process::exit(1);
+    }
     Ok(())
 }
@@ -0,0 +1,457 @@
+/*
+  Phase 1 of the BGM17 setup, shared by all circuits up to a size, and the
+  derivation of the initial phase-2 key of a circuit from it
+*/
+use crate::ceremony::{keccak256, next_hash, random_nonzero, same_ratio, CeremonyError, KnowledgeProof};
+use ark_bn254::{Bn254, Fr, G1Affine, G1Projective, G2Affine, G2Projective};
+use ark_ec::{msm::VariableBaseMSM, AffineCurve, ProjectiveCurve};
+use ark_ff::{BigInteger, One, PrimeField, UniformRand, Zero};
+use ark_groth16::{ProvingKey, VerifyingKey};
+use ark_poly::{EvaluationDomain, GeneralEvaluationDomain};
+use ark_relations::r1cs::{
+  ConstraintSynthesizer, ConstraintSystem, OptimizationGoal, SynthesisError, SynthesisMode,
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use ark_std::rand::{CryptoRng, Rng};
+
+/**
+ * Powers of tau for circuits over a domain of up to n elements:
+ * `[tau^i]_1` for i < 2n - 1, `[tau^i]_2`, `[alpha * tau^i]_1` and
+ * `[beta * tau^i]_1` for i < n, and `[beta]_2`. tau, alpha and beta are the
+ * products of the secrets of the participants.
+ */
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PowersOfTau {
+  pub tau_g1: Vec<G1Affine>,
+  pub tau_g2: Vec<G2Affine>,
+  pub alpha_tau_g1: Vec<G1Affine>,
+  pub beta_tau_g1: Vec<G1Affine>,
+  pub beta_g2: G2Affine,
+}
+
+impl PowersOfTau {
+  /**
+   * Powers before any contribution, i.e. for tau = alpha = beta = 1, over a
+   * domain of `size` elements rounded up to a power of two
+   */
+  pub fn new(size: usize) -> Self {
+    let n = size.next_power_of_two().max(2);
+    let g1 = G1Affine::prime_subgroup_generator();
+    let g2 = G2Affine::prime_subgroup_generator();
+    Self {
+      tau_g1: vec![g1; 2 * n - 1],
+      tau_g2: vec![g2; n],
+      alpha_tau_g1: vec![g1; n],
+      beta_tau_g1: vec![g1; n],
+      beta_g2: g2,
+    }
+  }
+
+  /**
+   * Powers of tau from untrusted uncompressed bytes: G1 points must be on
+   * the curve and G2 points in the prime-order subgroup
+   */
+  pub fn from_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+    let powers = Self::deserialize_unchecked(bytes)?;
+
+    let mut g1 = powers.tau_g1.iter().chain(&powers.alpha_tau_g1).chain(&powers.beta_tau_g1);
+    let mut g2 = powers.tau_g2.iter().chain(core::slice::from_ref(&powers.beta_g2));
+    if !g1.all(|p| p.is_on_curve()) || !g2.all(|p| p.is_on_curve() && p.is_in_correct_subgroup_assuming_on_curve()) {
+      return Err(SerializationError::InvalidData);
+    }
+    Ok(powers)
+  }
+
+  /**
+   * Largest domain size of the circuits
+   */
+  pub fn size(&self) -> usize {
+    self.tau_g2.len()
+  }
+}
+
+/**
+ * Phase-1 contribution: the secrets of the participant multiply tau, alpha
+ * and beta
+ */
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PowersOfTauContribution {
+  /// `[tau]_1`, `[alpha]_1` and `[beta]_1` after the contribution
+  pub tau_g1: G1Affine,
+  pub alpha_g1: G1Affine,
+  pub beta_g1: G1Affine,
+  pub tau_proof: KnowledgeProof,
+  pub alpha_proof: KnowledgeProof,
+  pub beta_proof: KnowledgeProof,
+}
+
+/**
+ * Contributions to powers of tau, in order. The hash chain starts from the
+ * size of the powers, which fixes the powers before any contribution.
+ */
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PowersOfTauTranscript {
+  pub size: u64,
+  pub contributions: Vec<PowersOfTauContribution>,
+}
+
+impl PowersOfTauTranscript {
+  pub fn new(powers: &PowersOfTau) -> Self {
+    Self { size: powers.size() as u64, contributions: vec![] }
+  }
+
+  /**
+   * Hash chained over the contributions. Participants check that their
+   * contribution hash is in the chain.
+   */
+  pub fn hash(&self) -> [u8; 32] {
+    let initial = keccak256(&[b"powers of tau", &self.size.to_le_bytes()]);
+    self.contributions.iter().fold(initial, |h, c| next_hash(&h, c))
+  }
+
+  pub fn len(&self) -> usize {
+    self.contributions.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.contributions.is_empty()
+  }
+}
+
+/*
+  points[i] * c * x^i
+*/
+fn scale_powers<G: AffineCurve<ScalarField = Fr>>(points: &mut Vec<G>, x: Fr, c: Fr) {
+  let mut factor = c;
+  let res: Vec<G::Projective> = points
+    .iter()
+    .map(|p| {
+      let q = p.mul(factor);
+      factor *= x;
+      q
+    })
+    .collect();
+  *points = G::Projective::batch_normalization_into_affine(&res);
+}
+
+/**
+ * Contributes fresh secrets from `rng` to `powers`, in place, and appends the
+ * contribution to `transcript`. Returns the new transcript hash.
+ */
+pub fn contribute_powers_of_tau<R: Rng + CryptoRng>(
+  powers: &mut PowersOfTau,
+  transcript: &mut PowersOfTauTranscript,
+  rng: &mut R,
+) -> [u8; 32] {
+  let (tau, alpha, beta) = (random_nonzero(rng), random_nonzero(rng), random_nonzero(rng));
+  let hash = transcript.hash();
+  let tau_proof = KnowledgeProof::new(&hash, tau, rng);
+  let alpha_proof = KnowledgeProof::new(&hash, alpha, rng);
+  let beta_proof = KnowledgeProof::new(&hash, beta, rng);
+
+  scale_powers(&mut powers.tau_g1, tau, Fr::one());
+  scale_powers(&mut powers.tau_g2, tau, Fr::one());
+  scale_powers(&mut powers.alpha_tau_g1, tau, alpha);
+  scale_powers(&mut powers.beta_tau_g1, tau, beta);
+  powers.beta_g2 = powers.beta_g2.mul(beta).into_affine();
+
+  transcript.contributions.push(PowersOfTauContribution {
+    tau_g1: powers.tau_g1[1],
+    alpha_g1: powers.alpha_tau_g1[0],
+    beta_g1: powers.beta_tau_g1[0],
+    tau_proof,
+    alpha_proof,
+    beta_proof,
+  });
+  transcript.hash()
+}
+
+/*
+  (sum(x_i * p_i), sum(x_i * p_{i+1})) for random x_i: the points are
+  successive powers of a ratio if the two sums have that ratio, but with a
+  negligible probability
+*/
+fn shifted_combinations<G: AffineCurve<ScalarField = Fr>, R: Rng>(points: &[G], rng: &mut R) -> (G, G) {
+  let scalars: Vec<_> = (1..points.len()).map(|_| Fr::rand(rng).into_repr()).collect();
+  (
+    VariableBaseMSM::multi_scalar_mul(&points[..points.len() - 1], &scalars).into_affine(),
+    VariableBaseMSM::multi_scalar_mul(&points[1..], &scalars).into_affine(),
+  )
+}
+
+/**
+ * Verifies that `powers` result from the contributions of `transcript`.
+ * Returns the transcript hash.
+ */
+pub fn verify_powers_of_tau<R: Rng + CryptoRng>(
+  powers: &PowersOfTau,
+  transcript: &PowersOfTauTranscript,
+  rng: &mut R,
+) -> Result<[u8; 32], CeremonyError> {
+  let n = powers.size();
+  let g1 = G1Affine::prime_subgroup_generator();
+  let g2 = G2Affine::prime_subgroup_generator();
+  if transcript.size != n as u64
+    || n < 2
+    || !n.is_power_of_two()
+    || powers.tau_g1.len() != 2 * n - 1
+    || powers.alpha_tau_g1.len() != n
+    || powers.beta_tau_g1.len() != n
+    || powers.tau_g1[0] != g1
+    || powers.tau_g2[0] != g2
+  {
+    return Err(CeremonyError::InvalidPowers);
+  }
+
+  let mut hash = PowersOfTauTranscript { size: transcript.size, contributions: vec![] }.hash();
+  let (mut tau, mut alpha, mut beta) = (g1, g1, g1);
+  for (i, c) in transcript.contributions.iter().enumerate() {
+    if !c.tau_proof.verify(&hash, tau, c.tau_g1)
+      || !c.alpha_proof.verify(&hash, alpha, c.alpha_g1)
+      || !c.beta_proof.verify(&hash, beta, c.beta_g1)
+    {
+      return Err(CeremonyError::InvalidContribution(i));
+    }
+    hash = next_hash(&hash, c);
+    (tau, alpha, beta) = (c.tau_g1, c.alpha_g1, c.beta_g1);
+  }
+
+  if powers.tau_g1[1] != tau || powers.alpha_tau_g1[0] != alpha || powers.beta_tau_g1[0] != beta {
+    return Err(CeremonyError::InvalidUpdate);
+  }
+
+  let tau_g2 = (g2, powers.tau_g2[1]);
+  let consistent = same_ratio(shifted_combinations(&powers.tau_g1, rng), tau_g2)
+    && same_ratio((g1, tau), shifted_combinations(&powers.tau_g2, rng))
+    && same_ratio(shifted_combinations(&powers.alpha_tau_g1, rng), tau_g2)
+    && same_ratio(shifted_combinations(&powers.beta_tau_g1, rng), tau_g2)
+    && same_ratio((g1, beta), (g2, powers.beta_g2));
+  if !consistent {
+    return Err(CeremonyError::InvalidPowers);
+  }
+
+  Ok(hash)
+}
+
+/*
+  Lagrange polynomials of the domain at tau, from the powers of tau: the
+  inverse FFT maps coefficients to evaluations on the domain
+*/
+fn lagrange<G: AffineCurve<ScalarField = Fr>>(domain: &GeneralEvaluationDomain<Fr>, powers: &[G]) -> Vec<G::Projective> {
+  domain.ifft(&powers[..domain.size()].iter().map(|p| p.into_projective()).collect::<Vec<_>>())
+}
+
+/*
+  p * c for a coefficient c of the constraint matrices: most of them are
+  small, or small negatives, and the multiplication only runs over the bits
+  of the scalar
+*/
+fn mul_coeff<G: ProjectiveCurve<ScalarField = Fr>>(p: &G, c: &Fr) -> G {
+  if c.is_one() {
+    return *p;
+  }
+  let neg = -*c;
+  if neg.into_repr().num_bits() < c.into_repr().num_bits() {
+    -p.mul(neg.into_repr())
+  } else {
+    p.mul(c.into_repr())
+  }
+}
+
+/**
+ * Initial phase-2 proving key of `circuit`, derived from `powers`: tau,
+ * alpha and beta come from phase 1, gamma and delta are 1. The derivation is
+ * deterministic, so anyone can check the initial key of a ceremony against
+ * the powers of tau. It runs inverse FFTs over curve points of the size of
+ * the circuit, which takes long for large circuits.
+ */
+pub fn initial_proving_key<C: ConstraintSynthesizer<Fr>>(
+  circuit: C,
+  powers: &PowersOfTau,
+) -> Result<ProvingKey<Bn254>, SynthesisError> {
+  // the constraint system of `generate_random_parameters`
+  let cs = ConstraintSystem::new_ref();
+  cs.set_optimization_goal(OptimizationGoal::Constraints);
+  cs.set_mode(SynthesisMode::Setup);
+  circuit.generate_constraints(cs.clone())?;
+  cs.finalize();
+
+  let num_constraints = cs.num_constraints();
+  let num_instance_variables = cs.num_instance_variables();
+  let num_variables = num_instance_variables + cs.num_witness_variables();
+  let domain = GeneralEvaluationDomain::<Fr>::new(num_constraints + num_instance_variables)
+    .ok_or(SynthesisError::PolynomialDegreeTooLarge)?;
+  let n = domain.size();
+  if n > powers.size() {
+    return Err(SynthesisError::PolynomialDegreeTooLarge);
+  }
+  let matrices = cs.to_matrices().ok_or(SynthesisError::MissingCS)?;
+
+  let l_g1 = lagrange(&domain, &powers.tau_g1);
+  let l_g2 = lagrange(&domain, &powers.tau_g2);
+  let alpha_l_g1 = lagrange(&domain, &powers.alpha_tau_g1);
+  let beta_l_g1 = lagrange(&domain, &powers.beta_tau_g1);
+
+  // u_k, v_k and beta * u_k + alpha * v_k + w_k at tau, for every variable k
+  let mut a = vec![G1Projective::zero(); num_variables];
+  let mut b_g1 = vec![G1Projective::zero(); num_variables];
+  let mut b_g2 = vec![G2Projective::zero(); num_variables];
+  let mut l = vec![G1Projective::zero(); num_variables];
+
+  // the inputs are bound by the constraints x_k * 0 = 0 that follow the others
+  for k in 0..num_instance_variables {
+    a[k] += l_g1[num_constraints + k];
+    l[k] += beta_l_g1[num_constraints + k];
+  }
+  for j in 0..num_constraints {
+    for (c, k) in &matrices.a[j] {
+      a[*k] += mul_coeff(&l_g1[j], c);
+      l[*k] += mul_coeff(&beta_l_g1[j], c);
+    }
+    for (c, k) in &matrices.b[j] {
+      b_g1[*k] += mul_coeff(&l_g1[j], c);
+      b_g2[*k] += mul_coeff(&l_g2[j], c);
+      l[*k] += mul_coeff(&alpha_l_g1[j], c);
+    }
+    for (c, k) in &matrices.c[j] {
+      l[*k] += mul_coeff(&l_g1[j], c);
+    }
+  }
+
+  // t(tau) * tau^i = tau^(n + i) - tau^i
+  let h: Vec<_> = (0..n - 1)
+    .map(|i| powers.tau_g1[n + i].into_projective() - powers.tau_g1[i].into_projective())
+    .collect();
+
+  let g1 = G1Affine::prime_subgroup_generator();
+  let g2 = G2Affine::prime_subgroup_generator();
+  let vk = VerifyingKey {
+    alpha_g1: powers.alpha_tau_g1[0],
+    beta_g2: powers.beta_g2,
+    gamma_g2: g2,
+    delta_g2: g2,
+    gamma_abc_g1: G1Projective::batch_normalization_into_affine(&l[..num_instance_variables]),
+  };
+  Ok(ProvingKey {
+    vk,
+    beta_g1: powers.beta_tau_g1[0],
+    delta_g1: g1,
+    a_query: G1Projective::batch_normalization_into_affine(&a),
+    b_g1_query: G1Projective::batch_normalization_into_affine(&b_g1),
+    b_g2_query: G2Projective::batch_normalization_into_affine(&b_g2),
+    h_query: G1Projective::batch_normalization_into_affine(&h),
+    l_query: G1Projective::batch_normalization_into_affine(&l[num_instance_variables..]),
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{contribute, test_utils::SquareCircuit, verify_transcript, Transcript};
+  use ark_groth16::{create_random_proof, prepare_verifying_key, verify_proof};
+  use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+
+  fn powers_of_tau(size: usize, contributions: usize, rng: &mut ChaCha20Rng) -> (PowersOfTau, PowersOfTauTranscript) {
+    let mut powers = PowersOfTau::new(size);
+    let mut transcript = PowersOfTauTranscript::new(&powers);
+    for _ in 0..contributions {
+      contribute_powers_of_tau(&mut powers, &mut transcript, rng);
+    }
+    (powers, transcript)
+  }
+
+  #[test]
+  fn test_powers_of_tau() {
+    let mut rng = ChaCha20Rng::from_seed([0; 32]);
+    let (powers, transcript) = powers_of_tau(3, 2, &mut rng);
+    assert_eq!((powers.size(), powers.tau_g1.len()), (4, 7));
+    assert_eq!(verify_powers_of_tau(&powers, &transcript, &mut rng), Ok(transcript.hash()));
+
+    let mut bytes = vec![];
+    powers.serialize_uncompressed(&mut bytes).unwrap();
+    assert_eq!(PowersOfTau::from_bytes(&bytes).unwrap(), powers);
+    let mut bytes = vec![];
+    transcript.serialize(&mut bytes).unwrap();
+    assert_eq!(PowersOfTauTranscript::deserialize(bytes.as_slice()).unwrap(), transcript);
+
+    // a point off the curve
+    let mut bad = powers.clone();
+    let p = bad.tau_g1[2];
+    bad.tau_g1[2] = G1Affine::new(p.x, p.y + p.x, p.infinity);
+    let mut bytes = vec![];
+    bad.serialize_uncompressed(&mut bytes).unwrap();
+    assert!(PowersOfTau::from_bytes(&bytes).is_err());
+  }
+
+  #[test]
+  fn test_powers_of_tau_tampering() {
+    let mut rng = ChaCha20Rng::from_seed([0; 32]);
+    let (powers, transcript) = powers_of_tau(4, 2, &mut rng);
+
+    // a contribution without knowledge of its secret
+    let mut bad = transcript.clone();
+    bad.contributions[1].alpha_proof.s_x = bad.contributions[1].alpha_proof.s;
+    assert_eq!(verify_powers_of_tau(&powers, &bad, &mut rng), Err(CeremonyError::InvalidContribution(1)));
+
+    // a power of tau of another tau
+    let mut bad = powers.clone();
+    bad.tau_g1[3] = bad.tau_g1[2];
+    assert_eq!(verify_powers_of_tau(&bad, &transcript, &mut rng), Err(CeremonyError::InvalidPowers));
+
+    let mut bad = powers.clone();
+    bad.beta_g2 = bad.tau_g2[1];
+    assert_eq!(verify_powers_of_tau(&bad, &transcript, &mut rng), Err(CeremonyError::InvalidPowers));
+
+    // alpha, without a contribution
+    let mut bad = powers.clone();
+    scale_powers(&mut bad.alpha_tau_g1, Fr::one(), Fr::from(2u8));
+    assert_eq!(verify_powers_of_tau(&bad, &transcript, &mut rng), Err(CeremonyError::InvalidUpdate));
+
+    let (other, _) = powers_of_tau(8, 2, &mut rng);
+    assert_eq!(verify_powers_of_tau(&other, &transcript, &mut rng), Err(CeremonyError::InvalidPowers));
+  }
+
+  #[test]
+  fn test_lagrange() {
+    let tau = Fr::from(5u8);
+    let mut powers = PowersOfTau::new(8);
+    scale_powers(&mut powers.tau_g1, tau, Fr::one());
+
+    let domain = GeneralEvaluationDomain::<Fr>::new(8).unwrap();
+    let expected: Vec<_> = domain
+      .evaluate_all_lagrange_coefficients(tau)
+      .iter()
+      .map(|l| G1Affine::prime_subgroup_generator().mul(*l))
+      .collect();
+    assert_eq!(lagrange(&domain, &powers.tau_g1), expected);
+  }
+
+  #[test]
+  fn test_initial_proving_key() {
+    let mut rng = ChaCha20Rng::from_seed([0; 32]);
+    let (powers, _) = powers_of_tau(4, 1, &mut rng);
+    let circuit = SquareCircuit(Fr::from(3u8));
+    let initial = initial_proving_key(circuit.clone(), &powers).unwrap();
+    assert_eq!(initial_proving_key(circuit.clone(), &powers).unwrap(), initial);
+
+    // phase 2 on top of the initial key
+    let mut pk = initial.clone();
+    let mut transcript = Transcript::new(&initial);
+    contribute(&mut pk, &mut transcript, &mut rng);
+    assert_eq!(verify_transcript(&initial, &pk, &transcript, &mut rng), Ok(transcript.hash()));
+
+    let pvk = prepare_verifying_key(&pk.vk);
+    let proof = create_random_proof(circuit.clone(), &pk, &mut rng).unwrap();
+    assert!(verify_proof(&pvk, &proof, &[Fr::from(9u8)]).unwrap());
+    assert!(!verify_proof(&pvk, &proof, &[Fr::from(4u8)]).unwrap());
+
+    // the transcript is tied to the powers of tau the initial key derives from
+    let (other, _) = powers_of_tau(4, 1, &mut rng);
+    let other_initial = initial_proving_key(circuit.clone(), &other).unwrap();
+    assert_eq!(verify_transcript(&other_initial, &pk, &transcript, &mut rng), Err(CeremonyError::InitialKey));
+
+    // the circuit needs a domain of 4 elements
+    let (small, _) = powers_of_tau(2, 1, &mut rng);
+    assert!(matches!(initial_proving_key(circuit, &small), Err(SynthesisError::PolynomialDegreeTooLarge)));
+  }
+}
@@ -0,0 +1,195 @@
+use ark_bn254::{Bn254, Fq2, Fr, G1Affine, G2Affine};
+use ark_ff::{BigInteger, PrimeField};
+use ark_groth16::Proof;
+use serde_json::{json, Value};
+use tiny_keccak::{Hasher, Keccak};
+
+/**
+ * A 32-byte big-endian EVM word
+ */
+pub type Word = [u8; 32];
+
+fn word<F: PrimeField>(x: &F) -> Word {
+  let mut res = [0u8; 32];
+  let bytes = x.into_repr().to_bytes_be();
+  res[32 - bytes.len()..].copy_from_slice(&bytes);
+  res
+}
+
+fn word_hex(x: &Word) -> String {
+  format!("0x{}", hex::encode(x))
+}
+
+fn keccak256(data: &[u8]) -> [u8; 32] {
+  let mut res = [0u8; 32];
+  let mut keccak = Keccak::v256();
+  keccak.update(data);
+  keccak.finalize(&mut res);
+  res
+}
+
+fn g1(p: &G1Affine) -> [Word; 2] {
+  [word(&p.x), word(&p.y)]
+}
+
+/*
+  The exported verifier writes its own G2 constants as [c0, c1] and swaps
+  them into the [c1, c0] order of the pairing precompile (EIP-197) in
+  `Pairing.pairing`: proof points must follow the verifier, not the
+  precompile.
+*/
+fn fq2(x: &Fq2) -> [Word; 2] {
+  [word(&x.c0), word(&x.c1)]
+}
+
+fn g2(p: &G2Affine) -> [[Word; 2]; 2] {
+  [fq2(&p.x), fq2(&p.y)]
+}
+
+/**
+ * Groth16 proof in the layout of the `Proof` struct of the exported
+ * `SolidityVerifier`: `[[a.x, a.y], [[b.x.c0, b.x.c1], [b.y.c0, b.y.c1]], [c.x, c.y]]`
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SolidityProof {
+  pub a: [Word; 2],
+  pub b: [[Word; 2]; 2],
+  pub c: [Word; 2],
+}
+
+impl From<&Proof<Bn254>> for SolidityProof {
+  fn from(proof: &Proof<Bn254>) -> Self {
+    Self { a: g1(&proof.a), b: g2(&proof.b), c: g1(&proof.c) }
+  }
+}
+
+impl SolidityProof {
+  pub fn to_json(&self) -> Value {
+    let hex = |x: &[Word; 2]| [word_hex(&x[0]), word_hex(&x[1])];
+    json!([hex(&self.a), [hex(&self.b[0]), hex(&self.b[1])], hex(&self.c)])
+  }
+
+  pub fn words(&self) -> Vec<Word> {
+    [self.a, self.b[0], self.b[1], self.c].concat()
+  }
+}
+
+/**
+ * Arguments of `verifyTx(Proof proof, uint[n] input)` in the exported
+ * `SolidityVerifier`
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SolidityCalldata {
+  pub proof: SolidityProof,
+  pub inputs: Vec<Word>,
+}
+
+impl SolidityCalldata {
+  pub fn new(proof: &Proof<Bn254>, public_inputs: &[Fr]) -> Self {
+    Self {
+      proof: proof.into(),
+      inputs: public_inputs.iter().map(word).collect(),
+    }
+  }
+
+  /**
+   * `[proof, input]`, as passed to `verifyTx` from Remix or ethers
+   */
+  pub fn to_json(&self) -> Value {
+    json!([self.proof.to_json(), self.inputs.iter().map(word_hex).collect::<Vec<_>>()])
+  }
+
+  pub fn signature(&self) -> String {
+    format!(
+      "verifyTx(((uint256,uint256),(uint256[2],uint256[2]),(uint256,uint256)),uint256[{}])",
+      self.inputs.len()
+    )
+  }
+
+  pub fn selector(&self) -> [u8; 4] {
+    let hash = keccak256(self.signature().as_bytes());
+    [hash[0], hash[1], hash[2], hash[3]]
+  }
+
+  /**
+   * ABI encoding of the arguments. Every member is static, so the words
+   * are laid out in order without offsets.
+   */
+  pub fn abi_encode(&self) -> Vec<u8> {
+    [self.proof.words(), self.inputs.clone()].concat().concat()
+  }
+
+  /**
+   * Transaction data of the `verifyTx` call: selector and arguments
+   */
+  pub fn calldata(&self) -> Vec<u8> {
+    [self.selector().as_ref(), &self.abi_encode()].concat()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use ark_bn254::Fq;
+  use ark_groth16::{create_random_proof, generate_random_parameters, prepare_verifying_key, verify_proof, Groth16};
+  use crate::test_utils::SquareCircuit;
+  use arkworks_solidity_verifier::SolidityVerifier;
+  use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+
+  #[test]
+  fn test_solidity_calldata() {
+    let mut rng = ChaCha20Rng::from_seed([0; 32]);
+    let circuit = SquareCircuit(Fr::from(3u8));
+    let params = generate_random_parameters::<Bn254, _, _>(circuit.clone(), &mut rng).unwrap();
+    let proof = create_random_proof(circuit, &params, &mut rng).unwrap();
+    let inputs = [Fr::from(9u8)];
+    assert!(verify_proof(&prepare_verifying_key(&params.vk), &proof, &inputs).unwrap());
+
+    let calldata = SolidityCalldata::new(&proof, &inputs);
+    let json = calldata.to_json();
+    assert_eq!(json[0][0][0], word_hex(&word(&proof.a.x)));
+    assert_eq!(json[0][1][0][1], word_hex(&word(&proof.b.x.c1)));
+    assert_eq!(json[0][1][1][0], word_hex(&word(&proof.b.y.c0)));
+    assert_eq!(json[0][2][1], word_hex(&word(&proof.c.y)));
+    assert_eq!(json[1][0], format!("0x{}09", "0".repeat(62)));
+
+    let encoded = calldata.abi_encode();
+    assert_eq!(encoded.len(), 9 * 32);
+    assert_eq!(encoded[2 * 32..3 * 32], word(&proof.b.x.c0));
+    assert_eq!(encoded[3 * 32..4 * 32], word(&proof.b.x.c1));
+    assert_eq!(encoded[8 * 32..], word(&inputs[0]));
+    assert_eq!(calldata.calldata()[4..], encoded);
+    assert_eq!(calldata.calldata()[..4], calldata.selector());
+
+    /*
+      `Pairing.pairing` passes [X[1], X[0], Y[1], Y[0]] to the precompile,
+      which reads each coordinate as (imaginary, real)
+    */
+    let w = |i: usize| Fq::from_be_bytes_mod_order(&encoded[i * 32..(i + 1) * 32]);
+    let precompile = [w(3), w(2), w(5), w(4)];
+    assert_eq!(Fq2::new(precompile[1], precompile[0]), proof.b.x);
+    assert_eq!(Fq2::new(precompile[3], precompile[2]), proof.b.y);
+
+    // G2 points are ordered as the constants of the exported verifier
+    let verifier = Groth16::export(&params.vk).to_lowercase();
+    for p in [params.vk.beta_g2, params.vk.gamma_g2, params.vk.delta_g2] {
+      let [x, y] = g2(&p);
+      let constant = format!(
+        "[uint256({}), uint256({})], [uint256({}), uint256({})]",
+        word_hex(&x[0]), word_hex(&x[1]), word_hex(&y[0]), word_hex(&y[1]),
+      );
+      assert!(verifier.contains(&constant));
+    }
+  }
+
+  #[test]
+  fn test_solidity_selector() {
+    let proof = SolidityProof { a: [[0; 32]; 2], b: [[[0; 32]; 2]; 2], c: [[0; 32]; 2] };
+    let calldata = SolidityCalldata { proof, inputs: vec![[0; 32]; 3] };
+    assert_eq!(
+      calldata.signature(),
+      "verifyTx(((uint256,uint256),(uint256[2],uint256[2]),(uint256,uint256)),uint256[3])"
+    );
+    assert_eq!(keccak256(b"transfer(address,uint256)")[..4], [0xa9, 0x05, 0x9c, 0xbb]);
+  }
+}
@@ -0,0 +1,19 @@
+use ark_bn254::Fr;
+use ark_r1cs_std::{alloc::AllocVar, eq::EqGadget, fields::fp::FpVar};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, Result};
+
+/**
+ * Knowledge of a square root of the public input: a circuit small enough
+ * to set up and prove quickly, for the tests that do not depend on the
+ * Falcon circuits
+ */
+#[derive(Clone)]
+pub struct SquareCircuit(pub Fr);
+
+impl ConstraintSynthesizer<Fr> for SquareCircuit {
+  fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<()> {
+    let x = FpVar::new_witness(cs.clone(), || Ok(self.0))?;
+    let y = FpVar::new_input(cs, || Ok(self.0 * self.0))?;
+    (&x * &x).enforce_equal(&y)
+  }
+}
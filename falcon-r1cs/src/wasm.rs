@@ -0,0 +1,308 @@
+use crate::{gadgets, FalconVerificationCircuit, SolidityCalldata};
+use ark_bn254::{Bn254, Fr};
+use ark_ff::{BigInteger, PrimeField};
+use ark_groth16::{create_random_proof, prepare_verifying_key, verify_proof, Proof, ProvingKey, VerifyingKey};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use falcon_rust::{Falcon1024, Falcon512, FalconParams, PublicKey, Signature, MODULUS};
+use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+use serde_json::json;
+use std::fmt::Display;
+use wasm_bindgen::prelude::*;
+
+/*
+  JavaScript bridge. Keys, signatures, messages and proofs are passed as
+  `Uint8Array`s. A single field element is returned as 0x-prefixed
+  big-endian hex, the uint256 expected by the Solidity verifier, and a
+  list of them as a `Uint8Array` of 32-byte big-endian words. The
+  parameter set is read from the public key header. Errors are thrown as
+  strings.
+*/
+
+const FR_LEN: usize = 32;
+
+fn js_err<E: Display>(e: E) -> String {
+  e.to_string()
+}
+
+fn fr_to_hex(x: &Fr) -> String {
+  format!("0x{}", hex::encode(x.into_repr().to_bytes_be()))
+}
+
+fn fr_from_bytes(bytes: &[u8]) -> Result<Fr, String> {
+  let x = Fr::from_be_bytes_mod_order(bytes);
+  if x.into_repr().to_bytes_be() != bytes {
+    return Err(format!("non-canonical field element 0x{}", hex::encode(bytes)));
+  }
+  Ok(x)
+}
+
+fn public_inputs_from_bytes(bytes: &[u8]) -> Result<Vec<Fr>, String> {
+  let chunks = bytes.chunks_exact(FR_LEN);
+  if !chunks.remainder().is_empty() {
+    return Err(format!("invalid public inputs length: {}", bytes.len()));
+  }
+  chunks.map(fr_from_bytes).collect()
+}
+
+/**
+ * Reads an uncompressed proving key from an untrusted source, such as the
+ * download of the front-end or a ceremony participant. The group law and
+ * the pairing checks of the ceremony only hold on the prime-order subgroup,
+ * so a malformed key is rejected here rather than turned into proofs that
+ * do not verify or contributions whose checks mean nothing. The checked
+ * deserialization multiplies every point by the group order, which
+ * dominates the proving time. G1 has cofactor 1 on BN254, so the G1 points
+ * only need to be on the curve and only the G2 points get the subgroup
+ * check.
+ */
+pub fn proving_key_from_bytes(bytes: &[u8]) -> Result<ProvingKey<Bn254>, String> {
+  let pk = ProvingKey::<Bn254>::deserialize_unchecked(bytes).map_err(js_err)?;
+
+  let g1 = [pk.vk.alpha_g1, pk.beta_g1, pk.delta_g1];
+  let mut g1 = g1
+    .iter()
+    .chain(&pk.vk.gamma_abc_g1)
+    .chain(&pk.a_query)
+    .chain(&pk.b_g1_query)
+    .chain(&pk.h_query)
+    .chain(&pk.l_query);
+  let g2 = [pk.vk.beta_g2, pk.vk.gamma_g2, pk.vk.delta_g2];
+  let mut g2 = g2.iter().chain(&pk.b_g2_query);
+  if !g1.all(|p| p.is_on_curve()) || !g2.all(|p| p.is_on_curve() && p.is_in_correct_subgroup_assuming_on_curve()) {
+    return Err("invalid proving key: point not in the prime-order subgroup".into());
+  }
+  Ok(pk)
+}
+
+fn is_falcon1024(pk: &[u8]) -> bool {
+  pk.first().map(|h| (h & 0x0F) as usize) == Some(Falcon1024::LOG_N)
+}
+
+fn circuit<P: FalconParams>(pk: &[u8], msg: &[u8], sig: &[u8]) -> Result<FalconVerificationCircuit<P>, String> {
+  let pk = PublicKey::<P>::from_bytes(pk).map_err(js_err)?;
+  let sig = Signature::<P>::from_bytes(sig).map_err(js_err)?;
+  if !pk.verify_rust(msg, &sig) {
+    return Err("the signature does not match the message and public key".into());
+  }
+  Ok(FalconVerificationCircuit::build_circuit(pk, msg.to_vec(), sig))
+}
+
+/**
+ * Poseidon hash of an NTT polynomial given as 2-byte big-endian
+ * coefficients, e.g. `hash(pk_ntt)`
+ */
+#[wasm_bindgen]
+pub fn hash_from_poly(poly: &[u8]) -> Result<String, String> {
+  if poly.len() != 2 * Falcon512::N && poly.len() != 2 * Falcon1024::N {
+    return Err(format!("invalid polynomial length: {}", poly.len()));
+  }
+
+  let mut inputs = Vec::with_capacity(poly.len() / 2);
+  for c in poly.chunks_exact(2) {
+    let c = u16::from_be_bytes([c[0], c[1]]);
+    if c >= MODULUS {
+      return Err(format!("invalid coefficient: {}", c));
+    }
+    inputs.push(Fr::from(c));
+  }
+  gadgets::hash_chain(&inputs).map(|x| fr_to_hex(&x)).map_err(js_err)
+}
+
+/**
+ * Poseidon hash of an encoded public key, the owner hash `hash(pk)`
+ */
+#[wasm_bindgen]
+pub fn hash_from_pk(pk: &[u8]) -> Result<String, String> {
+  fn hash<P: FalconParams>(pk: &[u8]) -> Result<Fr, String> {
+    let pk = PublicKey::<P>::from_bytes(pk).map_err(js_err)?;
+    gadgets::hash_from_pk(&(&pk).into()).map_err(js_err)
+  }
+
+  let res = if is_falcon1024(pk) { hash::<Falcon1024>(pk)? } else { hash::<Falcon512>(pk)? };
+  Ok(fr_to_hex(&res))
+}
+
+/**
+ * Public inputs `[hash(pk_ntt), hash(hm_ntt), hash(pk)]` of the proof of
+ * `sig` over `msg`
+ */
+#[wasm_bindgen]
+pub fn public_inputs(pk: &[u8], msg: &[u8], sig: &[u8]) -> Result<Vec<u8>, String> {
+  let inputs = if is_falcon1024(pk) {
+    circuit::<Falcon1024>(pk, msg, sig)?.public_inputs()
+  } else {
+    circuit::<Falcon512>(pk, msg, sig)?.public_inputs()
+  };
+  Ok(inputs.iter().flat_map(|x| x.into_repr().to_bytes_be()).collect())
+}
+
+/**
+ * Groth16 proof of knowledge of `sig` over `msg` for `pk`, with the
+ * uncompressed proving key written by `setup`. The proof is compressed.
+ */
+#[wasm_bindgen]
+pub fn prove(proving_key: &[u8], pk: &[u8], msg: &[u8], sig: &[u8]) -> Result<Vec<u8>, String> {
+  let params = proving_key_from_bytes(proving_key)?;
+  let mut seed = [0u8; 32];
+  getrandom::getrandom(&mut seed).map_err(js_err)?;
+  let mut rng = ChaCha20Rng::from_seed(seed);
+
+  let proof = if is_falcon1024(pk) {
+    create_random_proof(circuit::<Falcon1024>(pk, msg, sig)?, &params, &mut rng)
+  } else {
+    create_random_proof(circuit::<Falcon512>(pk, msg, sig)?, &params, &mut rng)
+  }
+  .map_err(js_err)?;
+
+  let mut res = vec![];
+  proof.serialize(&mut res).map_err(js_err)?;
+  Ok(res)
+}
+
+/**
+ * Verifies a compressed proof against the uncompressed verifying key
+ * written by `setup` and the public inputs, as 32-byte big-endian words
+ */
+#[wasm_bindgen]
+pub fn verify(verifying_key: &[u8], proof: &[u8], public_inputs: &[u8]) -> Result<bool, String> {
+  let vk = VerifyingKey::<Bn254>::deserialize_uncompressed(verifying_key).map_err(js_err)?;
+  let proof = Proof::<Bn254>::deserialize(proof).map_err(js_err)?;
+
+  let inputs = public_inputs_from_bytes(public_inputs)?;
+  if inputs.len() + 1 != vk.gamma_abc_g1.len() {
+    return Err(format!("expected {} public inputs, found {}", vk.gamma_abc_g1.len() - 1, inputs.len()));
+  }
+
+  verify_proof(&prepare_verifying_key(&vk), &proof, &inputs).map_err(js_err)
+}
+
+/**
+ * JSON with the `verifyTx` arguments of the exported `SolidityVerifier`
+ * (`args`) and the hex-encoded transaction data of the call (`calldata`),
+ * for a compressed proof and its public inputs
+ */
+#[wasm_bindgen]
+pub fn solidity_calldata(proof: &[u8], public_inputs: &[u8]) -> Result<String, String> {
+  let proof = Proof::<Bn254>::deserialize(proof).map_err(js_err)?;
+  let calldata = SolidityCalldata::new(&proof, &public_inputs_from_bytes(public_inputs)?);
+
+  let res = json!({
+    "args": calldata.to_json(),
+    "calldata": format!("0x{}", hex::encode(calldata.calldata())),
+  });
+  Ok(res.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{hash_from_pk as native_hash_from_pk, hash_from_poly as native_hash_from_poly, test_utils::SquareCircuit};
+  use ark_bn254::{Fq, G1Affine};
+  use ark_ff::One;
+  use ark_groth16::generate_random_parameters;
+  use falcon_rust::{KeyPair512, NTTPolynomial, Polynomial};
+
+  #[test]
+  fn test_wasm_hashes() {
+    let keypair = KeyPair512::keygen_from_seed(b"falcon-r1cs test seed");
+    let message = "Testing message".as_bytes();
+    let sig = keypair.secret_key.sign(message);
+
+    let pk = Polynomial::from(&keypair.public_key);
+    let pk_ntt = NTTPolynomial::from(&pk);
+    let pk_ntt_bytes: Vec<u8> = pk_ntt.coeff().iter().flat_map(|c| c.to_be_bytes()).collect();
+
+    assert_eq!(hash_from_pk(keypair.public_key.as_bytes()).unwrap(), fr_to_hex(&native_hash_from_pk(&pk).unwrap()));
+    assert_eq!(hash_from_poly(&pk_ntt_bytes).unwrap(), fr_to_hex(&native_hash_from_poly(&pk_ntt).unwrap()));
+
+    let inputs = public_inputs(keypair.public_key.as_bytes(), message, sig.as_bytes()).unwrap();
+    let expected = FalconVerificationCircuit::build_circuit(keypair.public_key, message.to_vec(), sig).public_inputs();
+    assert_eq!(inputs.chunks(FR_LEN).map(|x| fr_from_bytes(x).unwrap()).collect::<Vec<_>>(), expected);
+
+    assert!(hash_from_poly(&pk_ntt_bytes[2..]).is_err());
+    assert!(hash_from_poly(&[0xFF; 1024]).is_err());
+    assert!(hash_from_pk(&keypair.public_key.as_bytes()[1..]).is_err());
+    assert!(public_inputs(keypair.public_key.as_bytes(), b"another message", sig.as_bytes()).is_err());
+    assert!(fr_from_bytes(&[0xFF; FR_LEN]).is_err());
+  }
+
+  /*
+    `verify` and `solidity_calldata` are checked with the small
+    `SquareCircuit`, `test_wasm_prove` covers the Falcon circuit
+  */
+  #[test]
+  fn test_wasm_verify() {
+    let mut rng = ChaCha20Rng::from_seed([0; 32]);
+    let circuit = SquareCircuit(Fr::from(3u8));
+    let params = generate_random_parameters::<Bn254, _, _>(circuit.clone(), &mut rng).unwrap();
+    let (mut vk_bytes, mut proof_bytes) = (vec![], vec![]);
+    params.vk.serialize_uncompressed(&mut vk_bytes).unwrap();
+    create_random_proof(circuit, &params, &mut rng).unwrap().serialize(&mut proof_bytes).unwrap();
+
+    let mut inputs = Fr::from(9u8).into_repr().to_bytes_be();
+    assert!(verify(&vk_bytes, &proof_bytes, &inputs).unwrap());
+
+    inputs[FR_LEN - 1] ^= 1;
+    assert!(!verify(&vk_bytes, &proof_bytes, &inputs).unwrap());
+    assert!(verify(&vk_bytes, &proof_bytes, &[inputs.clone(), inputs.clone()].concat()).is_err());
+    assert!(verify(&vk_bytes, &proof_bytes, &inputs[1..]).is_err());
+    assert!(verify(&vk_bytes[1..], &proof_bytes, &inputs).is_err());
+    assert!(verify(&vk_bytes, &proof_bytes[1..], &inputs).is_err());
+
+    let res: serde_json::Value = serde_json::from_str(&solidity_calldata(&proof_bytes, &inputs).unwrap()).unwrap();
+    let proof = Proof::<Bn254>::deserialize(proof_bytes.as_slice()).unwrap();
+    let calldata = SolidityCalldata::new(&proof, &[fr_from_bytes(&inputs).unwrap()]);
+    assert_eq!(res["args"], calldata.to_json());
+    assert_eq!(res["calldata"], format!("0x{}", hex::encode(calldata.calldata())));
+    assert!(solidity_calldata(&proof_bytes, &inputs[1..]).is_err());
+  }
+
+  #[test]
+  fn test_wasm_prove() {
+    let keypair = KeyPair512::keygen_from_seed(b"falcon-r1cs test seed");
+    let message = "Testing message".as_bytes();
+    let sig = keypair.secret_key.sign(message);
+    let (pk, sig_bytes) = (keypair.public_key.as_bytes(), sig.as_bytes());
+
+    let mut rng = ChaCha20Rng::from_seed([0; 32]);
+    let circuit = FalconVerificationCircuit::build_circuit(keypair.public_key, message.to_vec(), sig);
+    let params = generate_random_parameters::<Bn254, _, _>(circuit, &mut rng).unwrap();
+    let (mut pk_bytes, mut vk_bytes) = (vec![], vec![]);
+    params.serialize_uncompressed(&mut pk_bytes).unwrap();
+    params.vk.serialize_uncompressed(&mut vk_bytes).unwrap();
+
+    let proof = prove(&pk_bytes, pk, message, sig_bytes).unwrap();
+    let inputs = public_inputs(pk, message, sig_bytes).unwrap();
+    assert!(verify(&vk_bytes, &proof, &inputs).unwrap());
+
+    // the public inputs of another message are rejected
+    let message2 = "Another testing message".as_bytes();
+    let sig2 = keypair.secret_key.sign(message2);
+    let inputs2 = public_inputs(pk, message2, sig2.as_bytes()).unwrap();
+    assert!(!verify(&vk_bytes, &proof, &inputs2).unwrap());
+
+    // truncated proving keys and points off the curve are rejected
+    assert!(prove(&pk_bytes[..pk_bytes.len() - 1], pk, message, sig_bytes).is_err());
+    let mut tampered = params;
+    let p = tampered.a_query[1];
+    tampered.a_query[1] = G1Affine::new(p.x, p.y + Fq::one(), p.infinity);
+    let mut tampered_bytes = vec![];
+    tampered.serialize_uncompressed(&mut tampered_bytes).unwrap();
+    assert!(prove(&tampered_bytes, pk, message, sig_bytes).is_err());
+  }
+
+  #[test]
+  fn test_wasm_prove_errors() {
+    let keypair = KeyPair512::keygen_from_seed(b"falcon-r1cs test seed");
+    let message = "Testing message".as_bytes();
+    let sig = keypair.secret_key.sign(message);
+    let (pk, sig) = (keypair.public_key.as_bytes(), sig.as_bytes());
+
+    assert!(prove(&[0; 64], pk, message, sig).is_err());
+    assert!(circuit::<Falcon512>(pk, message, sig).is_ok());
+    assert!(circuit::<Falcon512>(pk, b"another message", sig).is_err());
+    assert!(circuit::<Falcon512>(&pk[1..], message, sig).is_err());
+    assert!(circuit::<Falcon512>(pk, message, &sig[1..]).is_err());
+    assert!(circuit::<Falcon1024>(pk, message, sig).is_err());
+  }
+}
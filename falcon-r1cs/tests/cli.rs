@@ -0,0 +1,174 @@
+use std::{fs, path::PathBuf, process::Command};
+
+use ark_bn254::{Bn254, Fq, Fr, G1Affine};
+use ark_ff::One;
+use ark_groth16::generate_random_parameters;
+use ark_serialize::CanonicalSerialize;
+use falcon_r1cs::{wasm, Transcript};
+use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+use serde_json::{json, Value};
+
+// the ceremony commands do not depend on the circuit: a small one keeps them fast
+#[path = "../src/test_utils.rs"]
+mod test_utils;
+use test_utils::SquareCircuit;
+
+fn run(dir: &PathBuf, args: &[&str]) -> (bool, Value) {
+  let output = Command::new(env!("CARGO_BIN_EXE_falcon-r1cs"))
+    .current_dir(dir)
+    .args(args)
+    .output()
+    .unwrap();
+  (output.status.success(), serde_json::from_slice(&output.stdout).unwrap_or(Value::Null))
+}
+
+#[test]
+fn test_cli_keygen_sign_verify() {
+  for degree in ["512", "1024"] {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!("cli_{}", degree));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("msg.txt"), "Testing message").unwrap();
+    fs::write(dir.join("other.txt"), "Another message").unwrap();
+
+    let (ok, res) = run(&dir, &["keygen", "--degree", degree, "--seed", "00"]);
+    assert!(ok);
+    let pk = fs::read(dir.join("out/falcon_pk.bin")).unwrap();
+    assert_eq!(res["owner"], wasm::hash_from_pk(&pk).unwrap());
+    #[cfg(unix)]
+    {
+      use std::os::unix::fs::PermissionsExt;
+      let mode = fs::metadata(dir.join("out/falcon_sk.bin")).unwrap().permissions().mode();
+      assert_eq!(mode & 0o777, 0o600);
+    }
+
+    let (ok, res) = run(&dir, &["sign", "msg.txt"]);
+    assert!(ok);
+    assert_eq!(res["signature"], "out/falcon_sig.bin");
+
+    assert_eq!(run(&dir, &["verify", "msg.txt"]), (true, json!({ "valid": true })));
+    assert_eq!(run(&dir, &["verify", "other.txt"]), (false, json!({ "valid": false })));
+
+    // malformed proving keys and missing files are reported as errors
+    fs::write(dir.join("out/Verification_pk.bin"), [0u8; 64]).unwrap();
+    assert!(!run(&dir, &["prove", "msg.txt"]).0);
+    assert!(!run(&dir, &["verify", "missing.txt"]).0);
+  }
+}
+
+/*
+  Full Falcon-512 setup and proofs through the binary, from powers of tau of
+  2^20 elements: hours on a single core, even in optimized builds. It runs
+  on demand:
+    cargo test -p falcon-r1cs --test cli -- --ignored
+*/
+#[test]
+#[ignore]
+fn test_cli_setup_prove_export() {
+  let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("cli_proof");
+  fs::create_dir_all(&dir).unwrap();
+  fs::write(dir.join("msg.txt"), "Testing message").unwrap();
+  fs::write(dir.join("other.txt"), "Another message").unwrap();
+
+  assert!(run(&dir, &["keygen", "--seed", "00"]).0);
+  assert!(run(&dir, &["sign", "msg.txt"]).0);
+  assert!(run(&dir, &["powers-of-tau"]).0);
+  assert!(run(&dir, &["contribute-powers-of-tau"]).0);
+  assert!(run(&dir, &["setup"]).0);
+  let initial_pk = fs::read(dir.join("out/Verification_initial_pk.bin")).unwrap();
+  assert_eq!(initial_pk, fs::read(dir.join("out/Verification_pk.bin")).unwrap());
+  assert_eq!(run(&dir, &["verify-setup"]).1["valid"], true);
+
+  let (ok, proof) = run(&dir, &["prove", "msg.txt"]);
+  assert!(ok);
+  assert_eq!(serde_json::from_slice::<Value>(&fs::read(dir.join("out/proof.json")).unwrap()).unwrap(), proof);
+  assert_eq!(proof["public_inputs"].as_array().unwrap().len(), 3);
+  assert_eq!(run(&dir, &["verify-proof"]), (true, json!({ "valid": true })));
+
+  let (ok, res) = run(&dir, &["export-calldata"]);
+  assert!(ok);
+  let calldata = fs::read_to_string(dir.join("out/calldata.json")).unwrap();
+  assert_eq!(serde_json::from_str::<Value>(&calldata).unwrap(), res);
+  assert!(res["calldata"].as_str().unwrap().starts_with("0x"));
+  assert!(run(&dir, &["export-verifier"]).0);
+  assert!(fs::read_to_string(dir.join("out/Verification_verifier.sol")).unwrap().contains("verifyTx"));
+
+  // the signature of the message does not prove another one
+  assert!(!run(&dir, &["prove", "other.txt", "--proof", "out/other.json"]).0);
+
+  // the proof does not hold for the public inputs of another message
+  let mut tampered = proof;
+  assert!(run(&dir, &["sign", "other.txt", "--sig", "out/other_sig.bin"]).0);
+  assert!(run(&dir, &["prove", "other.txt", "--sig", "out/other_sig.bin", "--proof", "out/other.json"]).0);
+  let other: Value = serde_json::from_slice(&fs::read(dir.join("out/other.json")).unwrap()).unwrap();
+  tampered["public_inputs"] = other["public_inputs"].clone();
+  fs::write(dir.join("out/tampered.json"), tampered.to_string()).unwrap();
+  assert_eq!(run(&dir, &["verify-proof", "--proof", "out/tampered.json"]), (false, json!({ "valid": false })));
+}
+
+#[test]
+fn test_cli_powers_of_tau() {
+  let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("cli_powers_of_tau");
+  fs::create_dir_all(&dir).unwrap();
+
+  assert!(run(&dir, &["powers-of-tau", "--power", "3"]).0);
+  fs::copy(dir.join("out/powers_of_tau_transcript.bin"), dir.join("empty_transcript.bin")).unwrap();
+  // the setup needs a contribution
+  assert!(!run(&dir, &["setup"]).0);
+
+  for i in 1..=2 {
+    let (ok, res) = run(&dir, &["contribute-powers-of-tau"]);
+    assert!(ok);
+    assert_eq!(res["contribution"], i);
+  }
+  let (ok, res) = run(&dir, &["verify-powers-of-tau"]);
+  assert!(ok);
+  assert_eq!((res["valid"].clone(), res["contributions"].clone()), (json!(true), json!(2)));
+
+  // the powers without the transcript of their contributions
+  let args = ["verify-powers-of-tau", "--transcript", "empty_transcript.bin"];
+  let (ok, res) = run(&dir, &args);
+  assert!(!ok);
+  assert_eq!(res["valid"], false);
+
+  // too small for the verification circuit
+  assert!(!run(&dir, &["setup"]).0);
+}
+
+fn write_uncompressed(path: PathBuf, x: &impl CanonicalSerialize) {
+  let mut bytes = vec![];
+  x.serialize_uncompressed(&mut bytes).unwrap();
+  fs::write(path, bytes).unwrap();
+}
+
+#[test]
+fn test_cli_ceremony() {
+  let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("cli_ceremony");
+  fs::create_dir_all(dir.join("out")).unwrap();
+
+  let mut rng = ChaCha20Rng::from_seed([0; 32]);
+  let initial = generate_random_parameters::<Bn254, _, _>(SquareCircuit(Fr::from(3u8)), &mut rng).unwrap();
+  // what `setup` writes, for a small circuit
+  write_uncompressed(dir.join("out/Verification_initial_pk.bin"), &initial);
+  write_uncompressed(dir.join("out/Verification_pk.bin"), &initial);
+  write_uncompressed(dir.join("out/Verification_transcript.bin"), &Transcript::new(&initial));
+
+  for i in 1..=2 {
+    let (ok, res) = run(&dir, &["contribute"]);
+    assert!(ok);
+    assert_eq!(res["contribution"], i);
+  }
+  let (ok, res) = run(&dir, &["verify-ceremony"]);
+  assert!(ok);
+  assert_eq!((res["valid"].clone(), res["contributions"].clone()), (json!(true), json!(2)));
+
+  // keys from other participants are untrusted: a point off the curve is rejected
+  let mut tampered = initial;
+  let p = tampered.a_query[0];
+  tampered.a_query[0] = G1Affine::new(p.x, p.y + Fq::one(), p.infinity);
+  write_uncompressed(dir.join("tampered_pk.bin"), &tampered);
+  let args = ["verify-ceremony", "--initial-proving-key", "tampered_pk.bin"];
+  assert_eq!(run(&dir, &args), (false, Value::Null));
+  let args = ["verify-ceremony", "--proving-key", "tampered_pk.bin"];
+  assert_eq!(run(&dir, &args), (false, Value::Null));
+  assert!(!run(&dir, &["contribute", "--proving-key", "tampered_pk.bin"]).0);
+}
@@ -1,19 +1,28 @@
 mod ntt_poly;
 mod param;
 mod poly;
+mod signed_poly;
 
-use crate::{LOG_N, MODULUS, N, ONE_OVER_N};
+use crate::{Falcon1024, Falcon512, FalconParams, MODULUS};
 
 pub use ntt_poly::NTTPolynomial;
 pub use param::{INV_NTT_TABLE, NTT_TABLE};
 pub use poly::Polynomial;
+pub use signed_poly::SignedPolynomial;
 
+// fixed parameter set entry points, e.g. `Polynomial512::from_hash_of_message`
+pub type Polynomial512 = Polynomial<Falcon512>;
+pub type Polynomial1024 = Polynomial<Falcon1024>;
+pub type NTTPolynomial512 = NTTPolynomial<Falcon512>;
+pub type NTTPolynomial1024 = NTTPolynomial<Falcon1024>;
 
-pub(crate) fn ntt(input: &Polynomial) -> NTTPolynomial {
+
+pub(crate) fn ntt<P: FalconParams>(input: &Polynomial<P>) -> NTTPolynomial<P> {
   let mut output = input.0;
-  let mut t = N;
-  
-  for l in 0..LOG_N {
+  let output_ref = output.as_mut();
+  let mut t = P::N;
+
+  for l in 0..P::LOG_N {
     let m = 1 << l;
     let ht = t / 2;
     let mut i = 0;
@@ -23,10 +32,10 @@ pub(crate) fn ntt(input: &Polynomial) -> NTTPolynomial {
       let j2 = j1 + ht;
       let mut j = j1;
       while j < j2 {
-        let u = output[j];
-        let v = (output[j + ht] as u32 * s as u32 % MODULUS as u32) as u16;
-        output[j] = (u + v) % MODULUS;
-        output[j + ht] = (u + MODULUS - v) % MODULUS;
+        let u = output_ref[j];
+        let v = (output_ref[j + ht] as u32 * s as u32 % MODULUS as u32) as u16;
+        output_ref[j] = (u + v) % MODULUS;
+        output_ref[j + ht] = (u + MODULUS - v) % MODULUS;
         j += 1;
       }
       i += 1;
@@ -38,10 +47,11 @@ pub(crate) fn ntt(input: &Polynomial) -> NTTPolynomial {
   NTTPolynomial(output)
 }
 
-pub (crate) fn inv_ntt(input: &NTTPolynomial) -> Polynomial {
+pub (crate) fn inv_ntt<P: FalconParams>(input: &NTTPolynomial<P>) -> Polynomial<P> {
   let mut output = input.0;
+  let output_ref = output.as_mut();
   let mut t = 1;
-  let mut m = N;
+  let mut m = P::N;
   while m > 1 {
     let hm = m / 2;
     let dt = t * 2;
@@ -52,11 +62,11 @@ pub (crate) fn inv_ntt(input: &NTTPolynomial) -> Polynomial {
       let s = INV_NTT_TABLE[hm + i];
       let mut j = j1;
       while j < j2 {
-        let u = output[j];
-        let v = output[j + t];
-        output[j] = (u + v) % MODULUS;
+        let u = output_ref[j];
+        let v = output_ref[j + t];
+        output_ref[j] = (u + v) % MODULUS;
         let w = (u + MODULUS - v) % MODULUS;
-        output[j + t] = (w as u32 * s as u32 % MODULUS as u32) as u16;
+        output_ref[j + t] = (w as u32 * s as u32 % MODULUS as u32) as u16;
         j += 1;
       }
       i += 1;
@@ -65,8 +75,8 @@ pub (crate) fn inv_ntt(input: &NTTPolynomial) -> Polynomial {
     t = dt;
     m = hm;
   }
-  for e in output.iter_mut() {
-    *e = (*e as u32 * ONE_OVER_N % MODULUS as u32) as u16;
+  for e in output_ref.iter_mut() {
+    *e = (*e as u32 * P::ONE_OVER_N % MODULUS as u32) as u16;
   }
 
   Polynomial(output)
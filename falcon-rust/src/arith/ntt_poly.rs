@@ -1,68 +1,72 @@
 use super::ntt;
-use crate::{Polynomial, MODULUS, N, U32_SAMPLE_THRESHOLD};
+use crate::{Array, Falcon512, FalconParams, Polynomial, MODULUS, U32_SAMPLE_THRESHOLD};
 use rand_chacha::ChaCha20Rng;
 use rand_core::{CryptoRng, RngCore, SeedableRng};
-use std::ops::{Add, Mul, Sub};
+use alloc::vec::Vec;
+use core::ops::{Add, Mul, Sub};
 
 #[derive(Debug, Copy, Clone, PartialEq)]
-pub struct NTTPolynomial(pub(crate) [u16; N]);
+pub struct NTTPolynomial<P: FalconParams = Falcon512>(pub(crate) P::Coeffs);
 
-impl Default for NTTPolynomial {
+impl<P: FalconParams> Default for NTTPolynomial<P> {
   fn default() -> Self {
-    Self([0u16; N])
+    Self(P::Coeffs::ZERO)
   }
 }
 
-impl From<&Polynomial> for NTTPolynomial {
-  fn from(poly: &Polynomial) -> Self {
+impl<P: FalconParams> From<&Polynomial<P>> for NTTPolynomial<P> {
+  fn from(poly: &Polynomial<P>) -> Self {
     ntt(poly)
   }
 }
 
-impl Mul for NTTPolynomial {
+impl<P: FalconParams> Mul for NTTPolynomial<P> {
   type Output = Self;
   fn mul(self, other: Self) -> <Self as Mul<Self>>::Output {
     let mut res = self;
     res.0
+      .as_mut()
       .iter_mut()
-      .zip(other.0.iter())
+      .zip(other.0.as_ref().iter())
       .for_each(|(x, y)| *x = ((*x as u32 * *y as u32) % MODULUS as u32) as u16);
     res
   }
 }
 
-impl Add for NTTPolynomial {
+impl<P: FalconParams> Add for NTTPolynomial<P> {
   type Output = Self;
   fn add(self, other: Self) -> <Self as Add<Self>>::Output {
     let mut res = self;
     res.0
+      .as_mut()
       .iter_mut()
-      .zip(other.0.iter())
-      .for_each(|(x, y)| *x = (*x + *y) % MODULUS as u16);
+      .zip(other.0.as_ref().iter())
+      .for_each(|(x, y)| *x = (*x + *y) % MODULUS);
     res
   }
 }
 
-impl Sub for NTTPolynomial {
+impl<P: FalconParams> Sub for NTTPolynomial<P> {
   type Output = Self;
   fn sub(self, other: Self) -> <Self as Add<Self>>::Output {
     let mut res = self;
     res.0
+      .as_mut()
       .iter_mut()
-      .zip(other.0.iter())
-      .for_each(|(x, y)| *x = (*x + MODULUS as u16 - *y) % MODULUS as u16);
+      .zip(other.0.as_ref().iter())
+      .for_each(|(x, y)| *x = (*x + MODULUS - *y) % MODULUS);
     res
   }
 }
 
-impl NTTPolynomial {
+impl<P: FalconParams> NTTPolynomial<P> {
   pub fn from_hash_of_message(message: &[u8], nonce: &[u8]) -> Self {
     (&Polynomial::from_hash_of_message(message, nonce)).into()
   }
 
   pub fn rand<R:RngCore + CryptoRng>(rng: &mut R) -> Self {
-    let mut res = [0u16; N];
-    for e in res.iter_mut() {
+    let mut res = P::Coeffs::ZERO;
+    for e in res.as_mut().iter_mut() {
       let mut tmp = rng.next_u32();
       while tmp >= U32_SAMPLE_THRESHOLD {
         tmp = rng.next_u32();
@@ -73,7 +77,7 @@ impl NTTPolynomial {
   }
 
   pub fn to_bytes(&self) -> Vec<u8> {
-    let poly: Polynomial = self.into();
+    let poly: Polynomial<P> = self.into();
     poly.to_bytes()
   }
 
@@ -84,14 +88,14 @@ impl NTTPolynomial {
 
   pub fn neg_mod_q(&self) -> Self {
     let mut res = *self;
-    for e in res.0.iter_mut() {
-      *e = MODULUS as u16 - *e;
+    for e in res.0.as_mut().iter_mut() {
+      *e = MODULUS - *e;
     }
     res
   }
 
-  pub fn coeff(&self) -> &[u16; N] {
-    &self.0
+  pub fn coeff(&self) -> &[u16] {
+    self.0.as_ref()
   }
 }
 
@@ -99,10 +103,14 @@ impl NTTPolynomial {
 fn test_ntt_conversion() {
   let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
   for _ in 0..100 {
-    let t =  Polynomial::rand(&mut rng);
+    let t: Polynomial = Polynomial::rand(&mut rng);
     let t_ntt: NTTPolynomial = (&t).into();
     let t_rec = (&t_ntt).into();
 
     assert_eq!(t, t_rec)
   }
-}
\ No newline at end of file
+
+  let t: Polynomial<crate::Falcon1024> = Polynomial::rand(&mut rng);
+  let t_ntt: NTTPolynomial<_> = (&t).into();
+  assert_eq!(t, (&t_ntt).into());
+}
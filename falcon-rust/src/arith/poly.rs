@@ -1,62 +1,67 @@
 use super::{inv_ntt, NTTPolynomial};
-use crate::{shake256_context, MODULUS, MODULUS_MINUS_1_OVER_TWO, N, U32_SAMPLE_THRESHOLD};
+use crate::{
+  Array, Falcon512, FalconError, FalconParams, Shake256, MODULUS, MODULUS_MINUS_1_OVER_TWO, U32_SAMPLE_THRESHOLD,
+};
 use rand_chacha::ChaCha20Rng;
 use rand_core::{CryptoRng, RngCore, SeedableRng};
-use std::ops::{Add, Mul, Sub};
+use alloc::{vec, vec::Vec};
+use core::ops::{Add, Mul, Sub};
 
 #[derive(Debug, Copy, Clone, PartialEq)]
-pub struct Polynomial(pub(crate) [u16; N]);
+pub struct Polynomial<P: FalconParams = Falcon512>(pub(crate) P::Coeffs);
 
-impl Default for Polynomial {
+impl<P: FalconParams> Default for Polynomial<P> {
   fn default() -> Self {
-    Self([0u16; N])
+    Self(P::Coeffs::ZERO)
   }
 }
 
-impl Mul for Polynomial {
+impl<P: FalconParams> Mul for Polynomial<P> {
   type Output = Self;
   fn mul(self, other: Self) -> <Self as Mul<Self>>::Output {
-      let self_ntt: NTTPolynomial = (&self).into();
-      let other_ntt: NTTPolynomial = (&other).into();
+      let self_ntt: NTTPolynomial<P> = (&self).into();
+      let other_ntt: NTTPolynomial<P> = (&other).into();
 
       (&(self_ntt * other_ntt)).into()
   }
 }
 
-impl Add for Polynomial {
+impl<P: FalconParams> Add for Polynomial<P> {
   type Output = Self;
   fn add(self, other: Self) -> <Self as Add<Self>>::Output {
     let mut res = self;
     res.0
+      .as_mut()
       .iter_mut()
-      .zip(other.0.iter())
-      .for_each(|(x, y)| *x = (*x + *y) % MODULUS as u16);
+      .zip(other.0.as_ref().iter())
+      .for_each(|(x, y)| *x = (*x + *y) % MODULUS);
     res
   }
 }
 
-impl Sub for Polynomial {
+impl<P: FalconParams> Sub for Polynomial<P> {
   type Output = Self;
   fn sub(self, other: Self) -> <Self as Sub<Self>>::Output {
     let mut res = self;
     res.0
+      .as_mut()
       .iter_mut()
-      .zip(other.0.iter())
-      .for_each(|(x, y)| *x = (*x + MODULUS - *y) % MODULUS as u16);
+      .zip(other.0.as_ref().iter())
+      .for_each(|(x, y)| *x = (*x + MODULUS - *y) % MODULUS);
     res
   }
 }
 
-impl From<&NTTPolynomial> for Polynomial {
-  fn from(poly: &NTTPolynomial) -> Self {
+impl<P: FalconParams> From<&NTTPolynomial<P>> for Polynomial<P> {
+  fn from(poly: &NTTPolynomial<P>) -> Self {
     inv_ntt(poly)
   }
 }
 
-impl Polynomial {
+impl<P: FalconParams> Polynomial<P> {
   pub fn rand<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
-    let mut res = [0u16; N];
-    for e in res.iter_mut() {
+    let mut res = P::Coeffs::ZERO;
+    for e in res.as_mut().iter_mut() {
       let mut tmp = rng.next_u32();
       while tmp >= U32_SAMPLE_THRESHOLD {
         tmp = rng.next_u32();
@@ -68,7 +73,7 @@ impl Polynomial {
 
   pub fn to_bytes(&self) -> Vec<u8> {
     let mut res = Vec::new();
-    for b in self.0.iter() {
+    for b in self.0.as_ref().iter() {
       res.push((b >> 8) as u8);
       res.push((b & 0xFF) as u8);
     }
@@ -77,7 +82,7 @@ impl Polynomial {
 
   pub fn one() -> Self {
     let mut res = Self::default();
-    res.0[0] = 1;
+    res.0.as_mut()[0] = 1;
     res
   }
 
@@ -88,42 +93,44 @@ impl Polynomial {
 
   pub fn neg_mod_q(&self) -> Self {
     let mut res = *self;
-    for e in res.0.iter_mut() {
-      *e = MODULUS as u16 - *e;
+    for e in res.0.as_mut().iter_mut() {
+      *e = MODULUS - *e;
     }
     res
   }
 
   pub fn schoolbook_mul(a: &Self, b: &Self) -> Self {
-    let mut buf = [0u32; N << 1];
-    let mut c = [0; N];
-    for i in 0..N {
-      for j in 0..N {
-        buf[i + j] += (a.0[i] as u32 * b.0[j] as u32) % MODULUS as u32;
+    let n = P::N;
+    let (a, b) = (a.0.as_ref(), b.0.as_ref());
+    let mut buf = vec![0u32; n << 1];
+    let mut c = P::Coeffs::ZERO;
+    for i in 0..n {
+      for j in 0..n {
+        buf[i + j] += (a[i] as u32 * b[j] as u32) % MODULUS as u32;
       }
     }
 
-    for i in 0..N {
-      c[i] = ((buf[i] + MODULUS as u32 - (buf[i + N] % MODULUS as u32)) % MODULUS as u32) as u16;
+    for (i, e) in c.as_mut().iter_mut().enumerate() {
+      *e = ((buf[i] + MODULUS as u32 - (buf[i + n] % MODULUS as u32)) % MODULUS as u32) as u16;
     }
     Self(c)
   }
 
   pub fn from_hash_of_message(message: &[u8], nonce: &[u8]) -> Self {
-    let mut rng = shake256_context::init();
+    let mut rng = Shake256::init();
     rng.inject(nonce);
     rng.inject(message);
     rng.finalize();
 
-    let buffer = rng.extract(N * 3);
+    let buffer = rng.extract(P::N * 3);
     let mut ctr = 0;
-    let mut res = [0u16; N];
+    let mut res = P::Coeffs::ZERO;
     let mut i = 0;
-    while i < N {
+    while i < P::N {
       let coeff = (buffer[ctr] as u16) << 8 | (buffer[ctr + 1] as u16);
       ctr += 2;
       if coeff < 61445 {
-        res[i] = coeff % MODULUS;
+        res.as_mut()[i] = coeff % MODULUS;
         i += 1;
       }
     }
@@ -133,8 +140,8 @@ impl Polynomial {
 
   pub fn l2_norm(&self) -> u64 {
     let mut res = 0;
-    for e in self.0 {
-      if e > MODULUS_MINUS_1_OVER_TWO as u16 {
+    for &e in self.0.as_ref() {
+      if e > MODULUS_MINUS_1_OVER_TWO {
         res += (MODULUS - e) as u64 * (MODULUS - e) as u64
       } else {
         res += e as u64 * e as u64
@@ -143,14 +150,34 @@ impl Polynomial {
     res
   }
 
-  pub fn coeff(&self) -> &[u16; N] {
-    &self.0
+  pub fn coeff(&self) -> &[u16] {
+    self.0.as_ref()
+  }
+
+  /**
+   * Builds a polynomial from its N coefficients, which must be below q
+   */
+  pub fn from_coeffs(coeffs: &[u16]) -> Result<Self, FalconError> {
+    if coeffs.len() != P::N {
+      return Err(FalconError::InvalidLength {
+        expected: P::N,
+        found: coeffs.len(),
+      });
+    }
+    if let Some(x) = coeffs.iter().find(|x| **x >= MODULUS) {
+      return Err(FalconError::InvalidCoefficient(*x as u32));
+    }
+
+    let mut res = P::Coeffs::ZERO;
+    res.as_mut().copy_from_slice(coeffs);
+    Ok(Self(res))
   }
 }
 
 #[cfg(test)]
 mod test {
   use super::Polynomial;
+  use crate::Falcon1024;
   use rand_chacha::ChaCha20Rng;
   use rand_core::SeedableRng;
 
@@ -158,12 +185,16 @@ mod test {
   fn test_polynomial_mul() {
     let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
     for _ in 0..100 {
-      let t1 = Polynomial::rand(&mut rng);
+      let t1: Polynomial = Polynomial::rand(&mut rng);
       let t2 = Polynomial::rand(&mut rng);
       let tt = Polynomial::schoolbook_mul(&t1, &t2);
       let t = t1 * t2;
       
       assert_eq!(tt, t)
     }
+
+    let t1 = Polynomial::<Falcon1024>::rand(&mut rng);
+    let t2 = Polynomial::rand(&mut rng);
+    assert_eq!(Polynomial::schoolbook_mul(&t1, &t2), t1 * t2);
   }
 }
\ No newline at end of file
@@ -0,0 +1,138 @@
+use crate::{Array, Falcon512, FalconError, FalconParams, Polynomial, MODULUS, MODULUS_MINUS_1_OVER_TWO};
+use alloc::{vec, vec::Vec};
+use zeroize::Zeroize;
+
+/**
+ * Polynomial with small signed coefficients, such as the secret basis
+ * f, g, F, G or the signature vector s2
+ */
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SignedPolynomial<P: FalconParams = Falcon512>(pub(crate) P::SignedCoeffs);
+
+impl<P: FalconParams> Default for SignedPolynomial<P> {
+  fn default() -> Self {
+    Self(P::SignedCoeffs::ZERO)
+  }
+}
+
+/*
+  Coefficients are centered into [-(q-1)/2, (q-1)/2]
+*/
+impl<P: FalconParams> From<&Polynomial<P>> for SignedPolynomial<P> {
+  fn from(poly: &Polynomial<P>) -> Self {
+    let mut res = P::SignedCoeffs::ZERO;
+    for (x, &y) in res.as_mut().iter_mut().zip(poly.coeff()) {
+      let y = y % MODULUS;
+      *x = if y > MODULUS_MINUS_1_OVER_TWO {
+        y as i16 - MODULUS as i16
+      } else {
+        y as i16
+      };
+    }
+    Self(res)
+  }
+}
+
+impl<P: FalconParams> From<&SignedPolynomial<P>> for Polynomial<P> {
+  fn from(poly: &SignedPolynomial<P>) -> Self {
+    let mut res = P::Coeffs::ZERO;
+    for (x, &y) in res.as_mut().iter_mut().zip(poly.coeff()) {
+      *x = (y as i32).rem_euclid(MODULUS as i32) as u16;
+    }
+    Polynomial(res)
+  }
+}
+
+impl<P: FalconParams> SignedPolynomial<P> {
+  pub fn from_coeffs(coeffs: &[i16]) -> Result<Self, FalconError> {
+    if coeffs.len() != P::N {
+      return Err(FalconError::InvalidLength {
+        expected: P::N,
+        found: coeffs.len(),
+      });
+    }
+
+    let mut res = P::SignedCoeffs::ZERO;
+    res.as_mut().copy_from_slice(coeffs);
+    Ok(Self(res))
+  }
+
+  pub fn coeff(&self) -> &[i16] {
+    self.0.as_ref()
+  }
+
+  pub fn l2_norm(&self) -> u64 {
+    self.0.as_ref().iter().map(|&e| (e as i64 * e as i64) as u64).sum()
+  }
+
+  /**
+   * Product in Z[x]/(x^N + 1), without reduction mod q
+   */
+  pub fn mul_over_integers(&self, other: &Self) -> Vec<i64> {
+    let (a, b) = (self.coeff(), other.coeff());
+    let mut res = vec![0i64; P::N];
+    for i in 0..P::N {
+      for j in 0..P::N {
+        let t = a[i] as i64 * b[j] as i64;
+        if i + j < P::N {
+          res[i + j] += t;
+        } else {
+          res[i + j - P::N] -= t;
+        }
+      }
+    }
+    res
+  }
+}
+
+impl<P: FalconParams> Zeroize for SignedPolynomial<P> {
+  fn zeroize(&mut self) {
+    self.0.as_mut().zeroize();
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::SignedPolynomial;
+  use crate::{Falcon1024, Polynomial, MODULUS};
+  use alloc::vec;
+  use rand_chacha::ChaCha20Rng;
+  use rand_core::SeedableRng;
+
+  #[test]
+  fn test_signed_conversion() {
+    let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+    for _ in 0..100 {
+      let t: Polynomial = Polynomial::rand(&mut rng);
+      let s = SignedPolynomial::from(&t);
+      assert!(s.coeff().iter().all(|x| x.unsigned_abs() <= MODULUS / 2));
+      assert_eq!(Polynomial::from(&s), t);
+      assert_eq!(s.l2_norm(), t.l2_norm());
+    }
+
+    let t: Polynomial<Falcon1024> = Polynomial::rand(&mut rng);
+    assert_eq!(Polynomial::from(&SignedPolynomial::from(&t)), t);
+  }
+
+  #[test]
+  fn test_mul_over_integers() {
+    let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+    let a: Polynomial = Polynomial::rand(&mut rng);
+    let b = Polynomial::rand(&mut rng);
+    let (sa, sb) = (SignedPolynomial::from(&a), SignedPolynomial::from(&b));
+
+    // the integer product agrees with the product mod q
+    let ab = sa.mul_over_integers(&sb);
+    for (x, y) in ab.iter().zip((a * b).coeff()) {
+      assert_eq!(x.rem_euclid(MODULUS as i64), *y as i64);
+    }
+
+    // x^511 * x^511 = -x^510
+    let mut coeffs = vec![0i16; 512];
+    coeffs[511] = 1;
+    let x: SignedPolynomial = SignedPolynomial::from_coeffs(&coeffs).unwrap();
+    let mut expected = vec![0i64; 512];
+    expected[510] = -1;
+    assert_eq!(x.mul_over_integers(&x), expected);
+  }
+}
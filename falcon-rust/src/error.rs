@@ -0,0 +1,87 @@
+#[cfg(feature = "ffi")]
+use crate::binder::*;
+use core::fmt;
+
+/**
+ * Errors reported by the C library (`FALCON_ERR_*` in `falcon.h`) and by the
+ * Rust decoders
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FalconError {
+  /// FALCON_ERR_RANDOM: the random source failed
+  Random,
+  /// FALCON_ERR_SIZE: an output buffer is too small
+  Size,
+  /// FALCON_ERR_FORMAT: a key or signature is malformed
+  Format,
+  /// FALCON_ERR_BADSIG: the signature does not match the message and key
+  BadSignature,
+  /// FALCON_ERR_BADARG: an argument is out of range, e.g. a mismatched degree
+  BadArgument,
+  /// FALCON_ERR_INTERNAL: an internal error in the C library
+  Internal,
+  /// The encoded data does not have the expected length
+  InvalidLength { expected: usize, found: usize },
+  /// The header byte does not match the expected object type or degree
+  InvalidHeader(u8),
+  /// A decoded coefficient is out of range
+  InvalidCoefficient(u32),
+  /// The encoding is not canonical: "-0" or non-zero padding bits
+  NonCanonicalEncoding,
+  /// The input ended before all coefficients were decoded
+  UnexpectedEnd,
+}
+
+#[cfg(feature = "ffi")]
+impl FalconError {
+  pub(crate) fn check(code: i32) -> Result<(), Self> {
+    match code {
+      0 => Ok(()),
+      FALCON_ERR_RANDOM => Err(Self::Random),
+      FALCON_ERR_SIZE => Err(Self::Size),
+      FALCON_ERR_FORMAT => Err(Self::Format),
+      FALCON_ERR_BADSIG => Err(Self::BadSignature),
+      FALCON_ERR_BADARG => Err(Self::BadArgument),
+      _ => Err(Self::Internal),
+    }
+  }
+}
+
+impl fmt::Display for FalconError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Random => write!(f, "random source failed"),
+      Self::Size => write!(f, "buffer too small"),
+      Self::Format => write!(f, "invalid format"),
+      Self::BadSignature => write!(f, "invalid signature"),
+      Self::BadArgument => write!(f, "invalid argument"),
+      Self::Internal => write!(f, "internal error"),
+      Self::InvalidLength { expected, found } => {
+        write!(f, "invalid length: expected {}, found {}", expected, found)
+      }
+      Self::InvalidHeader(header) => write!(f, "invalid header byte: {:#04x}", header),
+      Self::InvalidCoefficient(coeff) => write!(f, "invalid coefficient: {}", coeff),
+      Self::NonCanonicalEncoding => write!(f, "non-canonical encoding"),
+      Self::UnexpectedEnd => write!(f, "unexpected end of input"),
+    }
+  }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FalconError {}
+
+#[cfg(all(test, feature = "ffi"))]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_check_codes() {
+    assert_eq!(FalconError::check(0), Ok(()));
+    assert_eq!(FalconError::check(FALCON_ERR_RANDOM), Err(FalconError::Random));
+    assert_eq!(FalconError::check(FALCON_ERR_SIZE), Err(FalconError::Size));
+    assert_eq!(FalconError::check(FALCON_ERR_FORMAT), Err(FalconError::Format));
+    assert_eq!(FalconError::check(FALCON_ERR_BADSIG), Err(FalconError::BadSignature));
+    assert_eq!(FalconError::check(FALCON_ERR_BADARG), Err(FalconError::BadArgument));
+    assert_eq!(FalconError::check(FALCON_ERR_INTERNAL), Err(FalconError::Internal));
+  }
+}
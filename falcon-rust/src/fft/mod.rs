@@ -0,0 +1,350 @@
+mod table;
+
+use table::GM_TAB;
+
+/*
+  Floating-point FFT over R[x]/(x^n + 1), following `fft.c` with native
+  doubles. A polynomial in FFT form holds n/2 complex values, the real
+  parts first, then the imaginary parts. The operations are performed in
+  the same order as the C code, so the results are bit-for-bit identical.
+*/
+
+#[inline]
+fn fpc_mul(a_re: f64, a_im: f64, b_re: f64, b_im: f64) -> (f64, f64) {
+  (a_re * b_re - a_im * b_im, a_re * b_im + a_im * b_re)
+}
+
+#[inline]
+fn fpc_div(a_re: f64, a_im: f64, b_re: f64, b_im: f64) -> (f64, f64) {
+  let m = 1.0 / (b_re * b_re + b_im * b_im);
+  fpc_mul(a_re, a_im, b_re * m, -b_im * m)
+}
+
+pub(crate) fn fft(f: &mut [f64]) {
+  let n = f.len();
+  let logn = n.trailing_zeros();
+  let hn = n >> 1;
+  let mut t = hn;
+
+  let mut m = 2;
+  for _ in 1..logn {
+    let ht = t >> 1;
+    let hm = m >> 1;
+    let mut j1 = 0;
+    for i1 in 0..hm {
+      let s_re = GM_TAB[(m + i1) << 1];
+      let s_im = GM_TAB[((m + i1) << 1) + 1];
+      for j in j1..j1 + ht {
+        let (x_re, x_im) = (f[j], f[j + hn]);
+        let (y_re, y_im) = fpc_mul(f[j + ht], f[j + ht + hn], s_re, s_im);
+        f[j] = x_re + y_re;
+        f[j + hn] = x_im + y_im;
+        f[j + ht] = x_re - y_re;
+        f[j + ht + hn] = x_im - y_im;
+      }
+      j1 += t;
+    }
+    t = ht;
+    m <<= 1;
+  }
+}
+
+pub(crate) fn ifft(f: &mut [f64]) {
+  let n = f.len();
+  let logn = n.trailing_zeros();
+  let hn = n >> 1;
+  let mut t = 1;
+  let mut m = n;
+
+  for _ in (2..=logn).rev() {
+    let hm = m >> 1;
+    let dt = t << 1;
+    let mut j1 = 0;
+    let mut i1 = 0;
+    while j1 < hn {
+      let s_re = GM_TAB[(hm + i1) << 1];
+      let s_im = -GM_TAB[((hm + i1) << 1) + 1];
+      for j in j1..j1 + t {
+        let (x_re, x_im) = (f[j], f[j + hn]);
+        let (y_re, y_im) = (f[j + t], f[j + t + hn]);
+        f[j] = x_re + y_re;
+        f[j + hn] = x_im + y_im;
+        let (d_re, d_im) = fpc_mul(x_re - y_re, x_im - y_im, s_re, s_im);
+        f[j + t] = d_re;
+        f[j + t + hn] = d_im;
+      }
+      i1 += 1;
+      j1 += dt;
+    }
+    t = dt;
+    m = hm;
+  }
+
+  if logn > 0 {
+    // fpr_p2_tab[logn] = 2^(1 - logn)
+    let ni = 2.0 / n as f64;
+    for e in f.iter_mut() {
+      *e *= ni;
+    }
+  }
+}
+
+pub(crate) fn poly_add(a: &mut [f64], b: &[f64]) {
+  for (x, y) in a.iter_mut().zip(b) {
+    *x += y;
+  }
+}
+
+pub(crate) fn poly_sub(a: &mut [f64], b: &[f64]) {
+  for (x, y) in a.iter_mut().zip(b) {
+    *x -= y;
+  }
+}
+
+pub(crate) fn poly_neg(a: &mut [f64]) {
+  for x in a.iter_mut() {
+    *x = -*x;
+  }
+}
+
+pub(crate) fn poly_mul_fft(a: &mut [f64], b: &[f64]) {
+  let hn = a.len() >> 1;
+  for u in 0..hn {
+    (a[u], a[u + hn]) = fpc_mul(a[u], a[u + hn], b[u], b[u + hn]);
+  }
+}
+
+/*
+  a * adj(b)
+*/
+pub(crate) fn poly_muladj_fft(a: &mut [f64], b: &[f64]) {
+  let hn = a.len() >> 1;
+  for u in 0..hn {
+    (a[u], a[u + hn]) = fpc_mul(a[u], a[u + hn], b[u], -b[u + hn]);
+  }
+}
+
+/*
+  a * adj(a), which is real
+*/
+pub(crate) fn poly_mulselfadj_fft(a: &mut [f64]) {
+  let hn = a.len() >> 1;
+  for u in 0..hn {
+    a[u] = a[u] * a[u] + a[u + hn] * a[u + hn];
+    a[u + hn] = 0.0;
+  }
+}
+
+pub(crate) fn poly_mulconst(a: &mut [f64], x: f64) {
+  for e in a.iter_mut() {
+    *e *= x;
+  }
+}
+
+pub(crate) fn poly_adj_fft(a: &mut [f64]) {
+  let hn = a.len() >> 1;
+  poly_neg(&mut a[hn..]);
+}
+
+/*
+  1 / (a * adj(a) + b * adj(b)), which is real: only its n/2 real parts
+  are written to d
+*/
+pub(crate) fn poly_invnorm2_fft(d: &mut [f64], a: &[f64], b: &[f64]) {
+  let hn = a.len() >> 1;
+  for u in 0..hn {
+    let (a_re, a_im) = (a[u], a[u + hn]);
+    let (b_re, b_im) = (b[u], b[u + hn]);
+    d[u] = 1.0 / ((a_re * a_re + a_im * a_im) + (b_re * b_re + b_im * b_im));
+  }
+}
+
+/*
+  F * adj(f) + G * adj(g)
+*/
+pub(crate) fn poly_add_muladj_fft(d: &mut [f64], F: &[f64], G: &[f64], f: &[f64], g: &[f64]) {
+  let hn = d.len() >> 1;
+  for u in 0..hn {
+    let (a_re, a_im) = fpc_mul(F[u], F[u + hn], f[u], -f[u + hn]);
+    let (b_re, b_im) = fpc_mul(G[u], G[u + hn], g[u], -g[u + hn]);
+    d[u] = a_re + b_re;
+    d[u + hn] = a_im + b_im;
+  }
+}
+
+/*
+  a * b, for a real b given by its n/2 real parts
+*/
+pub(crate) fn poly_mul_autoadj_fft(a: &mut [f64], b: &[f64]) {
+  let hn = a.len() >> 1;
+  for u in 0..hn {
+    a[u] *= b[u];
+    a[u + hn] *= b[u];
+  }
+}
+
+/*
+  a / b, for a real b given by its n/2 real parts
+*/
+pub(crate) fn poly_div_autoadj_fft(a: &mut [f64], b: &[f64]) {
+  let hn = a.len() >> 1;
+  for u in 0..hn {
+    let ib = 1.0 / b[u];
+    a[u] *= ib;
+    a[u + hn] *= ib;
+  }
+}
+
+/*
+  LDL decomposition of the self-adjoint matrix [[g00, g01], [adj(g01), g11]]:
+  g01 is replaced by l10 and g11 by d11 (d00 is g00)
+*/
+pub(crate) fn poly_ldl_fft(g00: &[f64], g01: &mut [f64], g11: &mut [f64]) {
+  let hn = g00.len() >> 1;
+  for u in 0..hn {
+    let (g01_re, g01_im) = (g01[u], g01[u + hn]);
+    let (mu_re, mu_im) = fpc_div(g01_re, g01_im, g00[u], g00[u + hn]);
+    let (t_re, t_im) = fpc_mul(mu_re, mu_im, g01_re, -g01_im);
+    g11[u] -= t_re;
+    g11[u + hn] -= t_im;
+    g01[u] = mu_re;
+    g01[u + hn] = -mu_im;
+  }
+}
+
+/*
+  Splits f(x) into f0(x^2) + x f1(x^2), in FFT form
+*/
+pub(crate) fn poly_split_fft(f0: &mut [f64], f1: &mut [f64], f: &[f64]) {
+  let hn = f.len() >> 1;
+  let qn = hn >> 1;
+
+  f0[0] = f[0];
+  f1[0] = f[hn];
+  for u in 0..qn {
+    let (a_re, a_im) = (f[u << 1], f[(u << 1) + hn]);
+    let (b_re, b_im) = (f[(u << 1) + 1], f[(u << 1) + 1 + hn]);
+
+    f0[u] = (a_re + b_re) * 0.5;
+    f0[u + qn] = (a_im + b_im) * 0.5;
+
+    let (t_re, t_im) = fpc_mul(
+      a_re - b_re,
+      a_im - b_im,
+      GM_TAB[(u + hn) << 1],
+      -GM_TAB[((u + hn) << 1) + 1],
+    );
+    f1[u] = t_re * 0.5;
+    f1[u + qn] = t_im * 0.5;
+  }
+}
+
+/*
+  Inverse of `poly_split_fft`
+*/
+pub(crate) fn poly_merge_fft(f: &mut [f64], f0: &[f64], f1: &[f64]) {
+  let hn = f.len() >> 1;
+  let qn = hn >> 1;
+
+  f[0] = f0[0];
+  f[hn] = f1[0];
+  for u in 0..qn {
+    let (a_re, a_im) = (f0[u], f0[u + qn]);
+    let (b_re, b_im) = fpc_mul(
+      f1[u],
+      f1[u + qn],
+      GM_TAB[(u + hn) << 1],
+      GM_TAB[((u + hn) << 1) + 1],
+    );
+    f[u << 1] = a_re + b_re;
+    f[(u << 1) + hn] = a_im + b_im;
+    f[(u << 1) + 1] = a_re - b_re;
+    f[(u << 1) + 1 + hn] = a_im - b_im;
+  }
+}
+
+/*
+  Rounding to the nearest integer (ties to even), computed without
+  branching on the value as `fpr_rint`
+*/
+pub(crate) fn fpr_rint(x: f64) -> i64 {
+  let sx = (x - 1.0) as i64;
+  let mut tx = x as i64;
+  let mut rp = (x + 4503599627370496.0) as i64 - 4503599627370496;
+  let mut rn = (x - 4503599627370496.0) as i64 + 4503599627370496;
+
+  let m = sx >> 63;
+  rn &= m;
+  rp &= !m;
+
+  let ub = ((tx as u64) >> 52) as u32;
+  let m = -((((ub.wrapping_add(1)) & 0xFFF).wrapping_sub(2) >> 31) as i64);
+  rp &= m;
+  rn &= m;
+  tx &= !m;
+
+  tx | rn | rp
+}
+
+pub(crate) fn fpr_floor(x: f64) -> i64 {
+  let r = x as i64;
+  r - (x < r as f64) as i64
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::{Polynomial, SignedPolynomial};
+  use alloc::{vec, vec::Vec};
+  use rand_chacha::ChaCha20Rng;
+  use rand_core::SeedableRng;
+
+  #[test]
+  fn test_fft_mul() {
+    let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+    let a: Polynomial = Polynomial::rand(&mut rng);
+    let b = Polynomial::rand(&mut rng);
+    let (a, b) = (SignedPolynomial::from(&a), SignedPolynomial::from(&b));
+
+    let mut fa: Vec<f64> = a.coeff().iter().map(|&x| x as f64).collect();
+    let mut fb: Vec<f64> = b.coeff().iter().map(|&x| x as f64).collect();
+    let orig = fa.clone();
+
+    fft(&mut fa);
+    fft(&mut fb);
+
+    let mut round_trip = fa.clone();
+    ifft(&mut round_trip);
+    assert!(round_trip.iter().zip(orig.iter()).all(|(x, y)| (x - y).abs() < 1e-6));
+
+    let mut split = (vec![0.0; 256], vec![0.0; 256]);
+    poly_split_fft(&mut split.0, &mut split.1, &fa);
+    let mut merged = vec![0.0; 512];
+    poly_merge_fft(&mut merged, &split.0, &split.1);
+    assert!(merged.iter().zip(fa.iter()).all(|(x, y)| (x - y).abs() < 1e-6));
+
+    poly_mul_fft(&mut fa, &fb);
+    ifft(&mut fa);
+    for (x, y) in fa.iter().zip(a.mul_over_integers(&b)) {
+      assert_eq!(fpr_rint(*x), y);
+    }
+  }
+
+  #[test]
+  fn test_fpr_rounding() {
+    for (x, r, f) in [
+      (0.5, 0, 0),
+      (1.5, 2, 1),
+      (2.5, 2, 2),
+      (-0.5, 0, -1),
+      (-1.5, -2, -2),
+      (-2.4, -2, -3),
+      (3.7, 4, 3),
+      (-3.0, -3, -3),
+      (1e17, 100000000000000000, 100000000000000000),
+    ] {
+      assert_eq!(fpr_rint(x), r);
+      assert_eq!(fpr_floor(x), f);
+    }
+  }
+}
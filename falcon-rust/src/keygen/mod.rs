@@ -0,0 +1,254 @@
+mod modp;
+mod ntru;
+mod table;
+mod zint;
+
+use crate::{
+  fft::*, structs::div_mod_q, FalconError, FalconParams, KeyPair, NtruBasis, Polynomial, SecretKey,
+  Shake256, SignedPolynomial, KEYGEN_SEED_LEN, KEY_L2_BOUND, SK_FG_BITS,
+};
+use alloc::{vec, vec::Vec};
+use ntru::solve_ntru;
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroize;
+
+/*
+  Native key generation, following `Zf(keygen)` in `keygen.c` (without
+  the ChaCha20 PRNG, as in the C build): f and g are sampled from SHAKE256,
+  then F and G are computed with NTRUSolve. With the same seed, keys are
+  identical to the C library's.
+*/
+
+impl<P: FalconParams> KeyPair<P> {
+  /**
+   * Same keys as `keygen_from_seed`, without the C library
+   */
+  pub fn keygen_rust_from_seed(seed: &[u8]) -> Self {
+    Self::try_keygen_rust_from_seed(seed).unwrap()
+  }
+
+  pub fn try_keygen_rust_from_seed(seed: &[u8]) -> Result<Self, FalconError> {
+    let mut rng = Shake256::init();
+    rng.inject(seed);
+    rng.finalize();
+    Self::try_keygen_rust_from_shake(&mut rng)
+  }
+
+  /**
+   * Generates a key pair from a 48-byte seed drawn from `rng`
+   */
+  pub fn keygen_rust_with_rng<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+    Self::try_keygen_rust_with_rng(rng).unwrap()
+  }
+
+  pub fn try_keygen_rust_with_rng<R: RngCore + CryptoRng>(rng: &mut R) -> Result<Self, FalconError> {
+    let mut seed = [0u8; KEYGEN_SEED_LEN];
+    rng.try_fill_bytes(&mut seed).map_err(|_| FalconError::Random)?;
+    let res = Self::try_keygen_rust_from_seed(&seed);
+    seed.zeroize();
+    res
+  }
+
+  pub(crate) fn try_keygen_rust_from_shake(rng: &mut Shake256) -> Result<Self, FalconError> {
+    let (mut f, mut g, mut F, mut G, h) = keygen::<P>(rng);
+
+    let basis = NtruBasis {
+      f: SignedPolynomial::from_coeffs(&f)?,
+      g: SignedPolynomial::from_coeffs(&g)?,
+      F: SignedPolynomial::from_coeffs(&F)?,
+      G: SignedPolynomial::from_coeffs(&G)?,
+    };
+    for x in [&mut f, &mut g, &mut F, &mut G] {
+      x.zeroize();
+    }
+
+    Ok(Self {
+      public_key: (&h).into(),
+      secret_key: SecretKey::try_from(&basis)?,
+    })
+  }
+}
+
+/*
+  Samples (f, g) until the basis is short enough, f is invertible modulo
+  q and NTRUSolve succeeds. Returns f, g, F, G and h = g / f mod q.
+*/
+#[allow(clippy::type_complexity)]
+fn keygen<P: FalconParams>(rng: &mut Shake256) -> (Vec<i16>, Vec<i16>, Vec<i16>, Vec<i16>, Polynomial<P>) {
+  let logn = P::LOG_N;
+
+  loop {
+    let f = poly_small_mkgauss(rng, logn);
+    let g = poly_small_mkgauss(rng, logn);
+
+    // f and g must fit in their encoding
+    let lim = 1 << (P::SK_fg_BITS - 1);
+    if f.iter().chain(g.iter()).any(|&x| x >= lim || x <= -lim) {
+      continue;
+    }
+
+    // squared norm of (g, -f)
+    let norm: u64 = f.iter().chain(g.iter()).map(|&x| (x as i32 * x as i32) as u64).sum();
+    if norm >= KEY_L2_BOUND {
+      continue;
+    }
+
+    // squared norm of the Gram-Schmidt orthogonalized vector q (adj(f), adj(g)) / (f adj(f) + g adj(g))
+    let mut rt1: Vec<f64> = f.iter().map(|&x| x as f64).collect();
+    let mut rt2: Vec<f64> = g.iter().map(|&x| x as f64).collect();
+    let mut rt3 = vec![0.0; P::N >> 1];
+    fft(&mut rt1);
+    fft(&mut rt2);
+    poly_invnorm2_fft(&mut rt3, &rt1, &rt2);
+    poly_adj_fft(&mut rt1);
+    poly_adj_fft(&mut rt2);
+    poly_mulconst(&mut rt1, 12289.0);
+    poly_mulconst(&mut rt2, 12289.0);
+    poly_mul_autoadj_fft(&mut rt1, &rt3);
+    poly_mul_autoadj_fft(&mut rt2, &rt3);
+    ifft(&mut rt1);
+    ifft(&mut rt2);
+    let mut bnorm = 0.0;
+    for (x, y) in rt1.iter().zip(rt2.iter()) {
+      bnorm += x * x;
+      bnorm += y * y;
+    }
+    let short = bnorm < 16822.4121;
+    if !short {
+      continue;
+    }
+
+    // h = g / f mod q, f must be invertible
+    let to_poly = |a: &[i16]| Polynomial::from(&SignedPolynomial::<P>::from_coeffs(a).unwrap());
+    let Ok(h) = div_mod_q(&to_poly(&g), &to_poly(&f)) else {
+      continue;
+    };
+
+    let lim = (1 << (SK_FG_BITS - 1)) - 1;
+    let Some((F, G)) = solve_ntru(logn, &f, &g, lim) else {
+      continue;
+    };
+
+    return (f, g, F, G, h);
+  }
+}
+
+/*
+  f with coefficients sampled from mkgauss and an odd sum of coefficients,
+  so that its resultant with x^n + 1 is odd
+*/
+fn poly_small_mkgauss(rng: &mut Shake256, logn: usize) -> Vec<i16> {
+  let n = 1 << logn;
+  let mut f = vec![0i16; n];
+  let mut mod2 = 0;
+  for (u, x) in f.iter_mut().enumerate() {
+    let s = loop {
+      let s = mkgauss(rng, logn);
+      if !(-127..=127).contains(&s) {
+        continue;
+      }
+      if u == n - 1 {
+        if mod2 ^ (s & 1) == 0 {
+          continue;
+        }
+      } else {
+        mod2 ^= s & 1;
+      }
+      break s;
+    };
+    *x = s as i16;
+  }
+  f
+}
+
+/*
+  P(x = 0), then P(x >= k + 1 | x > 0) for k > 0, scaled by 2^63, for a
+  discrete Gaussian of standard deviation 1.17 * sqrt(q / 2048)
+*/
+const GAUSS_1024_12289: [u64; 27] = [
+  1283868770400643928, 6416574995475331444, 4078260278032692663, 2353523259288686585,
+  1227179971273316331, 575931623374121527, 242543240509105209, 91437049221049666,
+  30799446349977173, 9255276791179340, 2478152334826140, 590642893610164, 125206034929641,
+  23590435911403, 3948334035941, 586753615614, 77391054539, 9056793210, 940121950, 86539696,
+  7062824, 510971, 32764, 1862, 94, 4, 0,
+];
+
+fn get_rng_u64(rng: &mut Shake256) -> u64 {
+  u64::from_le_bytes(rng.extract(8).try_into().unwrap())
+}
+
+/*
+  Sum of 2^(10 - logn) values with the distribution of N = 1024, so that
+  the standard deviation is 1.17 * sqrt(q / (2N)). The table is read in
+  full for each value.
+*/
+fn mkgauss(rng: &mut Shake256, logn: usize) -> i32 {
+  let mut val = 0i32;
+  for _ in 0..1 << (10 - logn) {
+    // sign, and whether the value is zero
+    let r = get_rng_u64(rng);
+    let neg = (r >> 63) as u32;
+    let r = r & !(1u64 << 63);
+    let mut f = (r.wrapping_sub(GAUSS_1024_12289[0]) >> 63) as u32;
+
+    // index of the first element not greater than r
+    let mut v = 0u32;
+    let r = get_rng_u64(rng) & !(1u64 << 63);
+    for (k, &e) in GAUSS_1024_12289.iter().enumerate().skip(1) {
+      let t = (r.wrapping_sub(e) >> 63) as u32 ^ 1;
+      v |= k as u32 & (t & (f ^ 1)).wrapping_neg();
+      f |= t;
+    }
+
+    v = (v ^ neg.wrapping_neg()).wrapping_add(neg);
+    val += v as i32;
+  }
+  val
+}
+
+#[cfg(test)]
+mod test {
+  use crate::{Falcon1024, Falcon512, FalconParams, KeyPair};
+  use rand_chacha::ChaCha20Rng;
+  use rand_core::SeedableRng;
+
+  fn keygen<P: FalconParams>() {
+    let keypair = KeyPair::<P>::keygen_rust_from_seed(b"rust keygen seed");
+    assert_eq!(keypair, KeyPair::<P>::keygen_rust_from_seed(b"rust keygen seed"));
+    assert_ne!(keypair, KeyPair::<P>::keygen_rust_from_seed(b"another seed"));
+
+    let basis = keypair.secret_key.basis();
+    assert!(basis.verify_ntru_equation());
+    assert_eq!(basis.public_key(), keypair.public_key);
+
+    let mut rng1 = ChaCha20Rng::from_seed([1; 32]);
+    let mut rng2 = ChaCha20Rng::from_seed([1; 32]);
+    assert_eq!(KeyPair::<P>::keygen_rust_with_rng(&mut rng1), KeyPair::keygen_rust_with_rng(&mut rng2));
+  }
+
+  #[test]
+  fn test_rust_keygen() {
+    keygen::<Falcon512>();
+    keygen::<Falcon1024>();
+  }
+
+  #[cfg(feature = "ffi")]
+  fn cross_check<P: FalconParams>() {
+    for seed in [&b"rust keygen seed"[..], &[0u8; 48][..], &b"seed 3"[..]] {
+      assert_eq!(KeyPair::<P>::keygen_rust_from_seed(seed), KeyPair::<P>::keygen_from_seed(seed));
+    }
+
+    let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+    let keypair = KeyPair::<P>::keygen_rust_with_rng(&mut rng);
+    let message = "Testing message";
+    let sig = keypair.secret_key.sign(message.as_ref());
+    assert!(keypair.public_key.verify(message.as_ref(), &sig));
+  }
+
+  #[cfg(feature = "ffi")]
+  #[test]
+  fn test_rust_keygen_matches_c() {
+    cross_check::<Falcon512>();
+    cross_check::<Falcon1024>();
+  }
+}
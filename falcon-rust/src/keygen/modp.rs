@@ -0,0 +1,227 @@
+/*
+  Arithmetic modulo the 31-bit small primes, with Montgomery
+  multiplication (R = 2^31), and the NTT over these primes (`modp_*` in
+  `keygen.c`)
+*/
+
+/*
+  x mod p, for -p < x < p
+*/
+pub(super) fn modp_set(x: i32, p: u32) -> u32 {
+  let w = x as u32;
+  w.wrapping_add(p & (w >> 31).wrapping_neg())
+}
+
+/*
+  x normalized around 0
+*/
+pub(super) fn modp_norm(x: u32, p: u32) -> i32 {
+  x.wrapping_sub(p & (x.wrapping_sub((p + 1) >> 1) >> 31).wrapping_sub(1)) as i32
+}
+
+/*
+  -1/p mod 2^31, for an odd p
+*/
+pub(super) fn modp_ninv31(p: u32) -> u32 {
+  let mut y = 2u32.wrapping_sub(p);
+  for _ in 0..4 {
+    y = y.wrapping_mul(2u32.wrapping_sub(p.wrapping_mul(y)));
+  }
+  0x7FFFFFFF & y.wrapping_neg()
+}
+
+/*
+  2^31 mod p, for 2^30 < p < 2^31
+*/
+pub(super) fn modp_r(p: u32) -> u32 {
+  (1u32 << 31) - p
+}
+
+pub(super) fn modp_add(a: u32, b: u32, p: u32) -> u32 {
+  let d = a.wrapping_add(b).wrapping_sub(p);
+  d.wrapping_add(p & (d >> 31).wrapping_neg())
+}
+
+pub(super) fn modp_sub(a: u32, b: u32, p: u32) -> u32 {
+  let d = a.wrapping_sub(b);
+  d.wrapping_add(p & (d >> 31).wrapping_neg())
+}
+
+/*
+  a * b / 2^31 mod p, with p0i = -1/p mod 2^31
+*/
+pub(super) fn modp_montymul(a: u32, b: u32, p: u32, p0i: u32) -> u32 {
+  let z = a as u64 * b as u64;
+  let w = (z.wrapping_mul(p0i as u64) & 0x7FFFFFFF) * p as u64;
+  let d = ((z + w) >> 31) as u32;
+  let d = d.wrapping_sub(p);
+  d.wrapping_add(p & (d >> 31).wrapping_neg())
+}
+
+/*
+  2^62 mod p
+*/
+pub(super) fn modp_r2(p: u32, p0i: u32) -> u32 {
+  // 2 in Montgomery representation, squared five times into 2^32 (2^63 mod p)
+  let mut z = modp_r(p);
+  z = modp_add(z, z, p);
+  for _ in 0..5 {
+    z = modp_montymul(z, z, p, p0i);
+  }
+  // halved into 2^62
+  (z + (p & (z & 1).wrapping_neg())) >> 1
+}
+
+/*
+  2^(31 * x) mod p, for 1 <= x <= 2^11
+*/
+pub(super) fn modp_rx(x: usize, p: u32, p0i: u32, r2: u32) -> u32 {
+  let x = x as u32 - 1;
+  let mut r = r2;
+  let mut z = modp_r(p);
+  let mut i = 0;
+  while (1u32 << i) <= x {
+    if x & (1u32 << i) != 0 {
+      z = modp_montymul(z, r, p, p0i);
+    }
+    r = modp_montymul(r, r, p, p0i);
+    i += 1;
+  }
+  z
+}
+
+/*
+  a / b mod p, or 0 if b = 0. r is 2^31 mod p.
+*/
+pub(super) fn modp_div(a: u32, b: u32, p: u32, p0i: u32, r: u32) -> u32 {
+  let e = p - 2;
+  let mut z = r;
+  for i in (0..31).rev() {
+    z = modp_montymul(z, z, p, p0i);
+    let z2 = modp_montymul(z, b, p, p0i);
+    z ^= (z ^ z2) & ((e >> i) & 1).wrapping_neg();
+  }
+
+  // z is R^2 / b since b was not in Montgomery representation
+  let z = modp_montymul(z, 1, p, p0i);
+  modp_montymul(a, z, p, p0i)
+}
+
+/*
+  Roots of x^n + 1 for the NTT, as powers of g (a primitive 2048-th root
+  of unity mod p) and of 1/g in Montgomery representation, in bit-reversed
+  order over 10 bits
+*/
+pub(super) fn modp_mkgm2(gm: &mut [u32], igm: &mut [u32], logn: usize, g: u32, p: u32, p0i: u32) {
+  let n = 1 << logn;
+
+  let r2 = modp_r2(p, p0i);
+  let mut g = modp_montymul(g, r2, p, p0i);
+  for _ in logn..10 {
+    g = modp_montymul(g, g, p, p0i);
+  }
+
+  let ig = modp_div(r2, g, p, p0i, modp_r(p));
+  let k = 10 - logn;
+  let mut x1 = modp_r(p);
+  let mut x2 = x1;
+  for u in 0..n {
+    let v = rev10(u << k);
+    gm[v] = x1;
+    igm[v] = x2;
+    x1 = modp_montymul(x1, g, p, p0i);
+    x2 = modp_montymul(x2, ig, p, p0i);
+  }
+}
+
+fn rev10(x: usize) -> usize {
+  ((x as u32).reverse_bits() >> 22) as usize
+}
+
+/*
+  NTT over the n = 2^logn coefficients a[0], a[stride], a[2 * stride]...
+*/
+pub(super) fn modp_ntt2_ext(a: &mut [u32], stride: usize, gm: &[u32], logn: usize, p: u32, p0i: u32) {
+  if logn == 0 {
+    return;
+  }
+  let n = 1 << logn;
+  let mut t = n;
+  let mut m = 1;
+  while m < n {
+    let ht = t >> 1;
+    let mut v1 = 0;
+    for u in 0..m {
+      let s = gm[m + u];
+      for v in 0..ht {
+        let r1 = (v1 + v) * stride;
+        let r2 = r1 + ht * stride;
+        let x = a[r1];
+        let y = modp_montymul(a[r2], s, p, p0i);
+        a[r1] = modp_add(x, y, p);
+        a[r2] = modp_sub(x, y, p);
+      }
+      v1 += t;
+    }
+    t = ht;
+    m <<= 1;
+  }
+}
+
+/*
+  Inverse NTT, including the division by n
+*/
+pub(super) fn modp_intt2_ext(a: &mut [u32], stride: usize, igm: &[u32], logn: usize, p: u32, p0i: u32) {
+  if logn == 0 {
+    return;
+  }
+  let n = 1 << logn;
+  let mut t = 1;
+  let mut m = n;
+  while m > 1 {
+    let hm = m >> 1;
+    let dt = t << 1;
+    let mut v1 = 0;
+    for u in 0..hm {
+      let s = igm[hm + u];
+      for v in 0..t {
+        let r1 = (v1 + v) * stride;
+        let r2 = r1 + t * stride;
+        let x = a[r1];
+        let y = a[r2];
+        a[r1] = modp_add(x, y, p);
+        a[r2] = modp_montymul(modp_sub(x, y, p), s, p, p0i);
+      }
+      v1 += dt;
+    }
+    t = dt;
+    m = hm;
+  }
+
+  // 1/n in Montgomery representation is 2^(31 - logn)
+  let ni = 1u32 << (31 - logn);
+  for k in 0..n {
+    a[k * stride] = modp_montymul(a[k * stride], ni, p, p0i);
+  }
+}
+
+pub(super) fn modp_ntt2(a: &mut [u32], gm: &[u32], logn: usize, p: u32, p0i: u32) {
+  modp_ntt2_ext(a, 1, gm, logn, p, p0i)
+}
+
+pub(super) fn modp_intt2(a: &mut [u32], igm: &[u32], logn: usize, p: u32, p0i: u32) {
+  modp_intt2_ext(a, 1, igm, logn, p, p0i)
+}
+
+/*
+  Replaces f, in NTT form, with f0^2 - x f1^2 of degree n/2 (in its first
+  n/2 elements), where f = f0(x^2) + x f1(x^2)
+*/
+pub(super) fn modp_poly_rec_res(f: &mut [u32], logn: usize, p: u32, p0i: u32, r2: u32) {
+  let hn = 1 << (logn - 1);
+  for u in 0..hn {
+    let w0 = f[u << 1];
+    let w1 = f[(u << 1) + 1];
+    f[u] = modp_montymul(modp_montymul(w0, w1, p, p0i), r2, p, p0i);
+  }
+}
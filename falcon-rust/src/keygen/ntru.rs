@@ -0,0 +1,824 @@
+use super::{modp::*, table::PRIMES, zint::*};
+use crate::fft::*;
+use alloc::{vec, vec::Vec};
+
+/*
+  NTRUSolve as in `keygen.c`: F and G with fG - gF = q are computed
+  recursively over the field norms of f and g, from their resultants with
+  x^n + 1 at the deepest level, and reduced with Babai's algorithm on the
+  way back up. Integers are kept in RNS, modulo the small primes, or as
+  31-bit words.
+*/
+
+/*
+  Word lengths of f and g (MAX_BL_SMALL) and of the unreduced F and G
+  (MAX_BL_LARGE), indexed by depth
+*/
+const MAX_BL_SMALL: [usize; 11] = [1, 1, 2, 2, 4, 7, 14, 27, 53, 106, 209];
+const MAX_BL_LARGE: [usize; 10] = [2, 2, 5, 7, 12, 21, 40, 78, 157, 308];
+
+// Average and standard deviation of the bit length of f and g, by depth
+const BITLENGTH: [(i32, i32); 11] = [
+  (4, 0),
+  (11, 1),
+  (24, 1),
+  (50, 1),
+  (102, 1),
+  (202, 2),
+  (401, 4),
+  (794, 5),
+  (1577, 8),
+  (3138, 13),
+  (6308, 25),
+];
+
+// Depth from which k * f and k * g are computed with the NTT
+const DEPTH_INT_FG: usize = 4;
+
+const PTWO31M1: f64 = 2147483647.0;
+const PTWO63M1: f64 = 9223372036854775807.0;
+
+/*
+  Solves fG - gF = q, with the coefficients of F and G within [-lim, lim]
+*/
+pub(super) fn solve_ntru(logn: usize, f: &[i16], g: &[i16], lim: i32) -> Option<(Vec<i16>, Vec<i16>)> {
+  let n = 1 << logn;
+
+  let (mut F, mut G) = solve_ntru_deepest(logn, f, g)?;
+
+  // below n = 8, the coefficients are too large for the depth 0 and 1 variants
+  if logn <= 2 {
+    for depth in (0..logn).rev() {
+      (F, G) = solve_ntru_intermediate(logn, f, g, depth, &F, &G)?;
+    }
+  } else {
+    for depth in (2..logn).rev() {
+      (F, G) = solve_ntru_intermediate(logn, f, g, depth, &F, &G)?;
+    }
+    (F, G) = solve_ntru_binary_depth1(logn, f, g, &F, &G)?;
+    (F, G) = solve_ntru_binary_depth0(logn, f, g, &F, &G);
+  }
+
+  let F = poly_big_to_small(&F, lim)?;
+  let G = poly_big_to_small(&G, lim)?;
+
+  // the equation is checked modulo a small prime, in NTT form
+  let p = PRIMES[0].p;
+  let p0i = modp_ninv31(p);
+  let mut gm = vec![0u32; n];
+  let mut igm = vec![0u32; n];
+  modp_mkgm2(&mut gm, &mut igm, logn, PRIMES[0].g, p, p0i);
+  let to_ntt = |a: &[i16]| {
+    let mut t: Vec<u32> = a.iter().map(|&x| modp_set(x as i32, p)).collect();
+    modp_ntt2(&mut t, &gm, logn, p, p0i);
+    t
+  };
+  let (ft, gt, Ft, Gt) = (to_ntt(f), to_ntt(g), to_ntt(&F), to_ntt(&G));
+  let r = modp_montymul(12289, 1, p, p0i);
+  for u in 0..n {
+    let z = modp_sub(
+      modp_montymul(ft[u], Gt[u], p, p0i),
+      modp_montymul(gt[u], Ft[u], p, p0i),
+      p,
+    );
+    if z != r {
+      return None;
+    }
+  }
+
+  Some((F, G))
+}
+
+/*
+  From f and g of degree n = 2^logn, in RNS (and in NTT form with
+  `in_ntt`) with the length of `depth`, computes their field norms
+  f' = f0^2 - x f1^2 and g' = g0^2 - x g1^2 of degree n/2, with the length
+  of depth + 1 (in NTT form with `out_ntt`)
+*/
+fn make_fg_step(
+  mut fs: Vec<u32>,
+  mut gs: Vec<u32>,
+  logn: usize,
+  depth: usize,
+  in_ntt: bool,
+  out_ntt: bool,
+) -> (Vec<u32>, Vec<u32>) {
+  let n = 1 << logn;
+  let hn = n >> 1;
+  let slen = MAX_BL_SMALL[depth];
+  let tlen = MAX_BL_SMALL[depth + 1];
+
+  let mut fd = vec![0u32; hn * tlen];
+  let mut gd = vec![0u32; hn * tlen];
+  let mut gm = vec![0u32; n];
+  let mut igm = vec![0u32; n];
+  let mut t1 = vec![0u32; n];
+
+  // first slen words: from the RNS input, de-NTTized as we go
+  for u in 0..slen {
+    let p = PRIMES[u].p;
+    let p0i = modp_ninv31(p);
+    let r2 = modp_r2(p, p0i);
+    modp_mkgm2(&mut gm, &mut igm, logn, PRIMES[u].g, p, p0i);
+
+    for (s, d) in [(&mut fs, &mut fd), (&mut gs, &mut gd)] {
+      for v in 0..n {
+        t1[v] = s[v * slen + u];
+      }
+      if !in_ntt {
+        modp_ntt2(&mut t1, &gm, logn, p, p0i);
+      }
+      for v in 0..hn {
+        let (w0, w1) = (t1[v << 1], t1[(v << 1) + 1]);
+        d[v * tlen + u] = modp_montymul(modp_montymul(w0, w1, p, p0i), r2, p, p0i);
+      }
+      if in_ntt {
+        modp_intt2_ext(&mut s[u..], slen, &igm, logn, p, p0i);
+      }
+    }
+
+    if !out_ntt {
+      modp_intt2_ext(&mut fd[u..], tlen, &igm, logn - 1, p, p0i);
+      modp_intt2_ext(&mut gd[u..], tlen, &igm, logn - 1, p, p0i);
+    }
+  }
+
+  zint_rebuild_crt(&mut fs, slen, slen, n, true);
+  zint_rebuild_crt(&mut gs, slen, slen, n, true);
+
+  // remaining words: from the rebuilt values, reduced modulo each prime
+  for u in slen..tlen {
+    let p = PRIMES[u].p;
+    let p0i = modp_ninv31(p);
+    let r2 = modp_r2(p, p0i);
+    let rx = modp_rx(slen, p, p0i, r2);
+    modp_mkgm2(&mut gm, &mut igm, logn, PRIMES[u].g, p, p0i);
+
+    for (s, d) in [(&fs, &mut fd), (&gs, &mut gd)] {
+      for v in 0..n {
+        t1[v] = zint_mod_small_signed(&s[v * slen..(v + 1) * slen], p, p0i, r2, rx);
+      }
+      modp_ntt2(&mut t1, &gm, logn, p, p0i);
+      for v in 0..hn {
+        let (w0, w1) = (t1[v << 1], t1[(v << 1) + 1]);
+        d[v * tlen + u] = modp_montymul(modp_montymul(w0, w1, p, p0i), r2, p, p0i);
+      }
+    }
+
+    if !out_ntt {
+      modp_intt2_ext(&mut fd[u..], tlen, &igm, logn - 1, p, p0i);
+      modp_intt2_ext(&mut gd[u..], tlen, &igm, logn - 1, p, p0i);
+    }
+  }
+
+  (fd, gd)
+}
+
+/*
+  f and g at the given depth, in RNS with MAX_BL_SMALL[depth] words per
+  coefficient
+*/
+fn make_fg(f: &[i16], g: &[i16], logn: usize, depth: usize, out_ntt: bool) -> (Vec<u32>, Vec<u32>) {
+  let p = PRIMES[0].p;
+  let mut ft: Vec<u32> = f.iter().map(|&x| modp_set(x as i32, p)).collect();
+  let mut gt: Vec<u32> = g.iter().map(|&x| modp_set(x as i32, p)).collect();
+
+  if depth == 0 && out_ntt {
+    let n = 1 << logn;
+    let p0i = modp_ninv31(p);
+    let mut gm = vec![0u32; n];
+    let mut igm = vec![0u32; n];
+    modp_mkgm2(&mut gm, &mut igm, logn, PRIMES[0].g, p, p0i);
+    modp_ntt2(&mut ft, &gm, logn, p, p0i);
+    modp_ntt2(&mut gt, &gm, logn, p, p0i);
+    return (ft, gt);
+  }
+
+  for d in 0..depth {
+    (ft, gt) = make_fg_step(ft, gt, logn - d, d, d != 0, d + 1 < depth || out_ntt);
+  }
+  (ft, gt)
+}
+
+/*
+  Deepest level: the resultants of f and g with x^n + 1 are rebuilt as
+  integers, then F and G are given by their Bezout coefficients times q
+*/
+fn solve_ntru_deepest(logn_top: usize, f: &[i16], g: &[i16]) -> Option<(Vec<u32>, Vec<u32>)> {
+  let len = MAX_BL_SMALL[logn_top];
+
+  let (mut fp, mut gp) = make_fg(f, g, logn_top, logn_top, false);
+  zint_rebuild_crt(&mut fp, len, len, 1, false);
+  zint_rebuild_crt(&mut gp, len, len, 1, false);
+
+  // the binary GCD requires odd resultants
+  let mut F = vec![0u32; len];
+  let mut G = vec![0u32; len];
+  if !zint_bezout(&mut G, &mut F, &fp, &gp) {
+    return None;
+  }
+
+  // a carry means that the values do not fit
+  if zint_mul_small(&mut F, 12289) != 0 || zint_mul_small(&mut G, 12289) != 0 {
+    return None;
+  }
+
+  Some((F, G))
+}
+
+/*
+  Unreduced F = F'(x^2) * adj(g) and G = G'(x^2) * adj(f) modulo the first
+  llen primes, with F' and G' (degree n/2, dlen words) from the deeper
+  level, as rows of llen words. ft and gt give f and g modulo each prime,
+  in NTT form (see `fg_mod_p`).
+*/
+fn lift_FG<M>(Fd: &[u32], Gd: &[u32], logn: usize, dlen: usize, llen: usize, mut fg_mod_p: M) -> (Vec<u32>, Vec<u32>)
+where
+  M: FnMut(usize, &mut [u32], &mut [u32], &mut [u32], &mut [u32]),
+{
+  let n = 1 << logn;
+  let hn = n >> 1;
+  let mut Ft = vec![0u32; n * llen];
+  let mut Gt = vec![0u32; n * llen];
+
+  // F' and G' modulo the primes, in the first n/2 rows
+  for u in 0..llen {
+    let p = PRIMES[u].p;
+    let p0i = modp_ninv31(p);
+    let r2 = modp_r2(p, p0i);
+    let rx = modp_rx(dlen, p, p0i, r2);
+    for v in 0..hn {
+      Ft[v * llen + u] = zint_mod_small_signed(&Fd[v * dlen..(v + 1) * dlen], p, p0i, r2, rx);
+      Gt[v * llen + u] = zint_mod_small_signed(&Gd[v * dlen..(v + 1) * dlen], p, p0i, r2, rx);
+    }
+  }
+
+  let mut Fp = vec![0u32; hn];
+  let mut Gp = vec![0u32; hn];
+  for u in 0..llen {
+    let p = PRIMES[u].p;
+    let p0i = modp_ninv31(p);
+    let r2 = modp_r2(p, p0i);
+
+    let mut gm = vec![0u32; n];
+    let mut igm = vec![0u32; n];
+    let mut fx = vec![0u32; n];
+    let mut gx = vec![0u32; n];
+    fg_mod_p(u, &mut gm, &mut igm, &mut fx, &mut gx);
+
+    for v in 0..hn {
+      Fp[v] = Ft[v * llen + u];
+      Gp[v] = Gt[v * llen + u];
+    }
+    modp_ntt2(&mut Fp, &gm, logn - 1, p, p0i);
+    modp_ntt2(&mut Gp, &gm, logn - 1, p, p0i);
+
+    /*
+      The roots w and -w of x^n + 1 are consecutive in the NTT and share
+      w^2, with f(-w) = adj(f)(w):
+        F(w) = F'(w^2) * g(-w)
+        G(w) = G'(w^2) * f(-w)
+    */
+    for v in 0..hn {
+      let (ftA, ftB) = (fx[v << 1], fx[(v << 1) + 1]);
+      let (gtA, gtB) = (gx[v << 1], gx[(v << 1) + 1]);
+      let mFp = modp_montymul(Fp[v], r2, p, p0i);
+      let mGp = modp_montymul(Gp[v], r2, p, p0i);
+      let x = (v << 1) * llen + u;
+      Ft[x] = modp_montymul(gtB, mFp, p, p0i);
+      Ft[x + llen] = modp_montymul(gtA, mFp, p, p0i);
+      Gt[x] = modp_montymul(ftB, mGp, p, p0i);
+      Gt[x + llen] = modp_montymul(ftA, mGp, p, p0i);
+    }
+    modp_intt2_ext(&mut Ft[u..], llen, &igm, logn, p, p0i);
+    modp_intt2_ext(&mut Gt[u..], llen, &igm, logn, p, p0i);
+  }
+
+  (Ft, Gt)
+}
+
+/*
+  Intermediate level: F and G are lifted from the deeper level, then
+  reduced with Babai's algorithm against (f, g) in floating point, k * f
+  and k * g being subtracted with a scaling that decreases at each round
+*/
+fn solve_ntru_intermediate(
+  logn_top: usize,
+  f: &[i16],
+  g: &[i16],
+  depth: usize,
+  Fd: &[u32],
+  Gd: &[u32],
+) -> Option<(Vec<u32>, Vec<u32>)> {
+  let logn = logn_top - depth;
+  let n = 1 << logn;
+  let hn = n >> 1;
+
+  /*
+    slen: length of f and g, and of the reduced F and G
+    dlen: length of F and G from the deeper level
+    llen: length of the unreduced F and G
+  */
+  let slen = MAX_BL_SMALL[depth];
+  let dlen = MAX_BL_SMALL[depth + 1];
+  let llen = MAX_BL_LARGE[depth];
+
+  let (mut ft, mut gt) = make_fg(f, g, logn_top, depth, true);
+
+  let (mut Ft, mut Gt) = lift_FG(Fd, Gd, logn, dlen, llen, |u, gm, igm, fx, gx| {
+    let p = PRIMES[u].p;
+    let p0i = modp_ninv31(p);
+    let r2 = modp_r2(p, p0i);
+
+    // f and g are de-NTTized over the first slen primes, then rebuilt
+    if u == slen {
+      zint_rebuild_crt(&mut ft, slen, slen, n, true);
+      zint_rebuild_crt(&mut gt, slen, slen, n, true);
+    }
+
+    modp_mkgm2(gm, igm, logn, PRIMES[u].g, p, p0i);
+
+    if u < slen {
+      for v in 0..n {
+        fx[v] = ft[v * slen + u];
+        gx[v] = gt[v * slen + u];
+      }
+      modp_intt2_ext(&mut ft[u..], slen, igm, logn, p, p0i);
+      modp_intt2_ext(&mut gt[u..], slen, igm, logn, p, p0i);
+    } else {
+      let rx = modp_rx(slen, p, p0i, r2);
+      for v in 0..n {
+        fx[v] = zint_mod_small_signed(&ft[v * slen..(v + 1) * slen], p, p0i, r2, rx);
+        gx[v] = zint_mod_small_signed(&gt[v * slen..(v + 1) * slen], p, p0i, r2, rx);
+      }
+      modp_ntt2(fx, gm, logn, p, p0i);
+      modp_ntt2(gx, gm, logn, p, p0i);
+    }
+  });
+
+  zint_rebuild_crt(&mut Ft, llen, llen, n, true);
+  zint_rebuild_crt(&mut Gt, llen, llen, n, true);
+
+  /*
+    Babai's reduction. f and g are converted to floating point from their
+    top 10 words at most, i.e. scaled down by 2^scale_fg, and
+    1 / (f * adj(f) + g * adj(g)) is kept with adj(f) and adj(g). Each
+    round assumes a maximum length for F and G: k is computed from their
+    top words, scaled down by 2^scale_k, and k * f and k * g are
+    subtracted from F and G.
+  */
+  let rlen = slen.min(10);
+  let mut rt3 = poly_big_to_fp(&ft[slen - rlen..], rlen, slen, logn);
+  let mut rt4 = poly_big_to_fp(&gt[slen - rlen..], rlen, slen, logn);
+  let scale_fg = 31 * (slen - rlen) as i32;
+
+  // bounds on the bit length of f and g, within six standard deviations
+  let (avg, std) = BITLENGTH[depth];
+  let minbl_fg = avg - 6 * std;
+  let maxbl_fg = avg + 6 * std;
+
+  fft(&mut rt3);
+  fft(&mut rt4);
+  let mut rt5 = vec![0.0; hn];
+  poly_invnorm2_fft(&mut rt5, &rt3, &rt4);
+  poly_adj_fft(&mut rt3);
+  poly_adj_fft(&mut rt4);
+
+  let mut FGlen = llen;
+  let mut maxbl_FG = 31 * llen as i32;
+  let mut scale_k = maxbl_FG - minbl_fg;
+  let mut k = vec![0i32; n];
+
+  loop {
+    let rlen = FGlen.min(10);
+    let scale_FG = 31 * (FGlen - rlen) as i32;
+    let mut rt1 = poly_big_to_fp(&Ft[FGlen - rlen..], rlen, llen, logn);
+    let mut rt2 = poly_big_to_fp(&Gt[FGlen - rlen..], rlen, llen, logn);
+
+    // (F * adj(f) + G * adj(g)) / (f * adj(f) + g * adj(g))
+    fft(&mut rt1);
+    fft(&mut rt2);
+    poly_mul_fft(&mut rt1, &rt3);
+    poly_mul_fft(&mut rt2, &rt4);
+    poly_add(&mut rt2, &rt1);
+    poly_mul_autoadj_fft(&mut rt2, &rt5);
+    ifft(&mut rt2);
+
+    // rescaled from 2^(scale_FG - scale_fg) to 2^scale_k
+    let mut dc = scale_k - scale_FG + scale_fg;
+    let mut pt = if dc < 0 {
+      dc = -dc;
+      2.0
+    } else {
+      0.5
+    };
+    let mut pdc = 1.0;
+    while dc != 0 {
+      if dc & 1 != 0 {
+        pdc *= pt;
+      }
+      dc >>= 1;
+      pt *= pt;
+    }
+
+    for (ku, &x) in k.iter_mut().zip(rt2.iter()) {
+      let xv = x * pdc;
+      // out of range values mean that the reduction failed
+      let in_range = -PTWO31M1 < xv && xv < PTWO31M1;
+      if !in_range {
+        return None;
+      }
+      *ku = fpr_rint(xv) as i32;
+    }
+
+    let sch = (scale_k / 31) as usize;
+    let scl = (scale_k % 31) as u32;
+    if depth <= DEPTH_INT_FG {
+      poly_sub_scaled_ntt(&mut Ft, FGlen, llen, &ft, slen, slen, &k, sch, scl, logn);
+      poly_sub_scaled_ntt(&mut Gt, FGlen, llen, &gt, slen, slen, &k, sch, scl, logn);
+    } else {
+      poly_sub_scaled(&mut Ft, FGlen, llen, &ft, slen, slen, &k, sch, scl, logn);
+      poly_sub_scaled(&mut Gt, FGlen, llen, &gt, slen, slen, &k, sch, scl, logn);
+    }
+
+    // new maximum length of F and G, for f and g of maximal length
+    let new_maxbl_FG = scale_k + maxbl_fg + 10;
+    if new_maxbl_FG < maxbl_FG {
+      maxbl_FG = new_maxbl_FG;
+      if FGlen as i32 * 31 >= maxbl_FG + 31 {
+        FGlen -= 1;
+      }
+    }
+
+    // each round is assumed to reduce F and G by at least 25 bits
+    if scale_k <= 0 {
+      break;
+    }
+    scale_k = (scale_k - 25).max(0);
+  }
+
+  // F and G are returned with slen words, sign-extended if needed
+  let mut F = vec![0u32; n * slen];
+  let mut G = vec![0u32; n * slen];
+  for (src, dst) in [(&Ft, &mut F), (&Gt, &mut G)] {
+    for u in 0..n {
+      let x = &src[u * llen..(u + 1) * llen];
+      let y = &mut dst[u * slen..(u + 1) * slen];
+      if FGlen < slen {
+        y[..FGlen].copy_from_slice(&x[..FGlen]);
+        let sw = (x[FGlen - 1] >> 30).wrapping_neg() >> 1;
+        y[FGlen..].fill(sw);
+      } else {
+        y.copy_from_slice(&x[..slen]);
+      }
+    }
+  }
+
+  Some((F, G))
+}
+
+/*
+  Depth 1: the unreduced F and G fit in 53 bits, and are reduced in a
+  single pass in floating point
+*/
+fn solve_ntru_binary_depth1(
+  logn_top: usize,
+  f: &[i16],
+  g: &[i16],
+  Fd: &[u32],
+  Gd: &[u32],
+) -> Option<(Vec<u32>, Vec<u32>)> {
+  let depth = 1;
+  let n_top = 1 << logn_top;
+  let logn = logn_top - depth;
+  let n = 1 << logn;
+
+  let slen = MAX_BL_SMALL[depth];
+  let dlen = MAX_BL_SMALL[depth + 1];
+  let llen = MAX_BL_LARGE[depth];
+
+  let mut ft = vec![0u32; n * slen];
+  let mut gt = vec![0u32; n * slen];
+
+  /*
+    f and g at depth 1 are recomputed from the full degree ones modulo
+    each prime. The tables for 2n are used for degree n, their first n
+    entries being the same.
+  */
+  let (mut Ft, mut Gt) = lift_FG(Fd, Gd, logn, dlen, llen, |u, gm, igm, fx, gx| {
+    let p = PRIMES[u].p;
+    let p0i = modp_ninv31(p);
+    let r2 = modp_r2(p, p0i);
+
+    let mut gm_top = vec![0u32; n_top];
+    let mut igm_top = vec![0u32; n_top];
+    modp_mkgm2(&mut gm_top, &mut igm_top, logn_top, PRIMES[u].g, p, p0i);
+    gm.copy_from_slice(&gm_top[..n]);
+    igm.copy_from_slice(&igm_top[..n]);
+
+    let mut fx_top: Vec<u32> = f.iter().map(|&x| modp_set(x as i32, p)).collect();
+    let mut gx_top: Vec<u32> = g.iter().map(|&x| modp_set(x as i32, p)).collect();
+    modp_ntt2(&mut fx_top, &gm_top, logn_top, p, p0i);
+    modp_ntt2(&mut gx_top, &gm_top, logn_top, p, p0i);
+    for e in (logn + 1..=logn_top).rev() {
+      modp_poly_rec_res(&mut fx_top, e, p, p0i, r2);
+      modp_poly_rec_res(&mut gx_top, e, p, p0i, r2);
+    }
+    fx.copy_from_slice(&fx_top[..n]);
+    gx.copy_from_slice(&gx_top[..n]);
+
+    // f and g are also kept, over the first slen primes
+    if u < slen {
+      let mut t = fx.to_vec();
+      modp_intt2(&mut t, igm, logn, p, p0i);
+      for v in 0..n {
+        ft[v * slen + u] = t[v];
+      }
+      let mut t = gx.to_vec();
+      modp_intt2(&mut t, igm, logn, p, p0i);
+      for v in 0..n {
+        gt[v * slen + u] = t[v];
+      }
+    }
+  });
+
+  zint_rebuild_crt(&mut Ft, llen, llen, n, true);
+  zint_rebuild_crt(&mut Gt, llen, llen, n, true);
+  zint_rebuild_crt(&mut ft, slen, slen, n, true);
+  zint_rebuild_crt(&mut gt, slen, slen, n, true);
+
+  // Babai's reduction, without scaling
+  let mut rt1 = poly_big_to_fp(&Ft, llen, llen, logn);
+  let mut rt2 = poly_big_to_fp(&Gt, llen, llen, logn);
+  let mut rt3 = poly_big_to_fp(&ft, slen, slen, logn);
+  let mut rt4 = poly_big_to_fp(&gt, slen, slen, logn);
+  fft(&mut rt1);
+  fft(&mut rt2);
+  fft(&mut rt3);
+  fft(&mut rt4);
+
+  // k = (F * adj(f) + G * adj(g)) / (f * adj(f) + g * adj(g)), rounded
+  let mut rt5 = vec![0.0; n];
+  let mut rt6 = vec![0.0; n >> 1];
+  poly_add_muladj_fft(&mut rt5, &rt1, &rt2, &rt3, &rt4);
+  poly_invnorm2_fft(&mut rt6, &rt3, &rt4);
+  poly_mul_autoadj_fft(&mut rt5, &rt6);
+  ifft(&mut rt5);
+  for z in rt5.iter_mut() {
+    let in_range = *z < PTWO63M1 && -PTWO63M1 < *z;
+    if !in_range {
+      return None;
+    }
+    *z = fpr_rint(*z) as f64;
+  }
+  fft(&mut rt5);
+
+  // F - k * f and G - k * g
+  poly_mul_fft(&mut rt3, &rt5);
+  poly_mul_fft(&mut rt4, &rt5);
+  poly_sub(&mut rt1, &rt3);
+  poly_sub(&mut rt2, &rt4);
+  ifft(&mut rt1);
+  ifft(&mut rt2);
+
+  let F = rt1.iter().map(|&x| fpr_rint(x) as u32).collect();
+  let G = rt2.iter().map(|&x| fpr_rint(x) as u32).collect();
+  Some((F, G))
+}
+
+/*
+  Top level: everything fits modulo the first small prime, and Babai's
+  reduction takes a single pass
+*/
+fn solve_ntru_binary_depth0(logn: usize, f: &[i16], g: &[i16], Fd: &[u32], Gd: &[u32]) -> (Vec<u32>, Vec<u32>) {
+  let n = 1 << logn;
+  let hn = n >> 1;
+
+  let p = PRIMES[0].p;
+  let p0i = modp_ninv31(p);
+  let r2 = modp_r2(p, p0i);
+
+  let mut gm = vec![0u32; n];
+  let mut igm = vec![0u32; n];
+  modp_mkgm2(&mut gm, &mut igm, logn, PRIMES[0].g, p, p0i);
+
+  let small_mod_p = |a: &[i16]| -> Vec<u32> { a.iter().map(|&x| modp_set(x as i32, p)).collect() };
+
+  // F' and G' in NTT form
+  let mut Fp: Vec<u32> = Fd[..hn].iter().map(|&x| modp_set(zint_one_to_plain(x), p)).collect();
+  let mut Gp: Vec<u32> = Gd[..hn].iter().map(|&x| modp_set(zint_one_to_plain(x), p)).collect();
+  modp_ntt2(&mut Fp, &gm, logn - 1, p, p0i);
+  modp_ntt2(&mut Gp, &gm, logn - 1, p, p0i);
+
+  let mut ft = small_mod_p(f);
+  let mut gt = small_mod_p(g);
+  modp_ntt2(&mut ft, &gm, logn, p, p0i);
+  modp_ntt2(&mut gt, &gm, logn, p, p0i);
+
+  // unreduced F and G
+  for u in (0..n).step_by(2) {
+    let (ftA, ftB) = (ft[u], ft[u + 1]);
+    let (gtA, gtB) = (gt[u], gt[u + 1]);
+    let mFp = modp_montymul(Fp[u >> 1], r2, p, p0i);
+    let mGp = modp_montymul(Gp[u >> 1], r2, p, p0i);
+    ft[u] = modp_montymul(gtB, mFp, p, p0i);
+    ft[u + 1] = modp_montymul(gtA, mFp, p, p0i);
+    gt[u] = modp_montymul(ftB, mGp, p, p0i);
+    gt[u + 1] = modp_montymul(ftA, mGp, p, p0i);
+  }
+  modp_intt2(&mut ft, &igm, logn, p, p0i);
+  modp_intt2(&mut gt, &igm, logn, p, p0i);
+
+  let (mut Fp, mut Gp) = (ft, gt);
+  modp_ntt2(&mut Fp, &gm, logn, p, p0i);
+  modp_ntt2(&mut Gp, &gm, logn, p, p0i);
+
+  // F * adj(f) + G * adj(g) in t2 and f * adj(f) + g * adj(g) in t3, modulo p
+  let mut t2 = vec![0u32; n];
+  let mut t3 = vec![0u32; n];
+  for (a, A, first) in [(f, &Fp, true), (g, &Gp, false)] {
+    let mut t4 = small_mod_p(a);
+    let mut t5 = vec![0u32; n];
+    t5[0] = t4[0];
+    for u in 1..n {
+      t5[n - u] = modp_set(-(a[u] as i32), p);
+    }
+    modp_ntt2(&mut t4, &gm, logn, p, p0i);
+    modp_ntt2(&mut t5, &gm, logn, p, p0i);
+    for u in 0..n {
+      let w = modp_montymul(t5[u], r2, p, p0i);
+      let x = modp_montymul(w, A[u], p, p0i);
+      let y = modp_montymul(w, t4[u], p, p0i);
+      if first {
+        t2[u] = x;
+        t3[u] = y;
+      } else {
+        t2[u] = modp_add(t2[u], x, p);
+        t3[u] = modp_add(t3[u], y, p);
+      }
+    }
+  }
+  modp_intt2(&mut t2, &igm, logn, p, p0i);
+  modp_intt2(&mut t3, &igm, logn, p, p0i);
+
+  /*
+    The quotient is rounded in floating point. f * adj(f) + g * adj(g) is
+    auto-adjoint, so only the real parts of its FFT are kept.
+  */
+  let mut rt2: Vec<f64> = t3.iter().map(|&x| modp_norm(x, p) as f64).collect();
+  fft(&mut rt2);
+  rt2.truncate(hn);
+  let mut rt3: Vec<f64> = t2.iter().map(|&x| modp_norm(x, p) as f64).collect();
+  fft(&mut rt3);
+  poly_div_autoadj_fft(&mut rt3, &rt2);
+  ifft(&mut rt3);
+  let mut k: Vec<u32> = rt3.iter().map(|&x| modp_set(fpr_rint(x) as i32, p)).collect();
+
+  // F - k * f and G - k * g
+  let mut t4 = small_mod_p(f);
+  let mut t5 = small_mod_p(g);
+  modp_ntt2(&mut k, &gm, logn, p, p0i);
+  modp_ntt2(&mut t4, &gm, logn, p, p0i);
+  modp_ntt2(&mut t5, &gm, logn, p, p0i);
+  for u in 0..n {
+    let kw = modp_montymul(k[u], r2, p, p0i);
+    Fp[u] = modp_sub(Fp[u], modp_montymul(kw, t4[u], p, p0i), p);
+    Gp[u] = modp_sub(Gp[u], modp_montymul(kw, t5[u], p, p0i), p);
+  }
+  modp_intt2(&mut Fp, &igm, logn, p, p0i);
+  modp_intt2(&mut Gp, &igm, logn, p, p0i);
+  for x in Fp.iter_mut().chain(Gp.iter_mut()) {
+    *x = modp_norm(*x, p) as u32;
+  }
+
+  (Fp, Gp)
+}
+
+/*
+  Floating-point approximation of a polynomial whose coefficients have
+  flen words and start fstride words apart
+*/
+fn poly_big_to_fp(f: &[u32], flen: usize, fstride: usize, logn: usize) -> Vec<f64> {
+  let n = 1 << logn;
+  let mut d = vec![0.0; n];
+  if flen == 0 {
+    return d;
+  }
+
+  for (u, x) in d.iter_mut().enumerate() {
+    let f = &f[u * fstride..u * fstride + flen];
+
+    // negative values are loaded as their absolute value, then negated
+    let neg = (f[flen - 1] >> 30).wrapping_neg();
+    let xm = neg >> 1;
+    let mut cc = neg & 1;
+    let mut fsc = 1.0;
+    for &fw in f {
+      let w = (fw ^ xm).wrapping_add(cc);
+      cc = w >> 31;
+      let w = w & 0x7FFFFFFF;
+      let w = w.wrapping_sub((w << 1) & neg);
+      *x += (w as i32) as f64 * fsc;
+      fsc *= 2147483648.0;
+    }
+  }
+  d
+}
+
+/*
+  One-word coefficients to small integers, failing outside [-lim, lim]
+*/
+fn poly_big_to_small(s: &[u32], lim: i32) -> Option<Vec<i16>> {
+  s.iter()
+    .map(|&x| {
+      let z = zint_one_to_plain(x);
+      (-lim..=lim).contains(&z).then_some(z as i16)
+    })
+    .collect()
+}
+
+/*
+  F - k * f * 2^sc, where F, f and k are polynomials modulo x^n + 1 and
+  sc = 31 * sch + scl, with the schoolbook multiplication
+*/
+#[allow(clippy::too_many_arguments)]
+fn poly_sub_scaled(
+  F: &mut [u32],
+  Flen: usize,
+  Fstride: usize,
+  f: &[u32],
+  flen: usize,
+  fstride: usize,
+  k: &[i32],
+  sch: usize,
+  scl: u32,
+  logn: usize,
+) {
+  let n = 1 << logn;
+  for (u, &ku) in k.iter().enumerate().take(n) {
+    let mut kf = -ku;
+    let mut x = u * Fstride;
+    for v in 0..n {
+      zint_add_scaled_mul_small(&mut F[x..x + Flen], &f[v * fstride..v * fstride + flen], kf, sch, scl);
+      // x^n = -1
+      if u + v == n - 1 {
+        x = 0;
+        kf = -kf;
+      } else {
+        x += Fstride;
+      }
+    }
+  }
+}
+
+/*
+  Same as `poly_sub_scaled`, with k * f computed in RNS with the NTT
+*/
+#[allow(clippy::too_many_arguments)]
+fn poly_sub_scaled_ntt(
+  F: &mut [u32],
+  Flen: usize,
+  Fstride: usize,
+  f: &[u32],
+  flen: usize,
+  fstride: usize,
+  k: &[i32],
+  sch: usize,
+  scl: u32,
+  logn: usize,
+) {
+  let n = 1 << logn;
+  let tlen = flen + 1;
+  let mut gm = vec![0u32; n];
+  let mut igm = vec![0u32; n];
+  let mut fk = vec![0u32; n * tlen];
+  let mut t1 = vec![0u32; n];
+
+  for u in 0..tlen {
+    let p = PRIMES[u].p;
+    let p0i = modp_ninv31(p);
+    let r2 = modp_r2(p, p0i);
+    let rx = modp_rx(flen, p, p0i, r2);
+    modp_mkgm2(&mut gm, &mut igm, logn, PRIMES[u].g, p, p0i);
+
+    for (t, &kv) in t1.iter_mut().zip(k) {
+      *t = modp_set(kv, p);
+    }
+    modp_ntt2(&mut t1, &gm, logn, p, p0i);
+    for v in 0..n {
+      fk[v * tlen + u] = zint_mod_small_signed(&f[v * fstride..v * fstride + flen], p, p0i, r2, rx);
+    }
+    modp_ntt2_ext(&mut fk[u..], tlen, &gm, logn, p, p0i);
+    for v in 0..n {
+      let x = &mut fk[v * tlen + u];
+      *x = modp_montymul(modp_montymul(t1[v], *x, p, p0i), r2, p, p0i);
+    }
+    modp_intt2_ext(&mut fk[u..], tlen, &igm, logn, p, p0i);
+  }
+
+  zint_rebuild_crt(&mut fk, tlen, tlen, n, true);
+
+  for u in 0..n {
+    zint_sub_scaled(&mut F[u * Fstride..u * Fstride + Flen], &fk[u * tlen..(u + 1) * tlen], sch, scl);
+  }
+}
@@ -0,0 +1,418 @@
+use super::{modp::*, table::PRIMES};
+use alloc::vec;
+
+/*
+  Big integers as little-endian arrays of 31-bit words, the top bit of
+  each u32 being zero. Negative values use two's complement. The
+  lengths are taken from the slices. (`zint_*` in `keygen.c`)
+*/
+
+/*
+  a - b, with a and b of the same length, applied only if ctl = 1. The
+  carry is returned in both cases.
+*/
+pub(super) fn zint_sub(a: &mut [u32], b: &[u32], ctl: u32) -> u32 {
+  let mut cc = 0;
+  let m = ctl.wrapping_neg();
+  for (aw, &bw) in a.iter_mut().zip(b) {
+    let w = aw.wrapping_sub(bw).wrapping_sub(cc);
+    cc = w >> 31;
+    *aw ^= ((w & 0x7FFFFFFF) ^ *aw) & m;
+  }
+  cc
+}
+
+/*
+  m * x, for x < 2^31, returning the carry word
+*/
+pub(super) fn zint_mul_small(m: &mut [u32], x: u32) -> u32 {
+  let mut cc = 0;
+  for w in m.iter_mut() {
+    let z = *w as u64 * x as u64 + cc as u64;
+    *w = z as u32 & 0x7FFFFFFF;
+    cc = (z >> 31) as u32;
+  }
+  cc
+}
+
+/*
+  d mod p, for an unsigned d, with p0i = -1/p mod 2^31 and r2 = 2^62 mod p
+*/
+pub(super) fn zint_mod_small_unsigned(d: &[u32], p: u32, p0i: u32, r2: u32) -> u32 {
+  // words are injected from the top: x <- x * 2^31 + w
+  let mut x = 0;
+  for &dw in d.iter().rev() {
+    x = modp_montymul(x, r2, p, p0i);
+    let w = dw.wrapping_sub(p);
+    let w = w.wrapping_add(p & (w >> 31).wrapping_neg());
+    x = modp_add(x, w, p);
+  }
+  x
+}
+
+/*
+  d mod p, for a signed d, with rx = 2^(31 * len) mod p
+*/
+pub(super) fn zint_mod_small_signed(d: &[u32], p: u32, p0i: u32, r2: u32, rx: u32) -> u32 {
+  if d.is_empty() {
+    return 0;
+  }
+  let z = zint_mod_small_unsigned(d, p, p0i, r2);
+  modp_sub(z, rx & (d[d.len() - 1] >> 30).wrapping_neg(), p)
+}
+
+/*
+  x + y * s, for s < 2^31: x has one more word than y for the carry
+*/
+pub(super) fn zint_add_mul_small(x: &mut [u32], y: &[u32], s: u32) {
+  let mut cc = 0;
+  for (xw, &yw) in x.iter_mut().zip(y) {
+    let z = yw as u64 * s as u64 + *xw as u64 + cc as u64;
+    *xw = z as u32 & 0x7FFFFFFF;
+    cc = (z >> 31) as u32;
+  }
+  x[y.len()] = cc;
+}
+
+/*
+  Replaces x with x - p if x > p / 2, for an odd p of the same length
+*/
+pub(super) fn zint_norm_zero(x: &mut [u32], p: &[u32]) {
+  // r is -1, 0 or 1 as (p - 1) / 2 is lower than, equal to or greater than x
+  let mut r = 0u32;
+  let mut bb = 0u32;
+  for (&wx, &pw) in x.iter().zip(p).rev() {
+    let wp = (pw >> 1) | (bb << 30);
+    bb = pw & 1;
+
+    let cc = wp.wrapping_sub(wx);
+    let cc = (cc.wrapping_neg() >> 31) | (cc >> 31).wrapping_neg();
+    r |= cc & (r & 1).wrapping_sub(1);
+  }
+
+  zint_sub(x, p, r >> 31);
+}
+
+/*
+  Rebuilds `num` integers from their RNS representation over the first
+  `xlen` small primes, the integer v starting at xx[v * xstride]. With
+  `normalize_signed`, the values are normalized around 0.
+*/
+pub(super) fn zint_rebuild_crt(xx: &mut [u32], xlen: usize, xstride: usize, num: usize, normalize_signed: bool) {
+  // product of the primes processed so far
+  let mut q = vec![0u32; xlen];
+  q[0] = PRIMES[0].p;
+
+  for u in 1..xlen {
+    let p = PRIMES[u].p;
+    let s = PRIMES[u].s;
+    let p0i = modp_ninv31(p);
+    let r2 = modp_r2(p, p0i);
+
+    for v in 0..num {
+      let x = &mut xx[v * xstride..];
+      // (x mod q) + q * (s * (x mod p - (x mod q) mod p) mod p)
+      let xp = x[u];
+      let xq = zint_mod_small_unsigned(&x[..u], p, p0i, r2);
+      let xr = modp_montymul(s, modp_sub(xp, xq, p), p, p0i);
+      zint_add_mul_small(&mut x[..=u], &q[..u], xr);
+    }
+
+    q[u] = zint_mul_small(&mut q[..u], p);
+  }
+
+  if normalize_signed {
+    for v in 0..num {
+      zint_norm_zero(&mut xx[v * xstride..v * xstride + xlen], &q);
+    }
+  }
+}
+
+/*
+  -a if ctl = 1, a if ctl = 0
+*/
+fn zint_negate(a: &mut [u32], ctl: u32) {
+  let mut cc = ctl;
+  let m = ctl.wrapping_neg() >> 1;
+  for w in a.iter_mut() {
+    let aw = (*w ^ m) + cc;
+    *w = aw & 0x7FFFFFFF;
+    cc = aw >> 31;
+  }
+}
+
+/*
+  (a, b) <- ((a * xa + b * xb) / 2^31, (a * ya + b * yb) / 2^31), negating
+  the results which are negative. Bit 0 (resp. 1) of the returned value
+  is set when a (resp. b) was negated.
+*/
+fn zint_co_reduce(a: &mut [u32], b: &mut [u32], xa: i64, xb: i64, ya: i64, yb: i64) -> u32 {
+  let len = a.len();
+  let mut cca = 0i64;
+  let mut ccb = 0i64;
+  for u in 0..len {
+    let (wa, wb) = (a[u] as u64, b[u] as u64);
+    let za = wa
+      .wrapping_mul(xa as u64)
+      .wrapping_add(wb.wrapping_mul(xb as u64))
+      .wrapping_add(cca as u64);
+    let zb = wa
+      .wrapping_mul(ya as u64)
+      .wrapping_add(wb.wrapping_mul(yb as u64))
+      .wrapping_add(ccb as u64);
+    if u > 0 {
+      a[u - 1] = za as u32 & 0x7FFFFFFF;
+      b[u - 1] = zb as u32 & 0x7FFFFFFF;
+    }
+    cca = za as i64 >> 31;
+    ccb = zb as i64 >> 31;
+  }
+  a[len - 1] = cca as u32;
+  b[len - 1] = ccb as u32;
+
+  let nega = (cca as u64 >> 63) as u32;
+  let negb = (ccb as u64 >> 63) as u32;
+  zint_negate(a, nega);
+  zint_negate(b, negb);
+  nega | (negb << 1)
+}
+
+/*
+  a mod m, for an odd m and -m <= a < 0 (neg = 1) or 0 <= a < 2m (neg = 0)
+*/
+fn zint_finish_mod(a: &mut [u32], m: &[u32], neg: u32) {
+  // compare a with m
+  let mut cc = 0u32;
+  for (&aw, &mw) in a.iter().zip(m) {
+    cc = aw.wrapping_sub(mw).wrapping_sub(cc) >> 31;
+  }
+
+  // add m if neg = 1, subtract it if neg = 0 and a >= m
+  let xm = neg.wrapping_neg() >> 1;
+  let ym = (neg | (1 - cc)).wrapping_neg();
+  let mut cc = neg;
+  for (w, &mw) in a.iter_mut().zip(m) {
+    let mw = (mw ^ xm) & ym;
+    let aw = w.wrapping_sub(mw).wrapping_sub(cc);
+    *w = aw & 0x7FFFFFFF;
+    cc = aw >> 31;
+  }
+}
+
+/*
+  (a, b) <- ((a * xa + b * xb) / 2^31 mod m, (a * ya + b * yb) / 2^31 mod m),
+  for an odd m with m0i = -1/m[0] mod 2^31
+*/
+#[allow(clippy::too_many_arguments)]
+fn zint_co_reduce_mod(a: &mut [u32], b: &mut [u32], m: &[u32], m0i: u32, xa: i64, xb: i64, ya: i64, yb: i64) {
+  let len = a.len();
+  let mut cca = 0i64;
+  let mut ccb = 0i64;
+
+  // four combined Montgomery multiplications
+  let fa = (a[0]
+    .wrapping_mul(xa as u32)
+    .wrapping_add(b[0].wrapping_mul(xb as u32)))
+  .wrapping_mul(m0i)
+    & 0x7FFFFFFF;
+  let fb = (a[0]
+    .wrapping_mul(ya as u32)
+    .wrapping_add(b[0].wrapping_mul(yb as u32)))
+  .wrapping_mul(m0i)
+    & 0x7FFFFFFF;
+  for u in 0..len {
+    let (wa, wb, mw) = (a[u] as u64, b[u] as u64, m[u] as u64);
+    let za = wa
+      .wrapping_mul(xa as u64)
+      .wrapping_add(wb.wrapping_mul(xb as u64))
+      .wrapping_add(mw * fa as u64)
+      .wrapping_add(cca as u64);
+    let zb = wa
+      .wrapping_mul(ya as u64)
+      .wrapping_add(wb.wrapping_mul(yb as u64))
+      .wrapping_add(mw * fb as u64)
+      .wrapping_add(ccb as u64);
+    if u > 0 {
+      a[u - 1] = za as u32 & 0x7FFFFFFF;
+      b[u - 1] = zb as u32 & 0x7FFFFFFF;
+    }
+    cca = za as i64 >> 31;
+    ccb = zb as i64 >> 31;
+  }
+  a[len - 1] = cca as u32;
+  b[len - 1] = ccb as u32;
+
+  // -m <= a, b < 2m at this point
+  zint_finish_mod(a, m, (cca as u64 >> 63) as u32);
+  zint_finish_mod(b, m, (ccb as u64 >> 63) as u32);
+}
+
+/*
+  Extended binary GCD of two odd integers x and y of the same length.
+  Returns true if the GCD is 1, in which case x * u - y * v = 1 with
+  0 <= u <= y and 0 <= v <= x.
+*/
+pub(super) fn zint_bezout(u: &mut [u32], v: &mut [u32], x: &[u32], y: &[u32]) -> bool {
+  let len = x.len();
+  if len == 0 {
+    return false;
+  }
+
+  /*
+    Invariants, from a = x, u0 = 1, v0 = 0, b = y, u1 = y, v1 = x - 1:
+      a = x * u0 - y * v0
+      b = x * u1 - y * v1
+    At each step a or b is halved, after subtracting the other one when
+    both are odd. Steps are grouped by 31: the reduction factors are
+    computed from the top and low words of a and b.
+  */
+  let (u0, v0) = (u, v);
+  let mut u1 = y.to_vec();
+  let mut v1 = x.to_vec();
+  let mut a = x.to_vec();
+  let mut b = y.to_vec();
+
+  let x0i = modp_ninv31(x[0]);
+  let y0i = modp_ninv31(y[0]);
+
+  u0.fill(0);
+  u0[0] = 1;
+  v0.fill(0);
+  v1[0] -= 1;
+
+  // the total length is reduced by at least 30 bits at each iteration
+  let mut num = 62 * len as u32 + 30;
+  while num >= 30 {
+    /*
+      Top words of a and b: (a[j] << 31) + a[j - 1] for the highest j >= 1
+      such that a[j] != 0 or b[j] != 0, or a[0] if both are one-word
+    */
+    let mut c0 = u32::MAX;
+    let mut c1 = u32::MAX;
+    let (mut a0, mut a1, mut b0, mut b1) = (0u32, 0u32, 0u32, 0u32);
+    for j in (0..len).rev() {
+      let aw = a[j];
+      let bw = b[j];
+      a0 ^= (a0 ^ aw) & c0;
+      a1 ^= (a1 ^ aw) & c1;
+      b0 ^= (b0 ^ bw) & c0;
+      b1 ^= (b1 ^ bw) & c1;
+      c1 = c0;
+      c0 &= (((aw | bw).wrapping_add(0x7FFFFFFF)) >> 31).wrapping_sub(1);
+    }
+
+    a1 |= a0 & c1;
+    a0 &= !c1;
+    b1 |= b0 & c1;
+    b0 &= !c1;
+    let mut a_hi = ((a0 as u64) << 31) + a1 as u64;
+    let mut b_hi = ((b0 as u64) << 31) + b1 as u64;
+    let mut a_lo = a[0];
+    let mut b_lo = b[0];
+
+    // a' = a * pa + b * pb and b' = a * qa + b * qb, both multiples of 2^31
+    let (mut pa, mut pb, mut qa, mut qb) = (1i64, 0i64, 0i64, 1i64);
+    for i in 0..31 {
+      // rt = 1 if a_hi > b_hi
+      let rz = b_hi.wrapping_sub(a_hi);
+      let rt = ((rz ^ ((a_hi ^ b_hi) & (a_hi ^ rz))) >> 63) as u32;
+
+      // subtract b from a (cab), a from b (cba), and halve a (ca) or b
+      let oa = (a_lo >> i) & 1;
+      let ob = (b_lo >> i) & 1;
+      let cab = oa & ob & rt;
+      let cba = oa & ob & !rt;
+      let ca = cab | (oa ^ 1);
+
+      a_lo = a_lo.wrapping_sub(b_lo & cab.wrapping_neg());
+      a_hi = a_hi.wrapping_sub(b_hi & (cab as u64).wrapping_neg());
+      pa = pa.wrapping_sub(qa & -(cab as i64));
+      pb = pb.wrapping_sub(qb & -(cab as i64));
+      b_lo = b_lo.wrapping_sub(a_lo & cba.wrapping_neg());
+      b_hi = b_hi.wrapping_sub(a_hi & (cba as u64).wrapping_neg());
+      qa = qa.wrapping_sub(pa & -(cba as i64));
+      qb = qb.wrapping_sub(pb & -(cba as i64));
+
+      a_lo = a_lo.wrapping_add(a_lo & ca.wrapping_sub(1));
+      pa = pa.wrapping_add(pa & (ca as i64 - 1));
+      pb = pb.wrapping_add(pb & (ca as i64 - 1));
+      a_hi ^= (a_hi ^ (a_hi >> 1)) & (ca as u64).wrapping_neg();
+      b_lo = b_lo.wrapping_add(b_lo & ca.wrapping_neg());
+      qa = qa.wrapping_add(qa & -(ca as i64));
+      qb = qb.wrapping_add(qb & -(ca as i64));
+      b_hi ^= (b_hi ^ (b_hi >> 1)) & (ca as u64).wrapping_sub(1);
+    }
+
+    // the factors are negated along with a or b when needed
+    let r = zint_co_reduce(&mut a, &mut b, pa, pb, qa, qb);
+    pa = pa.wrapping_sub((pa + pa) & -((r & 1) as i64));
+    pb = pb.wrapping_sub((pb + pb) & -((r & 1) as i64));
+    qa = qa.wrapping_sub((qa + qa) & -((r >> 1) as i64));
+    qb = qb.wrapping_sub((qb + qb) & -((r >> 1) as i64));
+    zint_co_reduce_mod(u0, &mut u1, y, y0i, pa, pb, qa, qb);
+    zint_co_reduce_mod(v0, &mut v1, x, x0i, pa, pb, qa, qb);
+
+    num -= 30;
+  }
+
+  // a is the GCD
+  let mut rc = a[0] ^ 1;
+  for &w in &a[1..] {
+    rc |= w;
+  }
+  (1 - ((rc | rc.wrapping_neg()) >> 31)) & x[0] & y[0] == 1
+}
+
+/*
+  x + k * y * 2^sc, truncated to the length of x, with sc = 31 * sch + scl
+*/
+pub(super) fn zint_add_scaled_mul_small(x: &mut [u32], y: &[u32], k: i32, sch: usize, scl: u32) {
+  let ylen = y.len();
+  if ylen == 0 {
+    return;
+  }
+
+  let ysign = (y[ylen - 1] >> 30).wrapping_neg() >> 1;
+  let mut tw = 0u32;
+  let mut cc = 0i32;
+  for (v, xu) in x.iter_mut().skip(sch).enumerate() {
+    // next word of y, scaled
+    let wy = if v < ylen { y[v] } else { ysign };
+    let wys = ((wy << scl) & 0x7FFFFFFF) | tw;
+    tw = wy >> (31 - scl);
+
+    let z = (wys as i64 * k as i64 + *xu as i64 + cc as i64) as u64;
+    *xu = z as u32 & 0x7FFFFFFF;
+    cc = (z >> 31) as u32 as i32;
+  }
+}
+
+/*
+  x - y * 2^sc, truncated to the length of x, with sc = 31 * sch + scl
+*/
+pub(super) fn zint_sub_scaled(x: &mut [u32], y: &[u32], sch: usize, scl: u32) {
+  let ylen = y.len();
+  if ylen == 0 {
+    return;
+  }
+
+  let ysign = (y[ylen - 1] >> 30).wrapping_neg() >> 1;
+  let mut tw = 0u32;
+  let mut cc = 0u32;
+  for (v, xu) in x.iter_mut().skip(sch).enumerate() {
+    let wy = if v < ylen { y[v] } else { ysign };
+    let wys = ((wy << scl) & 0x7FFFFFFF) | tw;
+    tw = wy >> (31 - scl);
+
+    let w = xu.wrapping_sub(wys).wrapping_sub(cc);
+    *xu = w & 0x7FFFFFFF;
+    cc = w >> 31;
+  }
+}
+
+/*
+  Value of a one-word signed integer
+*/
+pub(super) fn zint_one_to_plain(x: u32) -> i32 {
+  (x | ((x & 0x40000000) << 1)) as i32
+}
@@ -2,14 +2,33 @@
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 #![allow(deref_nullptr)]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 
 mod arith;
+#[cfg(feature = "ffi")]
 mod binder;
+mod error;
+// each backend only uses part of the FFT helpers
+#[cfg(any(feature = "rust-signer", feature = "rust-keygen"))]
+#[cfg_attr(not(all(feature = "rust-signer", feature = "rust-keygen")), allow(dead_code))]
+mod fft;
+#[cfg(feature = "rust-keygen")]
+mod keygen;
 mod param;
 mod shake;
+#[cfg(feature = "rust-signer")]
+mod sign;
 mod structs;
 
 pub use arith::*;
-use binder::*;
+pub use error::FalconError;
 pub use param::*;
+#[cfg(feature = "ffi")]
+pub use shake::shake256_context;
+pub use shake::Shake256;
+// Keccak tables of the in-circuit permutation of falcon-r1cs, not part of the API
+#[doc(hidden)]
+pub use shake::{KECCAK_RC, KECCAK_RHO, SHAKE256_RATE};
 pub use structs::*;
\ No newline at end of file
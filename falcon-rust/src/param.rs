@@ -9,44 +9,192 @@ pub const MODULUS_THRESHOLD: u16 = 61445;
 // Largest multiple of q that is smaller than 2^32
 pub const U32_SAMPLE_THRESHOLD: u32 = 4294956344;
 
+// Bits per s2 coefficient in FALCON_SIG_CT signatures (max_sig_bits for logn 9 and 10)
+pub const SIG_CT_BITS: usize = 12;
+
+// Bits per F and G coefficient in secret keys (max_FG_bits for logn 9 and 10)
+pub const SK_FG_BITS: usize = 8;
+
+// Bound on the squared norm of (g, -f) enforced by key generation, (1.17^2 * q)
+pub const KEY_L2_BOUND: u64 = 16823;
+
+// Signature header, nonce and s2 offsets
+pub const NONCE_LEN: usize = 40;
+pub const SIG_NONCE_OFFSET: usize = 1;
+pub const SIG_VALUE_OFFSET: usize = SIG_NONCE_OFFSET + NONCE_LEN;
+
+// Seed length used by shake256_init_prng_from_system
+pub const KEYGEN_SEED_LEN: usize = 48;
+
+/**
+ * Fixed-size coefficient or byte buffer of a parameter set
+ */
+pub trait Array<T>:
+  Copy + core::fmt::Debug + PartialEq + Eq + AsRef<[T]> + AsMut<[T]> + Send + Sync + 'static
+{
+  const ZERO: Self;
+}
+
+impl<const L: usize> Array<u8> for [u8; L] {
+  const ZERO: Self = [0u8; L];
+}
+
+impl<const L: usize> Array<u16> for [u16; L] {
+  const ZERO: Self = [0u16; L];
+}
+
+impl<const L: usize> Array<i16> for [i16; L] {
+  const ZERO: Self = [0i16; L];
+}
+
+/**
+ * Falcon parameter set: the degree and the sizes that depend on it
+ */
+pub trait FalconParams:
+  Copy + core::fmt::Debug + Default + PartialEq + Eq + Send + Sync + 'static
+{
+  /// `[u16; N]`
+  type Coeffs: Array<u16>;
+  /// `[i16; N]`
+  type SignedCoeffs: Array<i16>;
+  /// `[u8; PK_LEN]`
+  type PublicKeyBytes: Array<u8>;
+  /// `[u8; SK_LEN]`
+  type SecretKeyBytes: Array<u8>;
+  /// `[u8; SIG_MAX_LEN]`
+  type SignatureBytes: Array<u8>;
+
+  const LOG_N: usize;
+  const N: usize;
+  const ONE_OVER_N: u32;
+
+  const PK_LEN: usize;
+  const SK_LEN: usize;
+  // FALCON_SIG_PADDED size
+  const SIG_LEN: usize;
+  const SIG_COMPRESSED_MAX_LEN: usize;
+  const SIG_CT_LEN: usize;
+  const SIG_MAX_LEN: usize;
+  // bits per f and g coefficient in secret keys (max_fg_bits)
+  const SK_fg_BITS: usize;
+
+  const KEYGEN_BUF_LEN: usize;
+  const SIGN_BUF_LEN: usize;
+  const MAKE_PK_BUF_LEN: usize;
+  const VERIFY_BUF_LEN: usize;
+  const EXPAND_SK_BUF_LEN: usize;
+  const SIGN_TREE_BUF_LEN: usize;
+
+  // FALCON_EXPANDEDKEY_SIZE
+  const EXPANDED_SK_LEN: usize;
+
+  const SIG_L2_BOUND: u64;
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Falcon512;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Falcon1024;
+
+/**
+ * Parameter set of the deprecated fixed-degree constants below, selected
+ * with the `falcon-512` (default) or `falcon-1024` feature. Code written
+ * before the parameter set became a type parameter keeps compiling;
+ * `falcon-1024` takes precedence when both features are enabled.
+ */
+#[cfg(not(feature = "falcon-1024"))]
+pub type DefaultParams = Falcon512;
 #[cfg(feature = "falcon-1024")]
-pub use param1024::*;
+pub type DefaultParams = Falcon1024;
+
+#[deprecated(note = "use `FalconParams::LOG_N`, e.g. `Falcon512::LOG_N`")]
+pub const LOG_N: usize = DefaultParams::LOG_N;
+#[deprecated(note = "use `FalconParams::N`, e.g. `Falcon512::N`")]
+pub const N: usize = DefaultParams::N;
+#[deprecated(note = "use `FalconParams::ONE_OVER_N`, e.g. `Falcon512::ONE_OVER_N`")]
+pub const ONE_OVER_N: u32 = DefaultParams::ONE_OVER_N;
+#[deprecated(note = "use `FalconParams::PK_LEN`, e.g. `Falcon512::PK_LEN`")]
+pub const PK_LEN: usize = DefaultParams::PK_LEN;
+#[deprecated(note = "use `FalconParams::SK_LEN`, e.g. `Falcon512::SK_LEN`")]
+pub const SK_LEN: usize = DefaultParams::SK_LEN;
+#[deprecated(note = "use `FalconParams::SIG_LEN`, e.g. `Falcon512::SIG_LEN`")]
+pub const SIG_LEN: usize = DefaultParams::SIG_LEN;
+#[deprecated(note = "use `FalconParams::SIG_L2_BOUND`, e.g. `Falcon512::SIG_L2_BOUND`")]
+pub const SIG_L2_BOUND: u64 = DefaultParams::SIG_L2_BOUND;
 
-#[cfg(feature = "falcon-512")]
-pub use param512::*;
+impl FalconParams for Falcon512 {
+  type Coeffs = [u16; 512];
+  type SignedCoeffs = [i16; 512];
+  type PublicKeyBytes = [u8; 897];
+  type SecretKeyBytes = [u8; 1281];
+  type SignatureBytes = [u8; 809];
 
-mod param512 {
-  #![allow(dead_code)]
-  pub const LOG_N: usize = 9;
-  pub const N: usize = 512;
-  pub const ONE_OVER_N: u32 = 12265;
+  const LOG_N: usize = 9;
+  const N: usize = 512;
+  const ONE_OVER_N: u32 = 12265;
 
-  pub const PK_LEN: usize = 897;
-  pub const SK_LEN: usize = 1281;
-  pub const SIG_LEN: usize = 666;
+  const PK_LEN: usize = 897;
+  const SK_LEN: usize = 1281;
+  const SIG_LEN: usize = 666;
+  const SIG_COMPRESSED_MAX_LEN: usize = 752;
+  const SIG_CT_LEN: usize = 809;
+  const SIG_MAX_LEN: usize = Self::SIG_CT_LEN;
+  const SK_fg_BITS: usize = 6;
 
-  pub const KEYGEN_BUF_LEN: usize = 15879;
-  pub const SIGN_BUF_LEN: usize = 39943;
-  pub const MAKE_PK_BUF_LEN: usize = 3073;
-  pub const VERIFY_BUF_LEN: usize = 4097;
+  const KEYGEN_BUF_LEN: usize = 15879;
+  const SIGN_BUF_LEN: usize = 39943;
+  const MAKE_PK_BUF_LEN: usize = 3073;
+  const VERIFY_BUF_LEN: usize = 4097;
+  const EXPAND_SK_BUF_LEN: usize = 26631;
+  const SIGN_TREE_BUF_LEN: usize = 25607;
 
-  pub const SIG_L2_BOUND: u64 = 34034726;
+  const EXPANDED_SK_LEN: usize = 57352;
+
+  const SIG_L2_BOUND: u64 = 34034726;
 }
 
-mod param1024 {
-  #![allow(dead_code)]
-  pub const LOG_N: usize = 10;
-  pub const N: usize = 1024;
-  pub const ONE_OVER_N: u32 = 12277;
+impl FalconParams for Falcon1024 {
+  type Coeffs = [u16; 1024];
+  type SignedCoeffs = [i16; 1024];
+  type PublicKeyBytes = [u8; 1793];
+  type SecretKeyBytes = [u8; 2305];
+  type SignatureBytes = [u8; 1577];
+
+  const LOG_N: usize = 10;
+  const N: usize = 1024;
+  const ONE_OVER_N: u32 = 12277;
 
-  pub const PK_LEN: usize = 1793;
-  pub const SK_LEN: usize = 2305;
-  pub const SIG_LEN: usize = 1280;
+  const PK_LEN: usize = 1793;
+  const SK_LEN: usize = 2305;
+  const SIG_LEN: usize = 1280;
+  const SIG_COMPRESSED_MAX_LEN: usize = 1462;
+  const SIG_CT_LEN: usize = 1577;
+  const SIG_MAX_LEN: usize = Self::SIG_CT_LEN;
+  const SK_fg_BITS: usize = 5;
 
-  pub const KEYGEN_BUF_LEN: usize = 31751;
-  pub const SIGN_BUF_LEN: usize = 79879;
-  pub const MAKE_PK_BUF_LEN: usize = 6145;
-  pub const VERIFY_BUF_LEN: usize = 8193;
+  const KEYGEN_BUF_LEN: usize = 31751;
+  const SIGN_BUF_LEN: usize = 79879;
+  const MAKE_PK_BUF_LEN: usize = 6145;
+  const VERIFY_BUF_LEN: usize = 8193;
+  const EXPAND_SK_BUF_LEN: usize = 53255;
+  const SIGN_TREE_BUF_LEN: usize = 51207;
 
-  pub const SIG_L2_BOUND: u64 = 70265242;
-}
\ No newline at end of file
+  const EXPANDED_SK_LEN: usize = 122888;
+
+  const SIG_L2_BOUND: u64 = 70265242;
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  #[allow(deprecated)]
+  fn test_deprecated_constants() {
+    let expected = if cfg!(feature = "falcon-1024") { Falcon1024::N } else { Falcon512::N };
+    assert_eq!(N, expected);
+    assert_eq!(1 << LOG_N, N);
+    assert_eq!(PK_LEN, DefaultParams::PK_LEN);
+  }
+}
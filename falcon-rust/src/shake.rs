@@ -1,7 +1,16 @@
+#[cfg(feature = "ffi")]
 pub use crate::binder::shake256_context;
-use crate::binder::*;
+#[cfg(feature = "ffi")]
+use crate::{binder::*, FalconError, KEYGEN_SEED_LEN};
+use alloc::vec::Vec;
+#[cfg(feature = "ffi")]
 use libc::c_void;
+#[cfg(feature = "ffi")]
+use rand_core::{CryptoRng, RngCore};
+#[cfg(feature = "ffi")]
+use zeroize::Zeroize;
 
+#[cfg(feature = "ffi")]
 impl shake256_context {
   pub fn init() -> Self {
     let mut ctx = shake256_context {
@@ -13,6 +22,48 @@ impl shake256_context {
     ctx
   }
 
+  /**
+   * PRNG seeded with `seed`, ready for extraction
+   */
+  pub fn from_seed(seed: &[u8]) -> Self {
+    let mut ctx = shake256_context {
+      opaque_contents: [0u64; 26],
+    };
+    unsafe {
+      shake256_init_prng_from_seed(
+        &mut ctx as *mut shake256_context,
+        seed.as_ptr() as *const c_void,
+        (seed.len() as u64).try_into().unwrap(),
+      );
+    }
+    // the C helper only injects the seed, it does not flip to output mode
+    ctx.finalize();
+    ctx
+  }
+
+  /**
+   * PRNG seeded from the operating system, ready for extraction
+   */
+  pub fn from_system() -> Result<Self, FalconError> {
+    let mut ctx = shake256_context {
+      opaque_contents: [0u64; 26],
+    };
+    FalconError::check(unsafe { shake256_init_prng_from_system(&mut ctx as *mut shake256_context) })?;
+    ctx.finalize();
+    Ok(ctx)
+  }
+
+  /**
+   * PRNG seeded with 48 bytes drawn from `rng`, ready for extraction
+   */
+  pub fn from_rng<R: RngCore + CryptoRng>(rng: &mut R) -> Result<Self, FalconError> {
+    let mut seed = [0u8; KEYGEN_SEED_LEN];
+    rng.try_fill_bytes(&mut seed).map_err(|_| FalconError::Random)?;
+    let ctx = Self::from_seed(&seed);
+    seed.zeroize();
+    Ok(ctx)
+  }
+
   pub fn inject(&mut self, data: &[u8]) {
     unsafe {
       shake256_inject(
@@ -28,11 +79,11 @@ impl shake256_context {
   }
 
   pub fn extract(&mut self, len: usize) -> Vec<u8> {
-    let data = vec![0u8; len];
+    let mut data = vec![0u8; len];
     unsafe {
       shake256_extract(
         self as *mut shake256_context,
-        data.as_ptr() as *mut c_void,
+        data.as_mut_ptr() as *mut c_void,
         (len as u64).try_into().unwrap(),
       );
     }
@@ -40,11 +91,183 @@ impl shake256_context {
   }
 }
 
+/**
+ * Native SHAKE256, with the same init/inject/finalize/extract flow as the
+ * C `shake256_context`
+ */
+#[derive(Clone)]
+pub struct Shake256 {
+  state: [u64; 25],
+  // absorb position while injecting, squeeze position once finalized
+  pos: usize,
+}
+
+/// SHAKE256 rate in bytes: (1600 - 2 * 256) / 8
+pub const SHAKE256_RATE: usize = 136;
+
+/// Keccak-f[1600] round constants, shared with the in-circuit permutation
+pub const KECCAK_RC: [u64; 24] = [
+  0x0000000000000001, 0x0000000000008082, 0x800000000000808A, 0x8000000080008000,
+  0x000000000000808B, 0x0000000080000001, 0x8000000080008081, 0x8000000000008009,
+  0x000000000000008A, 0x0000000000000088, 0x0000000080008009, 0x000000008000000A,
+  0x000000008000808B, 0x800000000000008B, 0x8000000000008089, 0x8000000000008003,
+  0x8000000000008002, 0x8000000000000080, 0x000000000000800A, 0x800000008000000A,
+  0x8000000080008081, 0x8000000000008080, 0x0000000080000001, 0x8000000080008008,
+];
+
+/// Keccak-f[1600] rotation offsets, indexed by x + 5 * y
+pub const KECCAK_RHO: [u32; 25] = [
+  0, 1, 62, 28, 27,
+  36, 44, 6, 55, 20,
+  3, 10, 43, 25, 39,
+  41, 45, 15, 21, 8,
+  18, 2, 61, 56, 14,
+];
+
+impl Shake256 {
+  pub fn init() -> Self {
+    Self {
+      state: [0u64; 25],
+      pos: 0,
+    }
+  }
+
+  pub fn inject(&mut self, data: &[u8]) {
+    for &byte in data {
+      self.xor_byte(self.pos, byte);
+      self.pos += 1;
+      if self.pos == SHAKE256_RATE {
+        keccak_f1600(&mut self.state);
+        self.pos = 0;
+      }
+    }
+  }
+
+  pub fn finalize(&mut self) {
+    self.xor_byte(self.pos, 0x1F);
+    self.xor_byte(SHAKE256_RATE - 1, 0x80);
+    self.pos = SHAKE256_RATE;
+  }
+
+  pub fn extract(&mut self, len: usize) -> Vec<u8> {
+    let mut data = Vec::with_capacity(len);
+    for _ in 0..len {
+      if self.pos == SHAKE256_RATE {
+        keccak_f1600(&mut self.state);
+        self.pos = 0;
+      }
+      data.push((self.state[self.pos / 8] >> (8 * (self.pos % 8))) as u8);
+      self.pos += 1;
+    }
+    data
+  }
+
+  fn xor_byte(&mut self, pos: usize, byte: u8) {
+    self.state[pos / 8] ^= (byte as u64) << (8 * (pos % 8));
+  }
+}
+
+/**
+ * Keccak-f[1600] permutation
+ */
+fn keccak_f1600(state: &mut [u64; 25]) {
+  for rc in KECCAK_RC.iter() {
+    // theta
+    let mut c = [0u64; 5];
+    for (x, e) in c.iter_mut().enumerate() {
+      *e = state[x] ^ state[x + 5] ^ state[x + 10] ^ state[x + 15] ^ state[x + 20];
+    }
+    for x in 0..5 {
+      let d = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
+      for y in 0..5 {
+        state[x + 5 * y] ^= d;
+      }
+    }
+
+    // rho and pi
+    let mut b = [0u64; 25];
+    for x in 0..5 {
+      for y in 0..5 {
+        b[y + 5 * ((2 * x + 3 * y) % 5)] = state[x + 5 * y].rotate_left(KECCAK_RHO[x + 5 * y]);
+      }
+    }
+
+    // chi
+    for x in 0..5 {
+      for y in 0..5 {
+        state[x + 5 * y] = b[x + 5 * y] ^ (!b[(x + 1) % 5 + 5 * y] & b[(x + 2) % 5 + 5 * y]);
+      }
+    }
+
+    // iota
+    state[0] ^= rc;
+  }
+}
+
 #[cfg(test)]
 mod test {
   use super::*;
+
+  #[cfg(feature = "ffi")]
   #[test]
   fn test_prng() {
     let _rng1 = shake256_context::init();
   }
-}
\ No newline at end of file
+
+  #[cfg(feature = "ffi")]
+  #[test]
+  fn test_prng_from_seed() {
+    let a = shake256_context::from_seed(b"seed").extract(64);
+    let b = shake256_context::from_seed(b"seed").extract(64);
+    let c = shake256_context::from_seed(b"other seed").extract(64);
+    assert_eq!(a, b);
+    assert_ne!(a, c);
+
+    let mut expected = shake256_context::init();
+    expected.inject(b"seed");
+    expected.finalize();
+    assert_eq!(a, expected.extract(64));
+
+    let d = shake256_context::from_system().unwrap().extract(64);
+    let e = shake256_context::from_system().unwrap().extract(64);
+    assert_ne!(d, e);
+  }
+
+  #[test]
+  fn test_shake256_native() {
+    let mut shake = Shake256::init();
+    shake.inject(b"abc");
+    shake.finalize();
+    assert_eq!(
+      shake.extract(32),
+      [
+        0x48, 0x33, 0x66, 0x60, 0x13, 0x60, 0xa8, 0x77, 0x1c, 0x68, 0x63, 0x08, 0x0c, 0xc4, 0x11, 0x4d,
+        0x8d, 0xb4, 0x45, 0x30, 0xf8, 0xf1, 0xe1, 0xee, 0x4f, 0x94, 0xea, 0x37, 0xe7, 0x8b, 0x57, 0x39,
+      ]
+    );
+  }
+
+  #[cfg(feature = "ffi")]
+  #[test]
+  fn test_shake256_matches_c() {
+    let data = (0..1000u32).map(|i| (i * 31 + 7) as u8).collect::<Vec<_>>();
+
+    // lengths around the rate, injected and extracted in uneven chunks
+    for len in [0, 1, 135, 136, 137, 272, 500, 1000] {
+      for chunk in [1, 7, 136, 1000] {
+        let mut native = Shake256::init();
+        let mut ffi = shake256_context::init();
+        for part in data[..len].chunks(chunk) {
+          native.inject(part);
+          ffi.inject(part);
+        }
+        native.finalize();
+        ffi.finalize();
+
+        for out_len in [1, 135, 136, 137, 1000] {
+          assert_eq!(native.extract(out_len), ffi.extract(out_len), "len {} chunk {}", len, chunk);
+        }
+      }
+    }
+  }
+}
@@ -0,0 +1,364 @@
+mod prng;
+mod sampler;
+
+use crate::{
+  fft::*, FalconError, FalconParams, NtruBasis, Polynomial, SecretKey, Shake256, Signature,
+  SignatureFormat, SignedPolynomial, KEYGEN_SEED_LEN, MODULUS, NONCE_LEN,
+};
+use prng::Prng;
+use rand_core::{CryptoRng, OsRng, RngCore};
+use sampler::{Sampler, INV_SIGMA};
+use zeroize::Zeroize;
+
+/*
+  Native signing, following `falcon_sign_dyn` and `sign_dyn` in `sign.c`:
+  the basis and its Gram matrix are computed in FFT form for each
+  signature and the short vector is sampled with ffSampling. The nonce and
+  the sampler are seeded from the operating system; with the same seed,
+  signatures are identical to the C library's.
+*/
+
+impl<P: FalconParams> SecretKey<P> {
+  pub fn sign_rust(&self, message: &[u8]) -> Signature<P> {
+    self.try_sign_rust(message).unwrap()
+  }
+
+  pub fn try_sign_rust(&self, message: &[u8]) -> Result<Signature<P>, FalconError> {
+    self.try_sign_rust_with_format(message, SignatureFormat::Padded)
+  }
+
+  pub fn sign_rust_with_format(&self, message: &[u8], format: SignatureFormat) -> Signature<P> {
+    self.try_sign_rust_with_format(message, format).unwrap()
+  }
+
+  pub fn try_sign_rust_with_format(
+    &self,
+    message: &[u8],
+    format: SignatureFormat,
+  ) -> Result<Signature<P>, FalconError> {
+    self.try_sign_rust_with_rng(message, format, &mut OsRng)
+  }
+
+  /**
+   * Same signature as `sign_with_rng` for the same `rng` state: SHAKE256 is
+   * seeded with 48 bytes drawn from `rng`. Only use it with a fresh seed
+   * per message.
+   */
+  pub fn sign_rust_with_rng<R: RngCore + CryptoRng>(
+    &self,
+    message: &[u8],
+    format: SignatureFormat,
+    rng: &mut R,
+  ) -> Signature<P> {
+    self.try_sign_rust_with_rng(message, format, rng).unwrap()
+  }
+
+  pub fn try_sign_rust_with_rng<R: RngCore + CryptoRng>(
+    &self,
+    message: &[u8],
+    format: SignatureFormat,
+    rng: &mut R,
+  ) -> Result<Signature<P>, FalconError> {
+    let mut seed = [0u8; KEYGEN_SEED_LEN];
+    rng.try_fill_bytes(&mut seed).map_err(|_| FalconError::Random)?;
+    let mut shake = Shake256::init();
+    shake.inject(&seed);
+    shake.finalize();
+    seed.zeroize();
+    self.sign_rust_with_shake(message, format, &mut shake)
+  }
+
+  fn sign_rust_with_shake(
+    &self,
+    message: &[u8],
+    format: SignatureFormat,
+    rng: &mut Shake256,
+  ) -> Result<Signature<P>, FalconError> {
+    let basis = self.try_basis()?;
+
+    let nonce = rng.extract(NONCE_LEN);
+    let hm = Polynomial::<P>::from_hash_of_message(message, &nonce);
+
+    loop {
+      let s2 = sign_dyn(rng, &basis, &hm);
+      match Signature::from_polynomial(&nonce, &Polynomial::from(&s2), format) {
+        // the padded format restarts when the compressed s2 does not fit
+        Err(FalconError::Size) if format == SignatureFormat::Padded => continue,
+        res => return res,
+      }
+    }
+  }
+}
+
+/*
+  Samples until (s1, s2) is short enough and returns s2
+*/
+fn sign_dyn<P: FalconParams>(
+  rng: &mut Shake256,
+  basis: &NtruBasis<P>,
+  hm: &Polynomial<P>,
+) -> SignedPolynomial<P> {
+  loop {
+    let mut sampler = Sampler::new(Prng::new(rng), P::LOG_N);
+    if let Some(s2) = do_sign_dyn(&mut sampler, basis, hm) {
+      return s2;
+    }
+  }
+}
+
+/*
+  Lattice basis B = [[g, -f], [G, -F]] in FFT form
+*/
+fn basis_fft<P: FalconParams>(basis: &NtruBasis<P>) -> [Vec<f64>; 4] {
+  let to_fft = |p: &SignedPolynomial<P>, neg: bool| {
+    let mut r: Vec<f64> = p.coeff().iter().map(|&x| x as f64).collect();
+    fft(&mut r);
+    if neg {
+      poly_neg(&mut r);
+    }
+    r
+  };
+  [
+    to_fft(&basis.g, false),
+    to_fft(&basis.f, true),
+    to_fft(&basis.G, false),
+    to_fft(&basis.F, true),
+  ]
+}
+
+fn do_sign_dyn<P: FalconParams>(
+  sampler: &mut Sampler,
+  basis: &NtruBasis<P>,
+  hm: &Polynomial<P>,
+) -> Option<SignedPolynomial<P>> {
+  /*
+    Gram matrix G = B·B*, of which g00, g01 and g11 are kept:
+      g00 = b00*adj(b00) + b01*adj(b01)
+      g01 = b00*adj(b10) + b01*adj(b11)
+      g11 = b10*adj(b10) + b11*adj(b11)
+  */
+  let [b00, b01, b10, b11] = basis_fft(basis);
+
+  let mut g00 = b00.clone();
+  poly_mulselfadj_fft(&mut g00);
+  let mut t = b01.clone();
+  poly_mulselfadj_fft(&mut t);
+  poly_add(&mut g00, &t);
+
+  let mut g01 = b01.clone();
+  poly_muladj_fft(&mut g01, &b11);
+  let mut t = b00.clone();
+  poly_muladj_fft(&mut t, &b10);
+  poly_add(&mut g01, &t);
+
+  let mut g11 = b10.clone();
+  poly_mulselfadj_fft(&mut g11);
+  let mut t = b11.clone();
+  poly_mulselfadj_fft(&mut t);
+  poly_add(&mut g11, &t);
+
+  // target vector [hm, 0] · B^-1 = (hm * -F / q, hm * f / q)
+  let ni = 1.0 / MODULUS as f64;
+  let mut t0: Vec<f64> = hm.coeff().iter().map(|&x| x as f64).collect();
+  fft(&mut t0);
+  let mut t1 = t0.clone();
+  poly_mul_fft(&mut t1, &b01);
+  poly_mulconst(&mut t1, -ni);
+  poly_mul_fft(&mut t0, &b11);
+  poly_mulconst(&mut t0, ni);
+
+  ff_sampling(sampler, &mut t0, &mut t1, &mut g00, &mut g01, &mut g11, P::LOG_N);
+
+  // lattice point (t0, t1) · B, close to the target
+  let mut tx = t0.clone();
+  poly_mul_fft(&mut tx, &b00);
+  let mut ty = t1.clone();
+  poly_mul_fft(&mut ty, &b10);
+  poly_add(&mut tx, &ty);
+  let mut ty = t0.clone();
+  poly_mul_fft(&mut ty, &b01);
+
+  let mut t0 = tx;
+  poly_mul_fft(&mut t1, &b11);
+  poly_add(&mut t1, &ty);
+  ifft(&mut t0);
+  ifft(&mut t1);
+
+  // squared norm, saturated at 2^32 - 1 on overflow
+  let mut sqn = 0u32;
+  let mut ng = 0u32;
+  for (&h, &x) in hm.coeff().iter().zip(t0.iter()) {
+    let z = h as i32 - fpr_rint(x) as i32;
+    sqn = sqn.wrapping_add(z.wrapping_mul(z) as u32);
+    ng |= sqn;
+  }
+
+  let mut s2 = SignedPolynomial::<P>::default();
+  for (s, &x) in s2.0.as_mut().iter_mut().zip(t1.iter()) {
+    *s = -fpr_rint(x) as i16;
+    sqn = sqn.wrapping_add((*s as i32 * *s as i32) as u32);
+    ng |= sqn;
+  }
+  sqn |= (ng >> 31).wrapping_neg();
+
+  for mut v in [b00, b01, b10, b11, g00, g01, g11, t0, t1, ty] {
+    v.zeroize();
+  }
+
+  if sqn as u64 <= P::SIG_L2_BOUND {
+    Some(s2)
+  } else {
+    None
+  }
+}
+
+/*
+  Fast Fourier sampling of (t0, t1) over the Gram matrix
+  [[g00, g01], [adj(g01), g11]], computing the LDL tree on the fly
+  (`ffSampling_fft_dyntree`). The result is written over (t0, t1) and the
+  Gram matrix is consumed.
+*/
+fn ff_sampling(
+  sampler: &mut Sampler,
+  t0: &mut [f64],
+  t1: &mut [f64],
+  g00: &mut [f64],
+  g01: &mut [f64],
+  g11: &mut [f64],
+  orig_logn: usize,
+) {
+  let n = t0.len();
+
+  // leaf: g00 is the squared Gram-Schmidt norm, normalized with sigma
+  if n == 1 {
+    let leaf = g00[0].sqrt() * INV_SIGMA[orig_logn];
+    t0[0] = sampler.sample(t0[0], leaf) as f64;
+    t1[0] = sampler.sample(t1[0], leaf) as f64;
+    return;
+  }
+
+  let hn = n >> 1;
+
+  // g01 becomes l10 and g11 becomes d11; d00 is g00
+  poly_ldl_fft(g00, g01, g11);
+
+  /*
+    d00 and d11 are split into the half-size Gram matrices of the two
+    sub-trees:
+      - left: [[g00_0, g00_1], [adj(g00_1), g00_0]]
+      - right: [[g11_0, g11_1], [adj(g11_1), g11_0]]
+  */
+  let mut tmp = vec![0.0; n];
+  let (tmp0, tmp1) = tmp.split_at_mut(hn);
+  poly_split_fft(tmp0, tmp1, g00);
+  g00.copy_from_slice(&tmp);
+  let (tmp0, tmp1) = tmp.split_at_mut(hn);
+  poly_split_fft(tmp0, tmp1, g11);
+  g11.copy_from_slice(&tmp);
+  let mut l10 = g01.to_vec();
+  g01[..hn].copy_from_slice(&g00[..hn]);
+  g01[hn..].copy_from_slice(&g11[..hn]);
+
+  let (g00_0, g00_1) = g00.split_at_mut(hn);
+  let (g11_0, g11_1) = g11.split_at_mut(hn);
+  let (g01_0, g01_1) = g01.split_at_mut(hn);
+
+  // t1 is sampled first, on the right sub-tree
+  let mut z1 = vec![0.0; n];
+  let (z1_0, z1_1) = z1.split_at_mut(hn);
+  poly_split_fft(z1_0, z1_1, t1);
+  ff_sampling(sampler, z1_0, z1_1, g11_0, g11_1, g01_1, orig_logn);
+  let mut z1_merged = vec![0.0; n];
+  poly_merge_fft(&mut z1_merged, z1_0, z1_1);
+
+  // tb0 = t0 + (t1 - z1) * l10
+  z1.copy_from_slice(t1);
+  poly_sub(&mut z1, &z1_merged);
+  t1.copy_from_slice(&z1_merged);
+  poly_mul_fft(&mut l10, &z1);
+  poly_add(t0, &l10);
+
+  // then tb0, on the left sub-tree
+  let mut z0 = vec![0.0; n];
+  let (z0_0, z0_1) = z0.split_at_mut(hn);
+  poly_split_fft(z0_0, z0_1, t0);
+  ff_sampling(sampler, z0_0, z0_1, g00_0, g00_1, g01_0, orig_logn);
+  poly_merge_fft(t0, z0_0, z0_1);
+
+  for mut v in [tmp, l10, z0, z1, z1_merged] {
+    v.zeroize();
+  }
+}
+
+// keys come from either key generation backend
+#[cfg(all(test, any(feature = "ffi", feature = "rust-keygen")))]
+mod test {
+  #[cfg(feature = "ffi")]
+  use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+  #[cfg(feature = "rust-keygen")]
+  use crate::Signature;
+  use crate::{Falcon1024, Falcon512, FalconParams, KeyPair, SignatureFormat};
+
+  // without the C library: native keys, signatures and verification
+  #[cfg(feature = "rust-keygen")]
+  fn round_trip<P: FalconParams>() {
+    let keypair = KeyPair::<P>::keygen_rust_from_seed(b"rust signer seed");
+    let message = "Testing message";
+
+    for format in [
+      SignatureFormat::Compressed,
+      SignatureFormat::Padded,
+      SignatureFormat::ConstantTime,
+    ] {
+      let sig = keypair.secret_key.sign_rust_with_format(message.as_ref(), format);
+      let sig = Signature::<P>::from_bytes(sig.as_bytes()).unwrap();
+      assert!(keypair.public_key.verify_rust(message.as_ref(), &sig));
+      assert!(!keypair.public_key.verify_rust("Another testing message".as_ref(), &sig));
+    }
+  }
+
+  #[cfg(feature = "rust-keygen")]
+  #[test]
+  fn test_rust_round_trip() {
+    round_trip::<Falcon512>();
+    round_trip::<Falcon1024>();
+  }
+
+  // the signatures are checked against the C library
+  #[cfg(feature = "ffi")]
+  fn cross_check<P: FalconParams>() {
+    let keypair = KeyPair::<P>::keygen_from_seed(b"rust signer seed");
+    let message = "Testing message";
+
+    for format in [
+      SignatureFormat::Compressed,
+      SignatureFormat::Padded,
+      SignatureFormat::ConstantTime,
+    ] {
+      let sig = keypair.secret_key.sign_rust_with_format(message.as_ref(), format);
+      assert!(keypair.public_key.verify(message.as_ref(), &sig));
+      assert!(keypair.public_key.verify_rust(message.as_ref(), &sig));
+      assert!(!keypair.public_key.verify("Another testing message".as_ref(), &sig));
+
+      // every signature draws a fresh nonce
+      let sig2 = keypair.secret_key.sign_rust_with_format(message.as_ref(), format);
+      assert!(keypair.public_key.verify(message.as_ref(), &sig2));
+      assert_ne!(sig2.nonce(), sig.nonce());
+
+      // with a fixed seed, the C library signs identically
+      for seed in [[1u8; 32], [2u8; 32]] {
+        let sig = keypair.secret_key.sign_rust_with_rng(message.as_ref(), format, &mut ChaCha20Rng::from_seed(seed));
+        let c_sig = keypair.secret_key.sign_with_rng(message.as_ref(), format, &mut ChaCha20Rng::from_seed(seed));
+        assert!(keypair.public_key.verify(message.as_ref(), &sig));
+        assert_eq!(sig, c_sig);
+      }
+    }
+  }
+
+  #[cfg(feature = "ffi")]
+  #[test]
+  fn test_rust_signer() {
+    cross_check::<Falcon512>();
+    cross_check::<Falcon1024>();
+  }
+}
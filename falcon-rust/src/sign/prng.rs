@@ -0,0 +1,121 @@
+use crate::Shake256;
+use zeroize::Zeroize;
+
+const CW: [u32; 4] = [0x61707865, 0x3320646e, 0x79622d32, 0x6b206574];
+
+/**
+ * ChaCha20-based PRNG seeded from a SHAKE256 context, as `prng` in
+ * `rng.c`. Bytes are produced 512 at a time, from eight interleaved
+ * ChaCha20 blocks.
+ */
+pub(crate) struct Prng {
+  buf: [u8; 512],
+  ptr: usize,
+  state: [u8; 56],
+}
+
+impl Prng {
+  /**
+   * `src` must be in extraction mode
+   */
+  pub(crate) fn new(src: &mut Shake256) -> Self {
+    let mut state = [0u8; 56];
+    state.copy_from_slice(&src.extract(56));
+
+    let mut prng = Self {
+      buf: [0u8; 512],
+      ptr: 0,
+      state,
+    };
+    prng.refill();
+    prng
+  }
+
+  fn state_word(&self, i: usize) -> u32 {
+    u32::from_le_bytes(self.state[4 * i..4 * i + 4].try_into().unwrap())
+  }
+
+  fn refill(&mut self) {
+    let mut cc = u64::from_le_bytes(self.state[48..56].try_into().unwrap());
+
+    for u in 0..8 {
+      let mut state = [0u32; 16];
+      state[..4].copy_from_slice(&CW);
+      for (v, w) in state[4..].iter_mut().enumerate() {
+        *w = self.state_word(v);
+      }
+      state[14] ^= cc as u32;
+      state[15] ^= (cc >> 32) as u32;
+
+      for _ in 0..10 {
+        quarter_round(&mut state, 0, 4, 8, 12);
+        quarter_round(&mut state, 1, 5, 9, 13);
+        quarter_round(&mut state, 2, 6, 10, 14);
+        quarter_round(&mut state, 3, 7, 11, 15);
+        quarter_round(&mut state, 0, 5, 10, 15);
+        quarter_round(&mut state, 1, 6, 11, 12);
+        quarter_round(&mut state, 2, 7, 8, 13);
+        quarter_round(&mut state, 3, 4, 9, 14);
+      }
+
+      for (w, c) in state[..4].iter_mut().zip(CW) {
+        *w = w.wrapping_add(c);
+      }
+      for (v, w) in state[4..14].iter_mut().enumerate() {
+        *w = w.wrapping_add(self.state_word(v));
+      }
+      state[14] = state[14].wrapping_add(self.state_word(10) ^ cc as u32);
+      state[15] = state[15].wrapping_add(self.state_word(11) ^ (cc >> 32) as u32);
+      cc += 1;
+
+      // word v of block u goes to word u + 8 * v of the buffer
+      for (v, w) in state.iter().enumerate() {
+        let off = (u + (v << 3)) << 2;
+        self.buf[off..off + 4].copy_from_slice(&w.to_le_bytes());
+      }
+      state.zeroize();
+    }
+    self.state[48..56].copy_from_slice(&cc.to_le_bytes());
+    self.ptr = 0;
+  }
+
+  /*
+    Refills early when fewer than 9 bytes remain, dropping them
+  */
+  pub(crate) fn get_u64(&mut self) -> u64 {
+    let mut u = self.ptr;
+    if u >= self.buf.len() - 9 {
+      self.refill();
+      u = 0;
+    }
+    self.ptr = u + 8;
+    u64::from_le_bytes(self.buf[u..u + 8].try_into().unwrap())
+  }
+
+  pub(crate) fn get_u8(&mut self) -> u8 {
+    let v = self.buf[self.ptr];
+    self.ptr += 1;
+    if self.ptr == self.buf.len() {
+      self.refill();
+    }
+    v
+  }
+}
+
+fn quarter_round(state: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+  state[a] = state[a].wrapping_add(state[b]);
+  state[d] = (state[d] ^ state[a]).rotate_left(16);
+  state[c] = state[c].wrapping_add(state[d]);
+  state[b] = (state[b] ^ state[c]).rotate_left(12);
+  state[a] = state[a].wrapping_add(state[b]);
+  state[d] = (state[d] ^ state[a]).rotate_left(8);
+  state[c] = state[c].wrapping_add(state[d]);
+  state[b] = (state[b] ^ state[c]).rotate_left(7);
+}
+
+impl Drop for Prng {
+  fn drop(&mut self) {
+    self.buf.zeroize();
+    self.state.zeroize();
+  }
+}
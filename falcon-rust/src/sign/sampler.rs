@@ -0,0 +1,172 @@
+use super::prng::Prng;
+use crate::fft::fpr_floor;
+use core::f64::consts::{LN_2, LOG2_E};
+
+// 1 / (2 * 1.8205^2)
+#[allow(clippy::excessive_precision)]
+const INV_2SQRSIGMA0: f64 = 0.150865048875372721532312163019;
+
+// sigma_min, indexed by logn
+#[allow(clippy::excessive_precision)]
+pub(crate) const SIGMA_MIN: [f64; 11] = [
+  0.0, /* unused */
+  1.1165085072329102588881898380334015,
+  1.1321247692325272405718031785357108,
+  1.1475285353733668684571123112513188,
+  1.1702540788534828939713084716509250,
+  1.1925466358390344011122170489094133,
+  1.2144300507766139921088487776957699,
+  1.2359260567719808790104525941706723,
+  1.2570545284063214162779743112075080,
+  1.2778336969128335860256340575729042,
+  1.2982803343442918539708792538826807,
+];
+
+// 1 / sigma, indexed by logn
+#[allow(clippy::excessive_precision)]
+pub(crate) const INV_SIGMA: [f64; 11] = [
+  0.0, /* unused */
+  0.0069054793295940891952143765991630516,
+  0.0068102267767177975961393730687908629,
+  0.0067188101910722710707826117910434131,
+  0.0065883354370073665545865037227681924,
+  0.0064651781207602900738053897763485516,
+  0.0063486788828078995327741182928037856,
+  0.0062382586529084374473367528433697537,
+  0.0061334065020930261548984001431770281,
+  0.0060336696681577241031668062510953022,
+  0.0059386453095331159950250124336477482,
+];
+
+// Reversed cumulative distribution of the half-Gaussian, as 72-bit values in three 24-bit limbs
+const DIST: [u32; 54] = [
+  10745844, 3068844, 3741698,
+  5559083, 1580863, 8248194,
+  2260429, 13669192, 2736639,
+  708981, 4421575, 10046180,
+  169348, 7122675, 4136815,
+  30538, 13063405, 7650655,
+  4132, 14505003, 7826148,
+  417, 16768101, 11363290,
+  31, 8444042, 8086568,
+  1, 12844466, 265321,
+  0, 1232676, 13644283,
+  0, 38047, 9111839,
+  0, 870, 6138264,
+  0, 14, 12545723,
+  0, 0, 3104126,
+  0, 0, 28824,
+  0, 0, 198,
+  0, 0, 1,
+];
+
+/**
+ * SamplerZ from `sign.c`: discrete Gaussian sampling by rejection from a
+ * half-Gaussian of standard deviation 1.8205
+ */
+pub(crate) struct Sampler {
+  pub(crate) prng: Prng,
+  sigma_min: f64,
+}
+
+impl Sampler {
+  pub(crate) fn new(prng: Prng, logn: usize) -> Self {
+    Self {
+      prng,
+      sigma_min: SIGMA_MIN[logn],
+    }
+  }
+
+  /*
+    Half-Gaussian centered on zero, with a precision of 72 bits
+  */
+  fn gaussian0(&mut self) -> i32 {
+    let lo = self.prng.get_u64();
+    let hi = self.prng.get_u8() as u32;
+    let v0 = lo as u32 & 0xFFFFFF;
+    let v1 = (lo >> 24) as u32 & 0xFFFFFF;
+    let v2 = (lo >> 48) as u32 | (hi << 16);
+
+    let mut z = 0;
+    for w in DIST.chunks(3) {
+      let cc = v0.wrapping_sub(w[2]) >> 31;
+      let cc = v1.wrapping_sub(w[1]).wrapping_sub(cc) >> 31;
+      let cc = v2.wrapping_sub(w[0]).wrapping_sub(cc) >> 31;
+      z += cc as i32;
+    }
+    z
+  }
+
+  /*
+    Samples a bit with probability ccs * exp(-x), for x >= 0
+  */
+  fn ber_exp(&mut self, x: f64, ccs: f64) -> bool {
+    let s = (x * LOG2_E) as i32;
+    let r = x - s as f64 * LN_2;
+
+    // s is saturated at 63
+    let mut sw = s as u32;
+    sw ^= (sw ^ 63) & (63u32.wrapping_sub(sw) >> 31).wrapping_neg();
+    let s = sw;
+
+    let z = ((expm_p63(r, ccs) << 1).wrapping_sub(1)) >> s;
+
+    let mut i = 64;
+    let mut w;
+    loop {
+      i -= 8;
+      w = (self.prng.get_u8() as u32).wrapping_sub((z >> i) as u32 & 0xFF);
+      if w != 0 || i == 0 {
+        break;
+      }
+    }
+    (w >> 31) != 0
+  }
+
+  /**
+   * Integer following a discrete Gaussian centered on `mu`, with standard
+   * deviation 1 / `isigma`
+   */
+  pub(crate) fn sample(&mut self, mu: f64, isigma: f64) -> i32 {
+    let s = fpr_floor(mu) as i32;
+    let r = mu - s as f64;
+
+    let dss = (isigma * isigma) * 0.5;
+    let ccs = isigma * self.sigma_min;
+
+    loop {
+      let z0 = self.gaussian0();
+      let b = (self.prng.get_u8() & 1) as i32;
+      let z = b + ((b << 1) - 1) * z0;
+
+      let x = (z as f64 - r) * (z as f64 - r) * dss;
+      let x = x - (z0 * z0) as f64 * INV_2SQRSIGMA0;
+      if self.ber_exp(x, ccs) {
+        return s + z;
+      }
+    }
+  }
+}
+
+/*
+  2^63 * ccs * exp(-x), for 0 <= x < log(2), with the FACCT polynomial
+  approximation
+*/
+#[allow(clippy::excessive_precision)]
+fn expm_p63(x: f64, ccs: f64) -> u64 {
+  let mut y = 0.000000002073772366009083061987;
+  y = 0.000000025299506379442070029551 - y * x;
+  y = 0.000000275607356160477811864927 - y * x;
+  y = 0.000002755586350219122514855659 - y * x;
+  y = 0.000024801566833585381209939524 - y * x;
+  y = 0.000198412739277311890541063977 - y * x;
+  y = 0.001388888894063186997887560103 - y * x;
+  y = 0.008333333327800835146903501993 - y * x;
+  y = 0.041666666666110491190622155955 - y * x;
+  y = 0.166666666666984014666397229121 - y * x;
+  y = 0.500000000000019206858326015208 - y * x;
+  y = 0.999999999999994892974086724280 - y * x;
+  y = 1.000000000000000000000000000000 - y * x;
+  y *= ccs;
+  (y * 9223372036854775808.0) as u64
+}
@@ -0,0 +1,183 @@
+use crate::{param::*, FalconError, NTTPolynomial, Polynomial, SignedPolynomial};
+use core::fmt;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use super::{
+    sig::trim_i16_encode,
+    sk::{trim_i8_decode, SK_HEADER},
+    PublicKey, SecretKey,
+};
+
+/**
+ * Secret NTRU basis [[g, -f], [G, -F]] with fG - gF = q. Only f, g and F
+ * are encoded in a secret key, G is recomputed when decoding.
+ *
+ * The basis is zeroized on drop and redacted in `Debug` output.
+ */
+#[derive(Clone)]
+pub struct NtruBasis<P: FalconParams = Falcon512> {
+    pub f: SignedPolynomial<P>,
+    pub g: SignedPolynomial<P>,
+    pub F: SignedPolynomial<P>,
+    pub G: SignedPolynomial<P>,
+}
+
+/*
+  Follows the decoding done by `falcon_make_public` and `falcon_sign_dyn`
+*/
+impl<P: FalconParams> TryFrom<&SecretKey<P>> for NtruBasis<P> {
+    type Error = FalconError;
+
+    fn try_from(sk: &SecretKey<P>) -> Result<Self, Self::Error> {
+        let bytes = sk.as_bytes();
+        let mut offset = 1;
+
+        let (f, len) = trim_i8_decode::<P>(&bytes[offset..], P::SK_fg_BITS)?;
+        offset += len;
+        let (g, len) = trim_i8_decode::<P>(&bytes[offset..], P::SK_fg_BITS)?;
+        offset += len;
+        let (F, len) = trim_i8_decode::<P>(&bytes[offset..], SK_FG_BITS)?;
+        offset += len;
+        if offset != P::SK_LEN {
+            return Err(FalconError::InvalidLength {
+                expected: P::SK_LEN,
+                found: offset,
+            });
+        }
+
+        Self::complete(
+            SignedPolynomial(f),
+            SignedPolynomial(g),
+            SignedPolynomial(F),
+        )
+    }
+}
+
+/*
+  Encodes f, g and F as `falcon_keygen_make`
+*/
+impl<P: FalconParams> TryFrom<&NtruBasis<P>> for SecretKey<P> {
+    type Error = FalconError;
+
+    fn try_from(basis: &NtruBasis<P>) -> Result<Self, Self::Error> {
+        let mut sk = Self(P::SecretKeyBytes::ZERO);
+        let bytes = sk.0.as_mut();
+        bytes[0] = SK_HEADER | P::LOG_N as u8;
+
+        let mut offset = 1;
+        offset += trim_i16_encode(basis.f.coeff(), P::SK_fg_BITS, &mut bytes[offset..])?;
+        offset += trim_i16_encode(basis.g.coeff(), P::SK_fg_BITS, &mut bytes[offset..])?;
+        trim_i16_encode(basis.F.coeff(), SK_FG_BITS, &mut bytes[offset..])?;
+
+        Ok(sk)
+    }
+}
+
+impl<P: FalconParams> NtruBasis<P> {
+    /**
+     * Recomputes G = gF / f mod q, which must fit in 8 signed bits, as
+     * `complete_private` does
+     */
+    pub fn complete(
+        f: SignedPolynomial<P>,
+        g: SignedPolynomial<P>,
+        F: SignedPolynomial<P>,
+    ) -> Result<Self, FalconError> {
+        let gF = Polynomial::from(&g) * Polynomial::from(&F);
+        let G = SignedPolynomial::from(&div_mod_q(&gF, &Polynomial::from(&f))?);
+        if G.coeff().iter().any(|x| !(-127..=127).contains(x)) {
+            return Err(FalconError::Format);
+        }
+
+        Ok(Self { f, g, F, G })
+    }
+
+    /**
+     * Checks fG - gF = q over the integers
+     */
+    pub fn verify_ntru_equation(&self) -> bool {
+        let fG = self.f.mul_over_integers(&self.G);
+        let gF = self.g.mul_over_integers(&self.F);
+
+        fG.iter()
+            .zip(gF.iter())
+            .enumerate()
+            .all(|(i, (x, y))| x - y == if i == 0 { MODULUS as i64 } else { 0 })
+    }
+
+    pub fn public_key(&self) -> PublicKey<P> {
+        self.try_public_key().unwrap()
+    }
+
+    /**
+     * h = g / f mod q
+     */
+    pub fn try_public_key(&self) -> Result<PublicKey<P>, FalconError> {
+        let h = div_mod_q(&Polynomial::from(&self.g), &Polynomial::from(&self.f))?;
+        Ok(PublicKey::from(&h))
+    }
+}
+
+impl<P: FalconParams> SecretKey<P> {
+    pub fn basis(&self) -> NtruBasis<P> {
+        self.try_basis().unwrap()
+    }
+
+    pub fn try_basis(&self) -> Result<NtruBasis<P>, FalconError> {
+        NtruBasis::try_from(self)
+    }
+}
+
+/*
+  a / b mod q, coefficient-wise in NTT form. b must be invertible.
+*/
+pub(crate) fn div_mod_q<P: FalconParams>(
+    a: &Polynomial<P>,
+    b: &Polynomial<P>,
+) -> Result<Polynomial<P>, FalconError> {
+    let mut res = NTTPolynomial::from(a);
+    let b = NTTPolynomial::from(b);
+
+    for (x, &y) in res.0.as_mut().iter_mut().zip(b.coeff()) {
+        if y == 0 {
+            return Err(FalconError::Format);
+        }
+        // y^(q - 2) = 1 / y
+        let mut inv = 1u32;
+        let mut base = y as u32;
+        let mut e = MODULUS as u32 - 2;
+        while e > 0 {
+            if e & 1 == 1 {
+                inv = inv * base % MODULUS as u32;
+            }
+            base = base * base % MODULUS as u32;
+            e >>= 1;
+        }
+        *x = (*x as u32 * inv % MODULUS as u32) as u16;
+    }
+
+    Ok((&res).into())
+}
+
+impl<P: FalconParams> Zeroize for NtruBasis<P> {
+    fn zeroize(&mut self) {
+        self.f.zeroize();
+        self.g.zeroize();
+        self.F.zeroize();
+        self.G.zeroize();
+    }
+}
+
+impl<P: FalconParams> Drop for NtruBasis<P> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl<P: FalconParams> ZeroizeOnDrop for NtruBasis<P> {}
+
+impl<P: FalconParams> fmt::Debug for NtruBasis<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("NtruBasis(..)")
+    }
+}
@@ -0,0 +1,116 @@
+use crate::{binder::*, param::*, FalconError};
+use libc::c_void;
+use std::{fmt, marker::PhantomData};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use super::{SecretKey, Signature, SignatureFormat};
+
+/**
+ * Secret key expanded into its LDL tree (`falcon_expand_privkey`), for
+ * signing many messages with the same key through `falcon_sign_tree`.
+ *
+ * The expanded key is not portable and is zeroized on drop. It is stored
+ * in an 8-byte aligned heap buffer, since its alignment must not change
+ * once it has been computed.
+ */
+#[derive(Clone)]
+pub struct ExpandedSecretKey<P: FalconParams = Falcon512>(pub(crate) Vec<u64>, PhantomData<P>);
+
+impl<P: FalconParams> TryFrom<&SecretKey<P>> for ExpandedSecretKey<P> {
+    type Error = FalconError;
+
+    fn try_from(sk: &SecretKey<P>) -> Result<Self, Self::Error> {
+        let mut expanded_key = vec![0u64; P::EXPANDED_SK_LEN.div_ceil(8)];
+        let mut buf = vec![0u8; P::EXPAND_SK_BUF_LEN];
+
+        let res = unsafe {
+            falcon_expand_privkey(
+                expanded_key.as_mut_ptr() as *mut c_void,
+                (P::EXPANDED_SK_LEN as u64).try_into().unwrap(),
+                sk.0.as_ref().as_ptr() as *const c_void,
+                (P::SK_LEN as u64).try_into().unwrap(),
+                buf.as_mut_ptr() as *mut c_void,
+                (P::EXPAND_SK_BUF_LEN as u64).try_into().unwrap()
+            )
+        };
+        buf.zeroize();
+        if let Err(err) = FalconError::check(res) {
+            expanded_key.zeroize();
+            return Err(err);
+        }
+
+        Ok(Self(expanded_key, PhantomData))
+    }
+}
+
+impl<P: FalconParams> ExpandedSecretKey<P> {
+    pub fn sign(&self, message: &[u8]) -> Signature<P> {
+        self.try_sign(message).unwrap()
+    }
+
+    pub fn try_sign(&self, message: &[u8]) -> Result<Signature<P>, FalconError> {
+        self.try_sign_with_format(message, SignatureFormat::Padded)
+    }
+
+    pub fn sign_with_format(&self, message: &[u8], format: SignatureFormat) -> Signature<P> {
+        self.try_sign_with_format(message, format).unwrap()
+    }
+
+    pub fn try_sign_with_format(
+        &self,
+        message: &[u8],
+        format: SignatureFormat,
+    ) -> Result<Signature<P>, FalconError> {
+        let mut shake256_context = shake256_context::from_system()?;
+        let mut sig = P::SignatureBytes::ZERO;
+        let mut sig_len = format.max_len::<P>();
+        let mut buf = vec![0u8; P::SIGN_TREE_BUF_LEN];
+
+        let res = unsafe {
+            falcon_sign_tree(
+                &mut shake256_context as *mut shake256_context,
+                sig.as_mut().as_mut_ptr() as *mut c_void,
+                &mut sig_len as *mut usize,
+                format.sig_type(),
+                self.0.as_ptr() as *const c_void,
+                message.as_ptr() as *const c_void,
+                (message.len() as u64).try_into().unwrap(),
+                buf.as_mut_ptr() as *mut c_void,
+                (P::SIGN_TREE_BUF_LEN as u64).try_into().unwrap()
+            )
+        };
+        buf.zeroize();
+        shake256_context.opaque_contents.zeroize();
+        FalconError::check(res)?;
+
+        Ok(Signature {
+            format,
+            len: sig_len,
+            bytes: sig,
+        })
+    }
+}
+
+impl<P: FalconParams> SecretKey<P> {
+    pub fn expand(&self) -> ExpandedSecretKey<P> {
+        self.try_expand().unwrap()
+    }
+
+    pub fn try_expand(&self) -> Result<ExpandedSecretKey<P>, FalconError> {
+        ExpandedSecretKey::try_from(self)
+    }
+}
+
+impl<P: FalconParams> Drop for ExpandedSecretKey<P> {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl<P: FalconParams> ZeroizeOnDrop for ExpandedSecretKey<P> {}
+
+impl<P: FalconParams> fmt::Debug for ExpandedSecretKey<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ExpandedSecretKey(..)")
+    }
+}
@@ -1,41 +1,100 @@
-use crate::{binder::*, param::*};
+#[cfg(feature = "ffi")]
+use crate::{binder::*, FalconError};
+use crate::param::*;
+#[cfg(feature = "ffi")]
 use libc::c_void;
-use zeroize::Zeroize;
+#[cfg(feature = "ffi")]
+use rand_core::{CryptoRng, RngCore};
+use subtle::{Choice, ConstantTimeEq};
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 use super::{PublicKey, SecretKey};
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub struct KeyPair {
-    pub public_key: PublicKey,
-    pub secret_key: SecretKey,
+/**
+ * Public and secret key pair. The secret key is zeroized on drop and
+ * compared in constant time.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyPair<P: FalconParams = Falcon512> {
+    pub public_key: PublicKey<P>,
+    pub secret_key: SecretKey<P>,
 }
 
-impl KeyPair {
+#[cfg(feature = "ffi")]
+impl<P: FalconParams> KeyPair<P> {
+    /**
+     * Generates a key pair from system randomness
+     */
     pub fn keygen() -> Self {
-        let mut shake256_context = shake256_context::init();
-        let mut pk = [0u8; PK_LEN];
-        let mut sk = [0u8; SK_LEN];
-        let mut buf = vec![0u8; KEYGEN_BUF_LEN];
-
-        unsafe {
-            assert!(
-                falcon_keygen_make(
-                    &mut shake256_context as *mut shake256_context,
-                    LOG_N as u32,
-                    sk.as_mut_ptr() as *mut c_void,
-                    (SK_LEN as u64).try_into().unwrap(),
-                    pk.as_mut_ptr() as *mut c_void,
-                    (PK_LEN as u64).try_into().unwrap(),
-                    buf.as_mut_ptr() as *mut c_void,
-                    (KEYGEN_BUF_LEN as u64).try_into().unwrap()
-                ) == 0
-            );
-        }
+        Self::try_keygen().unwrap()
+    }
+
+    pub fn try_keygen() -> Result<Self, FalconError> {
+        Self::keygen_from_context(shake256_context::from_system()?)
+    }
+
+    /**
+     * Generates a key pair deterministically: the same seed always yields
+     * the same keys. The seed should hold at least 48 bytes of entropy.
+     */
+    pub fn keygen_from_seed(seed: &[u8]) -> Self {
+        Self::try_keygen_from_seed(seed).unwrap()
+    }
+
+    pub fn try_keygen_from_seed(seed: &[u8]) -> Result<Self, FalconError> {
+        Self::keygen_from_context(shake256_context::from_seed(seed))
+    }
+
+    /**
+     * Generates a key pair from a 48-byte seed drawn from `rng`
+     */
+    pub fn keygen_with_rng<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        Self::try_keygen_with_rng(rng).unwrap()
+    }
+
+    pub fn try_keygen_with_rng<R: RngCore + CryptoRng>(rng: &mut R) -> Result<Self, FalconError> {
+        Self::keygen_from_context(shake256_context::from_rng(rng)?)
+    }
+
+    fn keygen_from_context(mut shake256_context: shake256_context) -> Result<Self, FalconError> {
+        let mut pk = P::PublicKeyBytes::ZERO;
+        let mut sk = SecretKey::<P>(P::SecretKeyBytes::ZERO);
+        let mut buf = vec![0u8; P::KEYGEN_BUF_LEN];
+
+        let res = unsafe {
+            falcon_keygen_make(
+                &mut shake256_context as *mut shake256_context,
+                P::LOG_N as u32,
+                sk.0.as_mut().as_mut_ptr() as *mut c_void,
+                (P::SK_LEN as u64).try_into().unwrap(),
+                pk.as_mut().as_mut_ptr() as *mut c_void,
+                (P::PK_LEN as u64).try_into().unwrap(),
+                buf.as_mut_ptr() as *mut c_void,
+                (P::KEYGEN_BUF_LEN as u64).try_into().unwrap()
+            )
+        };
         buf.zeroize();
+        shake256_context.opaque_contents.zeroize();
+        FalconError::check(res)?;
 
-        Self {
+        Ok(Self {
             public_key: PublicKey(pk),
-            secret_key: SecretKey(sk),
-        }
+            secret_key: sk,
+        })
+    }
+}
+
+impl<P: FalconParams> Zeroize for KeyPair<P> {
+    fn zeroize(&mut self) {
+        self.secret_key.zeroize();
+    }
+}
+
+// the secret key zeroizes itself on drop
+impl<P: FalconParams> ZeroizeOnDrop for KeyPair<P> {}
+
+impl<P: FalconParams> ConstantTimeEq for KeyPair<P> {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.public_key.as_bytes().ct_eq(other.public_key.as_bytes()) & self.secret_key.ct_eq(&other.secret_key)
     }
 }
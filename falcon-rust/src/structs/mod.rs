@@ -1,29 +1,126 @@
+mod basis;
+#[cfg(feature = "ffi")]
+mod esk;
 mod kp;
 mod pk;
 mod sig;
 mod sk;
+#[cfg(feature = "ffi")]
+mod stream;
 
+pub use basis::NtruBasis;
+#[cfg(feature = "rust-keygen")]
+pub(crate) use basis::div_mod_q;
+#[cfg(feature = "ffi")]
+pub use esk::ExpandedSecretKey;
 pub use kp::KeyPair;
 pub use pk::PublicKey;
-pub use sig::Signature;
+pub use sig::{Signature, SignatureFormat};
 pub use sk::SecretKey;
+#[cfg(feature = "ffi")]
+pub use stream::{Signer, Verifier};
 
+use crate::{Falcon1024, Falcon512, FalconError};
 
-#[cfg(test)]
+/*
+  Fixed parameter set entry points: a default type parameter does not drive
+  inference, so `KeyPair::keygen()` needs the parameter set spelled out,
+  while `KeyPair512::keygen()` does not
+*/
+pub type KeyPair512 = KeyPair<Falcon512>;
+pub type KeyPair1024 = KeyPair<Falcon1024>;
+pub type PublicKey512 = PublicKey<Falcon512>;
+pub type PublicKey1024 = PublicKey<Falcon1024>;
+pub type SecretKey512 = SecretKey<Falcon512>;
+pub type SecretKey1024 = SecretKey<Falcon1024>;
+pub type Signature512 = Signature<Falcon512>;
+pub type Signature1024 = Signature<Falcon1024>;
+
+/*
+  Header byte: the high nibble is the object type or format, the low nibble
+  is logn (see `falcon_get_logn`).
+*/
+pub(crate) fn check_header(bytes: &[u8], format: u8, log_n: usize) -> Result<(), FalconError> {
+  let header = *bytes.first().ok_or(FalconError::UnexpectedEnd)?;
+  let logn = header & 0x0F;
+  if !(1..=10).contains(&logn) || header & 0xF0 != format || logn != log_n as u8 {
+    return Err(FalconError::InvalidHeader(header));
+  }
+  Ok(())
+}
+
+pub(crate) fn check_length(bytes: &[u8], expected: usize) -> Result<(), FalconError> {
+  if bytes.len() != expected {
+    return Err(FalconError::InvalidLength {
+      expected,
+      found: bytes.len(),
+    });
+  }
+  Ok(())
+}
+
+
+// the tests generate their keys and signatures with the C library
+#[cfg(all(test, feature = "ffi"))]
 mod test {
   use super::*;
+  use crate::{Falcon1024, Falcon512, FalconError, FalconParams, Polynomial, SignedPolynomial, MODULUS, SIG_VALUE_OFFSET};
+  use pk::PK_HEADER;
+  use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+  use subtle::ConstantTimeEq;
+  use zeroize::Zeroize;
 
-  #[test]
-  fn test_key_gen() {
-    let keypair = KeyPair::keygen();
+  fn key_gen<P: FalconParams>() {
+    let keypair = KeyPair::<P>::keygen();
     let pk2 = keypair.secret_key.make_public_key();
 
     assert_eq!(pk2, keypair.public_key);
+    assert_eq!(keypair.public_key.as_bytes().len(), P::PK_LEN);
+    assert_eq!(keypair.secret_key.as_bytes().len(), P::SK_LEN);
+  }
+
+  #[test]
+  fn test_key_gen() {
+    key_gen::<Falcon512>();
+    key_gen::<Falcon1024>();
+  }
+
+  #[test]
+  fn test_secret_key_hygiene() {
+    let keypair = KeyPair512::keygen_from_seed(b"falcon-rust test seed");
+    let debug = format!("{:?}", keypair);
+    assert!(debug.contains("SecretKey(..)"));
+    assert!(!debug.contains(&format!("{:?}", &keypair.secret_key.as_bytes()[1..8])));
+
+    let other = KeyPair512::keygen_from_seed(b"another seed");
+    assert!(bool::from(keypair.secret_key.ct_eq(&keypair.secret_key.clone())));
+    assert!(!bool::from(keypair.secret_key.ct_eq(&other.secret_key)));
+    assert!(bool::from(keypair.ct_eq(&keypair.clone())));
+    assert_ne!(keypair, other);
+
+    let mut zeroized = keypair.clone();
+    zeroized.zeroize();
+    assert!(zeroized.secret_key.as_bytes().iter().all(|x| *x == 0));
+    assert_eq!(zeroized.public_key, keypair.public_key);
+  }
+
+  #[test]
+  fn test_key_gen_from_seed() {
+    let keypair = KeyPair512::keygen_from_seed(b"falcon-rust test seed");
+    assert_eq!(keypair, KeyPair::keygen_from_seed(b"falcon-rust test seed"));
+    assert_ne!(keypair, KeyPair::keygen_from_seed(b"another seed"));
+    assert_ne!(KeyPair::<Falcon512>::keygen(), KeyPair::keygen());
+    assert_eq!(keypair.secret_key.make_public_key(), keypair.public_key);
+
+    let mut rng1 = ChaCha20Rng::from_seed([1; 32]);
+    let mut rng2 = ChaCha20Rng::from_seed([1; 32]);
+    assert_eq!(KeyPair::<Falcon512>::keygen_with_rng(&mut rng1), KeyPair::keygen_with_rng(&mut rng2));
+    assert_ne!(KeyPair::<Falcon512>::keygen_with_rng(&mut rng1), KeyPair::keygen_with_rng(&mut rng1));
   }
 
   #[test]
   fn test_signed_message() {
-    let keypair = KeyPair::keygen();
+    let keypair = KeyPair512::keygen();
 
     let message = "Testing message";
     let message2 = "Another testing message";
@@ -34,5 +131,342 @@ mod test {
     assert!(keypair.public_key.verify(message.as_ref(), &sig));
     assert!(keypair.public_key.verify_rust(message.as_ref(), &sig));
     assert!(!keypair.public_key.verify(message2.as_ref(), &sig));
+
+    // every signature draws a fresh nonce, unless the seed is fixed
+    let sig2 = keypair.secret_key.sign(message.as_ref());
+    assert!(keypair.public_key.verify(message.as_ref(), &sig2));
+    assert_ne!(sig2.nonce(), sig.nonce());
+
+    let format = SignatureFormat::Padded;
+    let mut rng1 = ChaCha20Rng::from_seed([1; 32]);
+    let mut rng2 = ChaCha20Rng::from_seed([1; 32]);
+    let sig = keypair.secret_key.sign_with_rng(message.as_ref(), format, &mut rng1);
+    assert!(keypair.public_key.verify(message.as_ref(), &sig));
+    assert_eq!(sig, keypair.secret_key.sign_with_rng(message.as_ref(), format, &mut rng2));
+    assert_ne!(sig, keypair.secret_key.sign_with_rng(message.as_ref(), format, &mut rng1));
+  }
+
+  #[test]
+  fn test_errors() {
+    let keypair = KeyPair512::try_keygen().unwrap();
+    let message = "Testing message";
+    let sig = keypair.secret_key.try_sign(message.as_ref()).unwrap();
+
+    assert_eq!(keypair.public_key.try_verify(message.as_ref(), &sig), Ok(()));
+    assert_eq!(
+      keypair.public_key.try_verify("Another testing message".as_ref(), &sig),
+      Err(FalconError::BadSignature)
+    );
+    assert_eq!(
+      keypair.public_key.try_verify_rust("Another testing message".as_ref(), &sig),
+      Err(FalconError::BadSignature)
+    );
+
+    let mut pk = keypair.public_key;
+    pk.0[0] = 0x19;
+    assert_eq!(pk.try_unpack(), Err(FalconError::InvalidHeader(0x19)));
+    assert_eq!(pk.try_verify(message.as_ref(), &sig), Err(FalconError::Format));
+
+    // first coefficient 0x3fff >= q
+    let mut pk = keypair.public_key;
+    pk.0[1] = 0xff;
+    pk.0[2] |= 0xfc;
+    assert_eq!(pk.try_unpack(), Err(FalconError::InvalidCoefficient(0x3fff)));
+
+    let mut sig = sig;
+    sig.bytes[SIG_VALUE_OFFSET..sig.len].iter_mut().for_each(|x| *x = 0);
+    assert_eq!(sig.try_unpack(), Err(FalconError::InvalidCoefficient(2048)));
+    assert!(!keypair.public_key.verify_rust(message.as_ref(), &sig));
+    assert_eq!(sig::comp_decode::<Falcon512>(&[0x00, 0x80]), Err(FalconError::UnexpectedEnd));
+  }
+
+  #[test]
+  fn test_from_bytes() {
+    let keypair = KeyPair512::keygen();
+    let message = "Testing message";
+    let sig = keypair.secret_key.sign(message.as_ref());
+
+    let pk = PublicKey::<Falcon512>::from_bytes(keypair.public_key.as_bytes()).unwrap();
+    let sk = SecretKey::<Falcon512>::try_from(keypair.secret_key.as_bytes()).unwrap();
+    let sig2 = Signature::<Falcon512>::from_bytes(sig.as_bytes()).unwrap();
+    assert_eq!(pk, keypair.public_key);
+    assert_eq!(sk, keypair.secret_key);
+    assert_eq!(sig2, sig);
+    assert!(pk.verify(message.as_ref(), &sig2));
+
+    // object type nibble and logn are checked
+    assert_eq!(
+      PublicKey::<Falcon512>::from_bytes(sig.as_bytes()),
+      Err(FalconError::InvalidHeader(sig.as_bytes()[0]))
+    );
+    assert_eq!(
+      SecretKey::<Falcon512>::from_bytes(keypair.public_key.as_bytes()),
+      Err(FalconError::InvalidHeader(keypair.public_key.as_bytes()[0]))
+    );
+    let mut bytes = sig.as_bytes().to_vec();
+    bytes[0] = 0x3a;
+    assert_eq!(Signature::<Falcon512>::from_bytes(&bytes), Err(FalconError::InvalidHeader(0x3a)));
+    assert_eq!(Signature::<Falcon512>::from_bytes(&[]), Err(FalconError::UnexpectedEnd));
+
+    // keys and signatures of one parameter set are rejected by the other
+    assert_eq!(
+      PublicKey::<Falcon1024>::from_bytes(keypair.public_key.as_bytes()),
+      Err(FalconError::InvalidHeader(keypair.public_key.as_bytes()[0]))
+    );
+    assert_eq!(
+      Signature::<Falcon1024>::from_bytes(sig.as_bytes()),
+      Err(FalconError::InvalidHeader(sig.as_bytes()[0]))
+    );
+
+    assert!(matches!(
+      PublicKey::<Falcon512>::from_bytes(&keypair.public_key.as_bytes()[..100]),
+      Err(FalconError::InvalidLength { .. })
+    ));
+
+    let mut bytes = keypair.public_key.as_bytes().to_vec();
+    bytes[1] = 0xff;
+    bytes[2] |= 0xfc;
+    assert_eq!(PublicKey::<Falcon512>::from_bytes(&bytes), Err(FalconError::InvalidCoefficient(0x3fff)));
+
+    let mut bytes = sig.as_bytes().to_vec();
+    *bytes.last_mut().unwrap() = 1;
+    assert_eq!(Signature::<Falcon512>::from_bytes(&bytes), Err(FalconError::NonCanonicalEncoding));
+
+    // the first f coefficient set to -2^5, then the first F coefficient to
+    // -128: both are forbidden in the trim_i8 encoding
+    let mut bytes = keypair.secret_key.as_bytes().to_vec();
+    bytes[1] = 0x80;
+    assert_eq!(SecretKey::<Falcon512>::from_bytes(&bytes), Err(FalconError::InvalidCoefficient(0x20)));
+    let mut bytes = keypair.secret_key.as_bytes().to_vec();
+    bytes[1 + 2 * Falcon512::N * Falcon512::SK_fg_BITS / 8] = 0x80;
+    assert_eq!(SecretKey::<Falcon512>::from_bytes(&bytes), Err(FalconError::InvalidCoefficient(0x80)));
+  }
+
+  fn signature_formats<P: FalconParams>() {
+    let keypair = KeyPair::<P>::keygen();
+    let message = "Testing message";
+
+    for format in [
+      SignatureFormat::Compressed,
+      SignatureFormat::Padded,
+      SignatureFormat::ConstantTime,
+    ] {
+      let sig = keypair.secret_key.sign_with_format(message.as_ref(), format);
+      assert_eq!(sig.format(), format);
+      assert!(sig.as_bytes().len() <= format.max_len::<P>());
+
+      assert!(keypair.public_key.verify(message.as_ref(), &sig));
+      assert!(keypair.public_key.verify_rust(message.as_ref(), &sig));
+      assert!(!keypair.public_key.verify("Another testing message".as_ref(), &sig));
+      assert!(!keypair.public_key.verify_rust("Another testing message".as_ref(), &sig));
+
+      let sig2 = Signature::<P>::from_bytes(sig.as_bytes()).unwrap();
+      assert_eq!(sig2.unpack(), sig.unpack());
+      assert_eq!(sig2.nonce(), sig.nonce());
+      assert_eq!(Signature::from_bytes_with_format(sig.as_bytes(), format), Ok(sig));
+    }
+
+    let sig = keypair.secret_key.sign_with_format(message.as_ref(), SignatureFormat::Compressed);
+    assert!(sig.as_bytes().len() < SignatureFormat::Padded.max_len::<P>());
+
+    // compressed signatures do not tolerate trailing bytes
+    let mut bytes = sig.as_bytes().to_vec();
+    bytes.push(0);
+    assert_eq!(
+      Signature::<P>::from_bytes_with_format(&bytes, SignatureFormat::Compressed),
+      Err(FalconError::NonCanonicalEncoding)
+    );
+    assert!(Signature::<P>::from_bytes_with_format(sig.as_bytes(), SignatureFormat::ConstantTime).is_err());
+
+    // -2^11 is forbidden in the constant-time encoding
+    let sig = keypair.secret_key.sign_with_format(message.as_ref(), SignatureFormat::ConstantTime);
+    let mut bytes = sig.as_bytes().to_vec();
+    bytes[SIG_VALUE_OFFSET] = 0x80;
+    bytes[SIG_VALUE_OFFSET + 1] &= 0x0f;
+    assert_eq!(Signature::<P>::from_bytes(&bytes), Err(FalconError::InvalidCoefficient(0x800)));
+  }
+
+  #[test]
+  fn test_signature_formats() {
+    signature_formats::<Falcon512>();
+    signature_formats::<Falcon1024>();
+  }
+
+  fn encoders<P: FalconParams>() {
+    let keypair = KeyPair::<P>::keygen_from_seed(b"encoder seed");
+    let message = "Testing message";
+
+    let pk = Polynomial::from(&keypair.public_key);
+    assert_eq!(PublicKey::from(&pk), keypair.public_key);
+    assert_eq!(PublicKey::from(&pk.neg_mod_q().neg_mod_q()), keypair.public_key);
+
+    for format in [
+      SignatureFormat::Compressed,
+      SignatureFormat::Padded,
+      SignatureFormat::ConstantTime,
+    ] {
+      let sig = keypair.secret_key.sign_with_format(message.as_ref(), format);
+      let s2 = Polynomial::from(&sig);
+      assert_eq!(Signature::from_polynomial(sig.nonce(), &s2, format), Ok(sig));
+
+      // a tampered coefficient still encodes, but no longer verifies
+      let mut coeffs = s2.coeff().to_vec();
+      coeffs[0] = (coeffs[0] + 1) % MODULUS;
+      let tampered = Signature::from_polynomial(sig.nonce(), &Polynomial::from_coeffs(&coeffs).unwrap(), format).unwrap();
+      assert_eq!(Polynomial::from(&tampered).coeff(), coeffs);
+      assert!(!keypair.public_key.verify(message.as_ref(), &tampered));
+      assert!(!keypair.public_key.verify_rust(message.as_ref(), &tampered));
+    }
+
+    // coefficients beyond the encodable range
+    let mut coeffs = vec![0u16; P::N];
+    coeffs[0] = 2048;
+    let s2 = Polynomial::<P>::from_coeffs(&coeffs).unwrap();
+    let nonce = [0u8; 40];
+    assert_eq!(
+      Signature::from_polynomial(&nonce, &s2, SignatureFormat::Compressed),
+      Err(FalconError::InvalidCoefficient(2048))
+    );
+    assert_eq!(
+      Signature::from_polynomial(&nonce, &s2, SignatureFormat::ConstantTime),
+      Err(FalconError::InvalidCoefficient(2048))
+    );
+    assert_eq!(
+      Signature::from_polynomial(&nonce[1..], &s2, SignatureFormat::Padded),
+      Err(FalconError::InvalidLength { expected: 40, found: 39 })
+    );
+
+    // too long for the padded format
+    let coeffs = vec![2047u16; P::N];
+    let s2 = Polynomial::<P>::from_coeffs(&coeffs).unwrap();
+    assert_eq!(Signature::from_polynomial(&nonce, &s2, SignatureFormat::Padded), Err(FalconError::Size));
+
+    assert_eq!(
+      Polynomial::<P>::from_coeffs(&coeffs[1..]),
+      Err(FalconError::InvalidLength { expected: P::N, found: P::N - 1 })
+    );
+    let mut coeffs = vec![0u16; P::N];
+    coeffs[1] = MODULUS;
+    assert_eq!(
+      Polynomial::<P>::from_coeffs(&coeffs),
+      Err(FalconError::InvalidCoefficient(MODULUS as u32))
+    );
+  }
+
+  #[test]
+  fn test_encoders() {
+    encoders::<Falcon512>();
+    encoders::<Falcon1024>();
+  }
+
+  fn ntru_basis<P: FalconParams>() {
+    let keypair = KeyPair::<P>::keygen_from_seed(b"basis seed");
+
+    let basis = keypair.secret_key.basis();
+    assert!(basis.verify_ntru_equation());
+    assert_eq!(basis.public_key(), keypair.public_key);
+    assert_eq!(SecretKey::try_from(&basis), Ok(keypair.secret_key.clone()));
+    assert!(basis.f.l2_norm() > 0 && basis.G.l2_norm() > 0);
+
+    let completed = NtruBasis::complete(basis.f, basis.g, basis.F).unwrap();
+    assert_eq!(completed.G, basis.G);
+
+    // a modified F breaks the NTRU equation
+    let mut tampered = basis.clone();
+    let mut coeffs = tampered.F.coeff().to_vec();
+    coeffs[0] += if coeffs[0] < 0 { 1 } else { -1 };
+    tampered.F = SignedPolynomial::from_coeffs(&coeffs).unwrap();
+    assert!(!tampered.verify_ntru_equation());
+    assert_ne!(SecretKey::try_from(&tampered), Ok(keypair.secret_key.clone()));
+
+    // out of range coefficients cannot be encoded
+    let mut coeffs = basis.F.coeff().to_vec();
+    coeffs[0] = 128;
+    tampered.F = SignedPolynomial::from_coeffs(&coeffs).unwrap();
+    assert_eq!(SecretKey::try_from(&tampered), Err(FalconError::InvalidCoefficient(128)));
+
+    // -2^(bits - 1) is forbidden in the trim_i8 encoding
+    let mut sk = keypair.secret_key.clone();
+    sk.0.as_mut()[1] = 0x80;
+    assert_eq!(
+      sk.try_basis().unwrap_err(),
+      FalconError::InvalidCoefficient(1 << (P::SK_fg_BITS - 1))
+    );
+  }
+
+  #[test]
+  fn test_ntru_basis() {
+    ntru_basis::<Falcon512>();
+    ntru_basis::<Falcon1024>();
+  }
+
+  #[test]
+  fn test_expanded_secret_key() {
+    let keypair = KeyPair512::keygen_from_seed(b"falcon-rust test seed");
+    let esk = keypair.secret_key.expand();
+    let message = "Testing message";
+
+    for format in [
+      SignatureFormat::Compressed,
+      SignatureFormat::Padded,
+      SignatureFormat::ConstantTime,
+    ] {
+      let sig = esk.sign_with_format(message.as_ref(), format);
+      assert_eq!(sig.format(), format);
+      assert!(keypair.public_key.verify(message.as_ref(), &sig));
+      assert!(keypair.public_key.verify_rust(message.as_ref(), &sig));
+      assert!(!keypair.public_key.verify("Another testing message".as_ref(), &sig));
+
+      // every signature draws a fresh nonce
+      let sig2 = esk.sign_with_format(message.as_ref(), format);
+      assert!(keypair.public_key.verify(message.as_ref(), &sig2));
+      assert_ne!(sig2.nonce(), sig.nonce());
+      assert_ne!(sig2, sig);
+    }
+
+    let esk2 = ExpandedSecretKey::try_from(&keypair.secret_key).unwrap();
+    assert!(keypair.public_key.verify(message.as_ref(), &esk2.sign(message.as_ref())));
+    assert_eq!(format!("{:?}", esk), "ExpandedSecretKey(..)");
+
+    let mut sk = keypair.secret_key;
+    sk.0[0] = PK_HEADER | Falcon512::LOG_N as u8;
+    assert_eq!(sk.try_expand().err(), Some(FalconError::Format));
+  }
+
+  #[test]
+  fn test_streaming() {
+    let keypair = KeyPair512::keygen_from_seed(b"falcon-rust test seed");
+    let esk = keypair.secret_key.expand();
+    let message = vec![7u8; 10000];
+
+    for format in [
+      SignatureFormat::Compressed,
+      SignatureFormat::Padded,
+      SignatureFormat::ConstantTime,
+    ] {
+      let mut signer = keypair.secret_key.signer().format(format);
+      for chunk in message.chunks(999) {
+        signer.update(chunk);
+      }
+      let sig = signer.finish();
+      assert_eq!(sig.format(), format);
+      assert!(keypair.public_key.verify(&message, &sig));
+
+      let mut signer = esk.signer().format(format);
+      std::io::copy(&mut message.as_slice(), &mut signer).unwrap();
+      let sig2 = signer.finish();
+      assert!(keypair.public_key.verify(&message, &sig2));
+      assert_ne!(sig2.nonce(), sig.nonce());
+
+      let mut verifier = keypair.public_key.verifier(&sig);
+      for chunk in message.chunks(1234) {
+        verifier.update(chunk);
+      }
+      assert!(verifier.finish());
+
+      let mut verifier = Verifier::new(&keypair.public_key, &sig);
+      std::io::copy(&mut message[1..].as_ref(), &mut verifier).unwrap();
+      assert_eq!(verifier.try_finish(), Err(FalconError::BadSignature));
+    }
   }
-}
\ No newline at end of file
+}
@@ -1,70 +1,167 @@
-use super::sig::Signature;
-use crate::{binder::*, param::*, NTTPolynomial, Polynomial};
+use super::{check_header, check_length, sig::Signature};
+#[cfg(feature = "ffi")]
+use crate::binder::*;
+use crate::{param::*, FalconError, NTTPolynomial, Polynomial};
+use alloc::vec::Vec;
+#[cfg(feature = "ffi")]
 use libc::c_void;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub struct PublicKey(pub(crate) [u8; PK_LEN]);
+pub(crate) const PK_HEADER: u8 = 0x00;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PublicKey<P: FalconParams = Falcon512>(pub(crate) P::PublicKeyBytes);
+
+impl<P: FalconParams> TryFrom<&[u8]> for PublicKey<P> {
+  type Error = FalconError;
+
+  fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+    check_header(bytes, PK_HEADER, P::LOG_N)?;
+    check_length(bytes, P::PK_LEN)?;
+    mod_q_decode::<P>(bytes[1..].as_ref())?;
+
+    let mut pk = P::PublicKeyBytes::ZERO;
+    pk.as_mut().copy_from_slice(bytes);
+    Ok(Self(pk))
+  }
+}
+
+impl<P: FalconParams> PublicKey<P> {
+  pub fn from_bytes(bytes: &[u8]) -> Result<Self, FalconError> {
+    Self::try_from(bytes)
+  }
 
-impl PublicKey {
   pub fn as_bytes(&self) -> &[u8] {
     self.0.as_ref()
   }
 
-  pub fn verify(&self, message: &[u8], sig: &Signature) -> bool {
-    let sig_type = 2;
-    let mut buf = [0u8; VERIFY_BUF_LEN];
-
-    let res = unsafe {
-      falcon_verify(
-        sig.0.as_ptr() as *const c_void, 
-        (sig.0.len() as u64).try_into().unwrap(), 
-        sig_type, 
-        self.0.as_ptr() as *const c_void, 
-        (self.0.len() as u64).try_into().unwrap(), 
-        message.as_ptr() as *const c_void, 
-        (message.len() as u64).try_into().unwrap(), 
-        buf.as_mut_ptr() as *mut c_void, 
-        (VERIFY_BUF_LEN as u64).try_into().unwrap(),
-      )
-    };
+  pub fn unpack(&self) -> P::Coeffs {
+    self.try_unpack().unwrap()
+  }
 
-    res == 0
+  pub fn try_unpack(&self) -> Result<P::Coeffs, FalconError> {
+    let bytes = self.0.as_ref();
+    if bytes[0] != P::LOG_N as u8 {
+      return Err(FalconError::InvalidHeader(bytes[0]));
+    }
+    mod_q_decode::<P>(bytes[1..].as_ref())
   }
 
-  pub fn unpack(&self) -> [u16; N] {
-    assert!(self.0[0] == LOG_N as u8);
-    mod_q_decode(self.0[1..].as_ref())
+  pub fn verify_rust(&self, message: &[u8], sig: &Signature<P>) -> bool {
+    self.try_verify_rust(message, sig).is_ok()
   }
 
-  pub fn verify_rust(&self, message: &[u8], sig: &Signature) -> bool {
-    let pk: Polynomial = self.into();
-    let sig_u: Polynomial = sig.into();
-    let hm = Polynomial::from_hash_of_message(message, sig.0[1..41].as_ref());
+  pub fn try_verify_rust(&self, message: &[u8], sig: &Signature<P>) -> Result<(), FalconError> {
+    let pk = Polynomial::<P>(self.try_unpack()?);
+    let sig_u = Polynomial::<P>(sig.try_unpack()?);
+    let hm = Polynomial::<P>::from_hash_of_message(message, sig.nonce());
 
     // compute v = hm - uh
     let uh = sig_u * pk;
     let v = hm - uh;
 
     let l2_norm = sig_u.l2_norm() + v.l2_norm();
-    l2_norm <= SIG_L2_BOUND
+    if l2_norm <= P::SIG_L2_BOUND {
+      Ok(())
+    } else {
+      Err(FalconError::BadSignature)
+    }
+  }
 }
+
+#[cfg(feature = "ffi")]
+impl<P: FalconParams> PublicKey<P> {
+  pub fn verify(&self, message: &[u8], sig: &Signature<P>) -> bool {
+    self.try_verify(message, sig).is_ok()
+  }
+
+  pub fn try_verify(&self, message: &[u8], sig: &Signature<P>) -> Result<(), FalconError> {
+    let mut buf = vec![0u8; P::VERIFY_BUF_LEN];
+
+    let res = unsafe {
+      falcon_verify(
+        sig.as_bytes().as_ptr() as *const c_void, 
+        (sig.as_bytes().len() as u64).try_into().unwrap(), 
+        sig.format().sig_type(), 
+        self.0.as_ref().as_ptr() as *const c_void, 
+        (P::PK_LEN as u64).try_into().unwrap(), 
+        message.as_ptr() as *const c_void, 
+        (message.len() as u64).try_into().unwrap(), 
+        buf.as_mut_ptr() as *mut c_void, 
+        (P::VERIFY_BUF_LEN as u64).try_into().unwrap(),
+      )
+    };
+
+    FalconError::check(res)
+  }
 }
 
-impl From<&PublicKey> for Polynomial {
-  fn from(pk: &PublicKey) -> Self {
+impl<P: FalconParams> From<&PublicKey<P>> for Polynomial<P> {
+  fn from(pk: &PublicKey<P>) -> Self {
     Polynomial(pk.unpack())
   }
 }
 
-impl From<&PublicKey> for NTTPolynomial {
-  fn from(pk: &PublicKey) -> Self {
+impl<P: FalconParams> From<&PublicKey<P>> for NTTPolynomial<P> {
+  fn from(pk: &PublicKey<P>) -> Self {
     (&Polynomial(pk.unpack())).into()
   }
 }
 
-fn mod_q_decode(input: &[u8]) -> [u16; N] {
-  if input.len() != (N * 14 + 7) / 8 {
-    panic!("Incorrect input length")
+/*
+  Encodes h as in `falcon_keygen_make`, after reducing its coefficients
+  into [0, q)
+*/
+impl<P: FalconParams> From<&Polynomial<P>> for PublicKey<P> {
+  fn from(poly: &Polynomial<P>) -> Self {
+    let coeffs: Vec<u16> = poly.coeff().iter().map(|x| x % MODULUS).collect();
+
+    let mut pk = P::PublicKeyBytes::ZERO;
+    pk.as_mut()[0] = PK_HEADER | P::LOG_N as u8;
+    mod_q_encode(&coeffs, pk.as_mut()[1..].as_mut()).unwrap();
+    Self(pk)
+  }
+}
+
+/*
+  Encodes 14-bit coefficients, all below q. Returns the number of bytes
+  written.
+*/
+pub(crate) fn mod_q_encode(input: &[u16], output: &mut [u8]) -> Result<usize, FalconError> {
+  let out_len = (input.len() * 14).div_ceil(8);
+  if output.len() < out_len {
+    return Err(FalconError::Size);
+  }
+
+  let mut acc = 0u32;
+  let mut acc_len = 0;
+  let mut output_ptr = 0;
+
+  for &x in input {
+    if x >= MODULUS {
+      return Err(FalconError::InvalidCoefficient(x as u32));
+    }
+    acc = (acc << 14) | x as u32;
+    acc_len += 14;
+    while acc_len >= 8 {
+      acc_len -= 8;
+      output[output_ptr] = (acc >> acc_len) as u8;
+      output_ptr += 1;
+    }
+  }
+  if acc_len > 0 {
+    output[output_ptr] = (acc << (8 - acc_len)) as u8;
+    output_ptr += 1;
+  }
+
+  Ok(output_ptr)
+}
+
+pub(crate) fn mod_q_decode<P: FalconParams>(input: &[u8]) -> Result<P::Coeffs, FalconError> {
+  if input.len() != (P::N * 14).div_ceil(8) {
+    return Err(FalconError::InvalidLength {
+      expected: (P::N * 14).div_ceil(8),
+      found: input.len(),
+    });
   }
 
   let mut input_pr = 0;
@@ -72,9 +169,9 @@ fn mod_q_decode(input: &[u8]) -> [u16; N] {
   let mut acc_len = 0;
 
   let mut output_ptr = 0;
-  let mut output = [0u16; N];
+  let mut output = P::Coeffs::ZERO;
 
-  while output_ptr < N {
+  while output_ptr < P::N {
     acc = (acc << 8) | (input[input_pr] as u32);
     input_pr += 1;
     acc_len += 8;
@@ -82,15 +179,17 @@ fn mod_q_decode(input: &[u8]) -> [u16; N] {
     if acc_len >= 14 {
       acc_len -= 14;
       let w = (acc >> acc_len) & 0x3FFF;
-      assert!(w < 12289, "Incorrect input {}", w);
-      output[output_ptr] = w as u16;
+      if w >= MODULUS as u32 {
+        return Err(FalconError::InvalidCoefficient(w));
+      }
+      output.as_mut()[output_ptr] = w as u16;
       output_ptr += 1;
     }
   }
 
   if (acc & ((1u32 << acc_len) -1)) != 0 {
-    panic!("Incorrect remaining data")
+    return Err(FalconError::NonCanonicalEncoding);
   }
 
-  output
-}
\ No newline at end of file
+  Ok(output)
+}
@@ -1,50 +1,347 @@
-use crate::{Polynomial, MODULUS, N, SIG_LEN};
+use super::{check_header, check_length};
+#[cfg(feature = "ffi")]
+use crate::binder::*;
+use crate::{
+  Array, Falcon512, FalconError, FalconParams, Polynomial, SignedPolynomial, MODULUS,
+  NONCE_LEN, SIG_CT_BITS, SIG_NONCE_OFFSET, SIG_VALUE_OFFSET,
+};
+
+/**
+ * Signature encodings supported by the C library
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SignatureFormat {
+  /// FALCON_SIG_COMPRESSED: variable length, smallest on average
+  Compressed,
+  /// FALCON_SIG_PADDED: compressed and zero-padded to a fixed length
+  #[default]
+  Padded,
+  /// FALCON_SIG_CT: fixed length, amenable to constant-time decoding
+  ConstantTime,
+}
+
+impl SignatureFormat {
+  #[cfg(feature = "ffi")]
+  pub(crate) fn sig_type(&self) -> i32 {
+    match self {
+      Self::Compressed => FALCON_SIG_COMPRESSED as i32,
+      Self::Padded => FALCON_SIG_PADDED as i32,
+      Self::ConstantTime => FALCON_SIG_CT as i32,
+    }
+  }
+
+  pub(crate) fn header(&self) -> u8 {
+    match self {
+      Self::Compressed | Self::Padded => 0x30,
+      Self::ConstantTime => 0x50,
+    }
+  }
+
+  /// Encoded length, or the maximum length for `Compressed`
+  pub fn max_len<P: FalconParams>(&self) -> usize {
+    match self {
+      Self::Compressed => P::SIG_COMPRESSED_MAX_LEN,
+      Self::Padded => P::SIG_LEN,
+      Self::ConstantTime => P::SIG_CT_LEN,
+    }
+  }
+}
 
 #[derive(Debug, Clone, Copy, PartialEq)]
-pub struct Signature(pub(crate) [u8; SIG_LEN]);
+pub struct Signature<P: FalconParams = Falcon512> {
+  pub(crate) format: SignatureFormat,
+  pub(crate) len: usize,
+  pub(crate) bytes: P::SignatureBytes,
+}
+
+/*
+  The format is detected from the header: 0x50 is `ConstantTime`, 0x30 is
+  `Padded` when the length matches the padded size and `Compressed`
+  otherwise.
+*/
+impl<P: FalconParams> TryFrom<&[u8]> for Signature<P> {
+  type Error = FalconError;
+
+  fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+    let header = *bytes.first().ok_or(FalconError::UnexpectedEnd)?;
+    let format = if header & 0xF0 == SignatureFormat::ConstantTime.header() {
+      SignatureFormat::ConstantTime
+    } else if bytes.len() == P::SIG_LEN {
+      SignatureFormat::Padded
+    } else {
+      SignatureFormat::Compressed
+    };
+
+    Self::from_bytes_with_format(bytes, format)
+  }
+}
+
+impl<P: FalconParams> Signature<P> {
+  pub fn from_bytes(bytes: &[u8]) -> Result<Self, FalconError> {
+    Self::try_from(bytes)
+  }
+
+  pub fn from_bytes_with_format(bytes: &[u8], format: SignatureFormat) -> Result<Self, FalconError> {
+    decode::<P>(bytes, format)?;
+
+    let mut sig = P::SignatureBytes::ZERO;
+    sig.as_mut()[..bytes.len()].copy_from_slice(bytes);
+    Ok(Self {
+      format,
+      len: bytes.len(),
+      bytes: sig,
+    })
+  }
 
-impl Signature {
-  pub fn unpack(&self) -> [u16; N] {
-    let res = comp_decode(self.0[41..].as_ref());
-    res
+  /**
+   * Encodes a nonce and the short polynomial s2 as `falcon_sign_dyn` does.
+   * Coefficients are taken mod q and centered around zero.
+   */
+  pub fn from_polynomial(
+    nonce: &[u8],
+    s2: &Polynomial<P>,
+    format: SignatureFormat,
+  ) -> Result<Self, FalconError> {
+    if nonce.len() != NONCE_LEN {
+      return Err(FalconError::InvalidLength {
+        expected: NONCE_LEN,
+        found: nonce.len(),
+      });
+    }
+
+    let coeffs = SignedPolynomial::from(s2);
+
+    let mut sig = P::SignatureBytes::ZERO;
+    let bytes = sig.as_mut()[..format.max_len::<P>()].as_mut();
+    bytes[0] = format.header() | P::LOG_N as u8;
+    bytes[SIG_NONCE_OFFSET..SIG_VALUE_OFFSET].copy_from_slice(nonce);
+
+    let output = bytes[SIG_VALUE_OFFSET..].as_mut();
+    let len = match format {
+      SignatureFormat::Compressed => SIG_VALUE_OFFSET + comp_encode(coeffs.coeff(), output)?,
+      SignatureFormat::Padded => {
+        comp_encode(coeffs.coeff(), output)?;
+        P::SIG_LEN
+      }
+      SignatureFormat::ConstantTime => {
+        SIG_VALUE_OFFSET + trim_i16_encode(coeffs.coeff(), SIG_CT_BITS, output)?
+      }
+    };
+
+    Ok(Self { format, len, bytes: sig })
+  }
+
+  pub fn as_bytes(&self) -> &[u8] {
+    self.bytes.as_ref()[..self.len].as_ref()
+  }
+
+  pub fn format(&self) -> SignatureFormat {
+    self.format
+  }
+
+  pub fn unpack(&self) -> P::Coeffs {
+    self.try_unpack().unwrap()
+  }
+
+  pub fn try_unpack(&self) -> Result<P::Coeffs, FalconError> {
+    decode::<P>(self.as_bytes(), self.format)
   }
 
   pub fn nonce(&self) -> &[u8] {
-    self.0[1..41].as_ref()
+    self.bytes.as_ref()[SIG_NONCE_OFFSET..SIG_VALUE_OFFSET].as_ref()
+  }
+}
+
+impl<P: FalconParams> From<&Signature<P>> for Polynomial<P> {
+  fn from(sig: &Signature<P>) -> Self {
+    Polynomial(sig.unpack())
   }
 }
 
-impl From<&Signature> for Polynomial {
-  fn from(sig: &Signature) -> Self {
-    let mut res = Self::default();
-    res.0.copy_from_slice(
-      sig.unpack()
-      .iter()
-      .map(|x| *x as u16)
-      .collect::<Vec<u16>>()
-      .as_ref(),
-    );
-    res
+/*
+  Checks the header, the length and the encoding of s2, following
+  `falcon_verify_finish`
+*/
+fn decode<P: FalconParams>(bytes: &[u8], format: SignatureFormat) -> Result<P::Coeffs, FalconError> {
+  check_header(bytes, format.header(), P::LOG_N)?;
+  match format {
+    SignatureFormat::Compressed => {
+      if bytes.len() <= SIG_VALUE_OFFSET || bytes.len() > P::SIG_COMPRESSED_MAX_LEN {
+        return Err(FalconError::InvalidLength {
+          expected: P::SIG_COMPRESSED_MAX_LEN,
+          found: bytes.len(),
+        });
+      }
+    }
+    _ => check_length(bytes, format.max_len::<P>())?,
+  }
+
+  let input = bytes[SIG_VALUE_OFFSET..].as_ref();
+  let (output, len) = match format {
+    SignatureFormat::ConstantTime => trim_i16_decode::<P>(input, SIG_CT_BITS)?,
+    _ => comp_decode::<P>(input)?,
+  };
+
+  // only the padded format tolerates trailing bytes, which must be zero
+  if len != input.len()
+    && (format != SignatureFormat::Padded || input[len..].iter().any(|x| *x != 0))
+  {
+    return Err(FalconError::NonCanonicalEncoding);
   }
+
+  Ok(output)
 }
 
-fn comp_decode(input: &[u8]) -> [u16; N] {
+/*
+  Decodes signed `bits`-bit coefficients and reduces them mod q. Returns the
+  coefficients and the number of bytes read.
+*/
+pub(crate) fn trim_i16_decode<P: FalconParams>(
+  input: &[u8],
+  bits: usize,
+) -> Result<(P::Coeffs, usize), FalconError> {
+  let in_len = (P::N * bits).div_ceil(8);
+  if input.len() < in_len {
+    return Err(FalconError::UnexpectedEnd);
+  }
+
+  let mask1 = (1u32 << bits) - 1;
+  let mask2 = 1u32 << (bits - 1);
+
   let mut input_pt = 0;
   let mut acc = 0u32;
   let mut acc_len = 0;
-  let mut output = [0u16; N];
+  let mut output_ptr = 0;
+  let mut output = P::Coeffs::ZERO;
 
-  for e in output.iter_mut() {
-    acc = (acc << 8) | (input[input_pt] as u32);
+  while output_ptr < P::N {
+    acc = (acc << 8) | input[input_pt] as u32;
     input_pt += 1;
+    acc_len += 8;
+
+    while acc_len >= bits && output_ptr < P::N {
+      acc_len -= bits;
+      let w = (acc >> acc_len) & mask1;
+      // -2^(bits - 1) is forbidden
+      if w == mask2 {
+        return Err(FalconError::InvalidCoefficient(w));
+      }
+      output.as_mut()[output_ptr] = if w & mask2 != 0 {
+        (MODULUS as u32 - ((mask1 + 1) - w)) as u16
+      } else {
+        w as u16
+      };
+      output_ptr += 1;
+    }
+  }
+
+  if (acc & ((1u32 << acc_len) - 1)) != 0 {
+    return Err(FalconError::NonCanonicalEncoding);
+  }
+
+  Ok((output, input_pt))
+}
+
+/*
+  Encodes signed `bits`-bit coefficients, in [-(2^(bits - 1) - 1), 2^(bits - 1) - 1].
+  Returns the number of bytes written.
+*/
+pub(crate) fn trim_i16_encode(input: &[i16], bits: usize, output: &mut [u8]) -> Result<usize, FalconError> {
+  let maxv = (1i16 << (bits - 1)) - 1;
+  if let Some(x) = input.iter().find(|x| **x < -maxv || **x > maxv) {
+    return Err(FalconError::InvalidCoefficient(x.unsigned_abs() as u32));
+  }
+  if output.len() < (input.len() * bits).div_ceil(8) {
+    return Err(FalconError::Size);
+  }
+
+  let mask = (1u32 << bits) - 1;
+  let mut acc = 0u32;
+  let mut acc_len = 0;
+  let mut output_ptr = 0;
+
+  for x in input {
+    acc = (acc << bits) | (*x as u16 as u32 & mask);
+    acc_len += bits;
+    while acc_len >= 8 {
+      acc_len -= 8;
+      output[output_ptr] = (acc >> acc_len) as u8;
+      output_ptr += 1;
+    }
+  }
+  if acc_len > 0 {
+    output[output_ptr] = (acc << (8 - acc_len)) as u8;
+    output_ptr += 1;
+  }
+
+  Ok(output_ptr)
+}
+
+/*
+  Each coefficient is written as a sign bit, its 7 low bits and its high
+  bits in unary. Returns the number of bytes written.
+*/
+pub(crate) fn comp_encode(input: &[i16], output: &mut [u8]) -> Result<usize, FalconError> {
+  if let Some(x) = input.iter().find(|x| **x < -2047 || **x > 2047) {
+    return Err(FalconError::InvalidCoefficient(x.unsigned_abs() as u32));
+  }
+
+  let mut acc = 0u32;
+  let mut acc_len = 0;
+  let mut output_ptr = 0;
+
+  for x in input {
+    acc <<= 1;
+    if *x < 0 {
+      acc |= 1;
+    }
+    let w = x.unsigned_abs() as u32;
+
+    acc = (acc << 7) | (w & 127);
+    acc_len += 8;
+
+    let w = w >> 7;
+    acc = (acc << (w + 1)) | 1;
+    acc_len += w + 1;
+
+    while acc_len >= 8 {
+      acc_len -= 8;
+      *output.get_mut(output_ptr).ok_or(FalconError::Size)? = (acc >> acc_len) as u8;
+      output_ptr += 1;
+    }
+  }
+  if acc_len > 0 {
+    *output.get_mut(output_ptr).ok_or(FalconError::Size)? = (acc << (8 - acc_len)) as u8;
+    output_ptr += 1;
+  }
+
+  Ok(output_ptr)
+}
+
+/*
+  Returns the decoded coefficients and the number of bytes read
+*/
+pub(crate) fn comp_decode<P: FalconParams>(input: &[u8]) -> Result<(P::Coeffs, usize), FalconError> {
+  let mut input_pt = 0;
+  let mut acc = 0u32;
+  let mut acc_len = 0;
+  let mut output = P::Coeffs::ZERO;
+
+  let mut next_byte = || {
+    let b = input.get(input_pt).ok_or(FalconError::UnexpectedEnd);
+    input_pt += 1;
+    b.map(|b| *b as u32)
+  };
+
+  for e in output.as_mut().iter_mut() {
+    acc = (acc << 8) | next_byte()?;
     let b = acc >> acc_len;
     let s = b & 128;
     let mut m = b & 127;
 
     loop {
       if acc_len == 0 {
-        acc = (acc << 8) | (input[input_pt] as u32);
-        input_pt += 1;
+        acc = (acc << 8) | next_byte()?;
         acc_len = 8;
       }
       acc_len -= 1;
@@ -52,11 +349,13 @@ fn comp_decode(input: &[u8]) -> [u16; N] {
         break;
       }
       m += 128;
-      assert!(m < 2048, "Incorrect input: {}", m);
+      if m >= 2048 {
+        return Err(FalconError::InvalidCoefficient(m));
+      }
     }
 
     if s != 0 && m == 0 {
-      panic!("Inocrrect remaining data")
+      return Err(FalconError::NonCanonicalEncoding);
     }
     *e = if s != 0 {
       (MODULUS as u32 - m) as u16
@@ -66,8 +365,8 @@ fn comp_decode(input: &[u8]) -> [u16; N] {
   }
 
   if (acc & ((1 << acc_len) - 1)) != 0 {
-    panic!("Incorrect remaining data")
+    return Err(FalconError::NonCanonicalEncoding);
   }
 
-  output
-}
\ No newline at end of file
+  Ok((output, input_pt))
+}
@@ -1,57 +1,250 @@
-use crate::{binder::*, param::*};
+#[cfg(feature = "ffi")]
+use crate::binder::*;
+use crate::{param::*, FalconError};
+use core::fmt;
+#[cfg(feature = "ffi")]
 use libc::c_void;
-use zeroize::Zeroize;
-
-use super::{PublicKey, Signature};
-
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub struct SecretKey(pub(crate) [u8; SK_LEN]);
-
-impl SecretKey {
-    pub fn make_public_key(&self) -> PublicKey {
-        let mut pk = [0u8; PK_LEN];
-        let mut buf = [0u8; MAKE_PK_BUF_LEN];
-
-        unsafe {
-            assert!(
-                falcon_make_public(
-                    pk.as_mut_ptr() as *mut c_void,
-                    (PK_LEN as u64).try_into().unwrap(),
-                    self.0.as_ptr() as *const c_void,
-                    (SK_LEN as u64).try_into().unwrap(),
-                    buf.as_mut_ptr() as *mut c_void,
-                    (MAKE_PK_BUF_LEN as u64).try_into().unwrap()
-                ) == 0
+#[cfg(feature = "ffi")]
+use rand_core::{CryptoRng, RngCore};
+use subtle::{Choice, ConstantTimeEq};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use super::{check_header, check_length};
+#[cfg(feature = "ffi")]
+use super::{PublicKey, Signature, SignatureFormat};
+
+pub(crate) const SK_HEADER: u8 = 0x50;
+
+/**
+ * Encoded secret key. It is zeroized on drop, redacted in `Debug` output
+ * and compared in constant time.
+ */
+#[derive(Clone)]
+pub struct SecretKey<P: FalconParams = Falcon512>(pub(crate) P::SecretKeyBytes);
+
+impl<P: FalconParams> TryFrom<&[u8]> for SecretKey<P> {
+    type Error = FalconError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        check_header(bytes, SK_HEADER, P::LOG_N)?;
+        check_length(bytes, P::SK_LEN)?;
+        check_encoding::<P>(bytes)?;
+
+        let mut sk = Self(P::SecretKeyBytes::ZERO);
+        sk.0.as_mut().copy_from_slice(bytes);
+        Ok(sk)
+    }
+}
+
+/*
+  f, g and F must decode as `trim_i8_decode` and fill the key exactly; the
+  decoded coefficients are discarded
+*/
+fn check_encoding<P: FalconParams>(bytes: &[u8]) -> Result<(), FalconError> {
+    let mut offset = 1;
+    for bits in [P::SK_fg_BITS, P::SK_fg_BITS, SK_FG_BITS] {
+        let (mut coeffs, len) = trim_i8_decode::<P>(&bytes[offset..], bits)?;
+        coeffs.as_mut().zeroize();
+        offset += len;
+    }
+    check_length(&bytes[..offset], P::SK_LEN)
+}
+
+impl<P: FalconParams> SecretKey<P> {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, FalconError> {
+        Self::try_from(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        self.0.as_ref()
+    }
+}
+
+#[cfg(feature = "ffi")]
+impl<P: FalconParams> SecretKey<P> {
+    pub fn make_public_key(&self) -> PublicKey<P> {
+        self.try_make_public_key().unwrap()
+    }
+
+    pub fn try_make_public_key(&self) -> Result<PublicKey<P>, FalconError> {
+        let mut pk = P::PublicKeyBytes::ZERO;
+        let mut buf = vec![0u8; P::MAKE_PK_BUF_LEN];
+
+        let res = unsafe {
+            falcon_make_public(
+                pk.as_mut().as_mut_ptr() as *mut c_void,
+                (P::PK_LEN as u64).try_into().unwrap(),
+                self.0.as_ref().as_ptr() as *const c_void,
+                (P::SK_LEN as u64).try_into().unwrap(),
+                buf.as_mut_ptr() as *mut c_void,
+                (P::MAKE_PK_BUF_LEN as u64).try_into().unwrap()
             )
-        }
+        };
         buf.zeroize();
-        PublicKey(pk)
-    }
-
-    pub fn sign(&self, message: &[u8]) -> Signature {
-        let mut shake256_context = shake256_context::init();
-        let mut sig = [0u8; SIG_LEN];
-        let sig_len = &mut (SIG_LEN as u64);
-        let sig_type = 2;
-        let mut buf = [0u8; SIGN_BUF_LEN];
-
-        unsafe {
-            assert!(
-                falcon_sign_dyn(
-                    &mut shake256_context as *mut shake256_context,
-                    sig.as_mut_ptr() as *mut c_void,
-                    (sig_len as *mut u64) as *mut usize,
-                    sig_type,
-                    self.0.as_ptr() as *const c_void,
-                    (SK_LEN as u64).try_into().unwrap(),
-                    message.as_ptr() as *const c_void,
-                    (message.len() as u64).try_into().unwrap(),
-                    buf.as_mut_ptr() as *mut c_void,
-                    (SIGN_BUF_LEN as u64).try_into().unwrap()
-                ) == 0
+        FalconError::check(res)?;
+
+        Ok(PublicKey(pk))
+    }
+
+    pub fn sign(&self, message: &[u8]) -> Signature<P> {
+        self.try_sign(message).unwrap()
+    }
+
+    pub fn try_sign(&self, message: &[u8]) -> Result<Signature<P>, FalconError> {
+        self.try_sign_with_format(message, SignatureFormat::Padded)
+    }
+
+    pub fn sign_with_format(&self, message: &[u8], format: SignatureFormat) -> Signature<P> {
+        self.try_sign_with_format(message, format).unwrap()
+    }
+
+    pub fn try_sign_with_format(
+        &self,
+        message: &[u8],
+        format: SignatureFormat,
+    ) -> Result<Signature<P>, FalconError> {
+        self.sign_with_context(message, format, shake256_context::from_system()?)
+    }
+
+    /**
+     * Signs with a nonce and sampler randomness derived from a 48-byte seed
+     * drawn from `rng`: the same seed always yields the same signature.
+     * Only use it with a fresh seed per message.
+     */
+    pub fn sign_with_rng<R: RngCore + CryptoRng>(
+        &self,
+        message: &[u8],
+        format: SignatureFormat,
+        rng: &mut R,
+    ) -> Signature<P> {
+        self.try_sign_with_rng(message, format, rng).unwrap()
+    }
+
+    pub fn try_sign_with_rng<R: RngCore + CryptoRng>(
+        &self,
+        message: &[u8],
+        format: SignatureFormat,
+        rng: &mut R,
+    ) -> Result<Signature<P>, FalconError> {
+        self.sign_with_context(message, format, shake256_context::from_rng(rng)?)
+    }
+
+    fn sign_with_context(
+        &self,
+        message: &[u8],
+        format: SignatureFormat,
+        mut rng: shake256_context,
+    ) -> Result<Signature<P>, FalconError> {
+        let mut sig = P::SignatureBytes::ZERO;
+        let mut sig_len = format.max_len::<P>();
+        let mut buf = vec![0u8; P::SIGN_BUF_LEN];
+
+        let res = unsafe {
+            falcon_sign_dyn(
+                &mut rng as *mut shake256_context,
+                sig.as_mut().as_mut_ptr() as *mut c_void,
+                &mut sig_len as *mut usize,
+                format.sig_type(),
+                self.0.as_ref().as_ptr() as *const c_void,
+                (P::SK_LEN as u64).try_into().unwrap(),
+                message.as_ptr() as *const c_void,
+                (message.len() as u64).try_into().unwrap(),
+                buf.as_mut_ptr() as *mut c_void,
+                (P::SIGN_BUF_LEN as u64).try_into().unwrap()
             )
-        }
+        };
         buf.zeroize();
-        Signature(sig)
+        rng.opaque_contents.zeroize();
+        FalconError::check(res)?;
+
+        Ok(Signature {
+            format,
+            len: sig_len,
+            bytes: sig,
+        })
+    }
+}
+
+impl<P: FalconParams> Zeroize for SecretKey<P> {
+    fn zeroize(&mut self) {
+        self.0.as_mut().zeroize();
+    }
+}
+
+impl<P: FalconParams> Drop for SecretKey<P> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl<P: FalconParams> ZeroizeOnDrop for SecretKey<P> {}
+
+impl<P: FalconParams> ConstantTimeEq for SecretKey<P> {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.as_ref().ct_eq(other.0.as_ref())
+    }
+}
+
+impl<P: FalconParams> PartialEq for SecretKey<P> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl<P: FalconParams> Eq for SecretKey<P> {}
+
+impl<P: FalconParams> fmt::Debug for SecretKey<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretKey(..)")
     }
 }
+
+/*
+  Decodes signed `bits`-bit coefficients, as `trim_i8_decode`. Returns the
+  coefficients and the number of bytes read.
+*/
+pub(crate) fn trim_i8_decode<P: FalconParams>(
+    input: &[u8],
+    bits: usize,
+) -> Result<(P::SignedCoeffs, usize), FalconError> {
+    let in_len = (P::N * bits).div_ceil(8);
+    if input.len() < in_len {
+        return Err(FalconError::UnexpectedEnd);
+    }
+
+    let mask1 = (1u32 << bits) - 1;
+    let mask2 = 1u32 << (bits - 1);
+
+    let mut input_pt = 0;
+    let mut acc = 0u32;
+    let mut acc_len = 0;
+    let mut output_ptr = 0;
+    let mut output = P::SignedCoeffs::ZERO;
+
+    while output_ptr < P::N {
+        acc = (acc << 8) | input[input_pt] as u32;
+        input_pt += 1;
+        acc_len += 8;
+
+        while acc_len >= bits && output_ptr < P::N {
+            acc_len -= bits;
+            let w = (acc >> acc_len) & mask1;
+            // -2^(bits - 1) is forbidden
+            if w == mask2 {
+                return Err(FalconError::InvalidCoefficient(w));
+            }
+            output.as_mut()[output_ptr] = if w & mask2 != 0 {
+                w as i16 - (mask1 + 1) as i16
+            } else {
+                w as i16
+            };
+            output_ptr += 1;
+        }
+    }
+
+    if (acc & ((1u32 << acc_len) - 1)) != 0 {
+        return Err(FalconError::NonCanonicalEncoding);
+    }
+
+    Ok((output, input_pt))
+}
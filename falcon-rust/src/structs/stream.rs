@@ -0,0 +1,242 @@
+use super::{ExpandedSecretKey, PublicKey, SecretKey, Signature, SignatureFormat};
+use crate::{binder::*, param::*, FalconError};
+use libc::c_void;
+use std::io;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+enum SigningKey<'a, P: FalconParams> {
+  Secret(&'a SecretKey<P>),
+  Expanded(&'a ExpandedSecretKey<P>),
+}
+
+/**
+ * Incremental signer: the message is hashed chunk by chunk
+ * (`falcon_sign_start`) and signed by `finish`
+ * (`falcon_sign_dyn_finish` or `falcon_sign_tree_finish`).
+ *
+ * The nonce and the sampler are seeded from system randomness; the sampler
+ * state is zeroized on drop.
+ */
+pub struct Signer<'a, P: FalconParams = Falcon512> {
+  key: SigningKey<'a, P>,
+  format: SignatureFormat,
+  rng: shake256_context,
+  hash_data: shake256_context,
+  nonce: [u8; NONCE_LEN],
+}
+
+impl<'a, P: FalconParams> Signer<'a, P> {
+  pub fn new(sk: &'a SecretKey<P>) -> Self {
+    Self::try_new(sk).unwrap()
+  }
+
+  pub fn try_new(sk: &'a SecretKey<P>) -> Result<Self, FalconError> {
+    Self::start(SigningKey::Secret(sk))
+  }
+
+  pub fn with_expanded_key(esk: &'a ExpandedSecretKey<P>) -> Self {
+    Self::try_with_expanded_key(esk).unwrap()
+  }
+
+  pub fn try_with_expanded_key(esk: &'a ExpandedSecretKey<P>) -> Result<Self, FalconError> {
+    Self::start(SigningKey::Expanded(esk))
+  }
+
+  fn start(key: SigningKey<'a, P>) -> Result<Self, FalconError> {
+    let mut rng = shake256_context::from_system()?;
+    let mut hash_data = shake256_context::init();
+    let mut nonce = [0u8; NONCE_LEN];
+
+    let res = unsafe {
+      falcon_sign_start(
+        &mut rng as *mut shake256_context,
+        nonce.as_mut_ptr() as *mut c_void,
+        &mut hash_data as *mut shake256_context,
+      )
+    };
+    FalconError::check(res)?;
+
+    Ok(Self {
+      key,
+      format: SignatureFormat::default(),
+      rng,
+      hash_data,
+      nonce,
+    })
+  }
+
+  /**
+   * Sets the format of the produced signature (`Padded` by default)
+   */
+  pub fn format(mut self, format: SignatureFormat) -> Self {
+    self.format = format;
+    self
+  }
+
+  pub fn update(&mut self, chunk: &[u8]) {
+    self.hash_data.inject(chunk);
+  }
+
+  pub fn finish(self) -> Signature<P> {
+    self.try_finish().unwrap()
+  }
+
+  pub fn try_finish(mut self) -> Result<Signature<P>, FalconError> {
+    let mut sig = P::SignatureBytes::ZERO;
+    let mut sig_len = self.format.max_len::<P>();
+
+    let res = match &self.key {
+      SigningKey::Secret(sk) => {
+        let mut buf = vec![0u8; P::SIGN_BUF_LEN];
+        let res = unsafe {
+          falcon_sign_dyn_finish(
+            &mut self.rng as *mut shake256_context,
+            sig.as_mut().as_mut_ptr() as *mut c_void,
+            &mut sig_len as *mut usize,
+            self.format.sig_type(),
+            sk.0.as_ref().as_ptr() as *const c_void,
+            (P::SK_LEN as u64).try_into().unwrap(),
+            &mut self.hash_data as *mut shake256_context,
+            self.nonce.as_ptr() as *const c_void,
+            buf.as_mut_ptr() as *mut c_void,
+            (P::SIGN_BUF_LEN as u64).try_into().unwrap(),
+          )
+        };
+        buf.zeroize();
+        res
+      }
+      SigningKey::Expanded(esk) => {
+        let mut buf = vec![0u8; P::SIGN_TREE_BUF_LEN];
+        let res = unsafe {
+          falcon_sign_tree_finish(
+            &mut self.rng as *mut shake256_context,
+            sig.as_mut().as_mut_ptr() as *mut c_void,
+            &mut sig_len as *mut usize,
+            self.format.sig_type(),
+            esk.0.as_ptr() as *const c_void,
+            &mut self.hash_data as *mut shake256_context,
+            self.nonce.as_ptr() as *const c_void,
+            buf.as_mut_ptr() as *mut c_void,
+            (P::SIGN_TREE_BUF_LEN as u64).try_into().unwrap(),
+          )
+        };
+        buf.zeroize();
+        res
+      }
+    };
+    FalconError::check(res)?;
+
+    Ok(Signature {
+      format: self.format,
+      len: sig_len,
+      bytes: sig,
+    })
+  }
+}
+
+impl<P: FalconParams> Drop for Signer<'_, P> {
+  fn drop(&mut self) {
+    self.rng.opaque_contents.zeroize();
+  }
+}
+
+impl<P: FalconParams> ZeroizeOnDrop for Signer<'_, P> {}
+
+impl<P: FalconParams> io::Write for Signer<'_, P> {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    self.update(buf);
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    Ok(())
+  }
+}
+
+/**
+ * Incremental verifier: the message is hashed chunk by chunk, starting
+ * with the signature nonce (`falcon_verify_start`), and checked by `finish`
+ * (`falcon_verify_finish`).
+ */
+pub struct Verifier<'a, P: FalconParams = Falcon512> {
+  pk: &'a PublicKey<P>,
+  sig: &'a Signature<P>,
+  hash_data: shake256_context,
+}
+
+impl<'a, P: FalconParams> Verifier<'a, P> {
+  pub fn new(pk: &'a PublicKey<P>, sig: &'a Signature<P>) -> Self {
+    Self::try_new(pk, sig).unwrap()
+  }
+
+  pub fn try_new(pk: &'a PublicKey<P>, sig: &'a Signature<P>) -> Result<Self, FalconError> {
+    let mut hash_data = shake256_context::init();
+
+    let res = unsafe {
+      falcon_verify_start(
+        &mut hash_data as *mut shake256_context,
+        sig.as_bytes().as_ptr() as *const c_void,
+        (sig.as_bytes().len() as u64).try_into().unwrap(),
+      )
+    };
+    FalconError::check(res)?;
+
+    Ok(Self { pk, sig, hash_data })
+  }
+
+  pub fn update(&mut self, chunk: &[u8]) {
+    self.hash_data.inject(chunk);
+  }
+
+  pub fn finish(self) -> bool {
+    self.try_finish().is_ok()
+  }
+
+  pub fn try_finish(mut self) -> Result<(), FalconError> {
+    let mut buf = vec![0u8; P::VERIFY_BUF_LEN];
+
+    let res = unsafe {
+      falcon_verify_finish(
+        self.sig.as_bytes().as_ptr() as *const c_void,
+        (self.sig.as_bytes().len() as u64).try_into().unwrap(),
+        self.sig.format().sig_type(),
+        self.pk.0.as_ref().as_ptr() as *const c_void,
+        (P::PK_LEN as u64).try_into().unwrap(),
+        &mut self.hash_data as *mut shake256_context,
+        buf.as_mut_ptr() as *mut c_void,
+        (P::VERIFY_BUF_LEN as u64).try_into().unwrap(),
+      )
+    };
+
+    FalconError::check(res)
+  }
+}
+
+impl<P: FalconParams> io::Write for Verifier<'_, P> {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    self.update(buf);
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    Ok(())
+  }
+}
+
+impl<P: FalconParams> SecretKey<P> {
+  pub fn signer(&self) -> Signer<'_, P> {
+    Signer::new(self)
+  }
+}
+
+impl<P: FalconParams> ExpandedSecretKey<P> {
+  pub fn signer(&self) -> Signer<'_, P> {
+    Signer::with_expanded_key(self)
+  }
+}
+
+impl<P: FalconParams> PublicKey<P> {
+  pub fn verifier<'a>(&'a self, sig: &'a Signature<P>) -> Verifier<'a, P> {
+    Verifier::new(self, sig)
+  }
+}